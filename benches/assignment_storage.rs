@@ -0,0 +1,50 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rukki::trio::{Assignment, AssignmentStorage, TrioGroup};
+
+const NODE_CNT: usize = 1_000_000;
+
+//every 100th node is a path boundary, the rest are marked as used by the path search
+fn info(node_id: usize) -> &'static str {
+    if node_id.is_multiple_of(100) {
+        "path_boundary"
+    } else {
+        ""
+    }
+}
+
+fn assignment_storage(c: &mut Criterion) {
+    eprintln!(
+        "Bytes per dense slot: {} (owned info string), {} (interned info)",
+        std::mem::size_of::<Option<(TrioGroup, String, f32)>>(),
+        std::mem::size_of::<Option<Assignment>>()
+    );
+
+    let mut group = c.benchmark_group("dense_assignment_storage");
+    //previous representation with an owned info string per slot, kept as a baseline
+    group.bench_function("owned_info", |b| {
+        b.iter(|| {
+            let mut slots: Vec<Option<(TrioGroup, String, f32)>> = vec![None; NODE_CNT];
+            for (node_id, slot) in slots.iter_mut().enumerate() {
+                black_box(slot.replace((
+                    TrioGroup::MATERNAL,
+                    String::from(info(node_id)),
+                    f32::NAN,
+                )));
+            }
+            black_box(slots.iter().filter(|s| s.is_some()).count())
+        })
+    });
+    group.bench_function("interned_info", |b| {
+        b.iter(|| {
+            let mut storage = AssignmentStorage::new_dense(NODE_CNT);
+            for node_id in 0..NODE_CNT {
+                black_box(storage.assign(node_id, TrioGroup::MATERNAL, info(node_id)));
+            }
+            black_box(storage.assigned().count())
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, assignment_storage);
+criterion_main!(benches);
@@ -0,0 +1,68 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rukki::graph_algos::dfs::{TraversalDirection, VertexSet, DFS};
+use rukki::*;
+use std::collections::HashSet;
+
+//chain of simple bubbles s_i -> {a_i, b_i} -> s_{i+1}, where every 10th junction is long
+fn bubble_chain(n: usize) -> Graph {
+    let mut s = String::new();
+    for i in 0..=n {
+        let len = if i % 10 == 0 { 100_000 } else { 100 };
+        s += &format!("S\ts{i}\t*\tLN:i:{len}\n");
+    }
+    for i in 0..n {
+        for alt in ["a", "b"] {
+            s += &format!("S\t{alt}{i}\t*\tLN:i:100\n");
+            s += &format!("L\ts{i}\t+\t{alt}{i}\t+\t10M\n");
+            s += &format!("L\t{alt}{i}\t+\ts{}\t+\t10M\n", i + 1);
+        }
+    }
+    Graph::read(&s)
+}
+
+//previous HashSet-based visited set, kept as a baseline
+fn hash_set_search(g: &Graph, v: Vertex, node_len_thr: usize) -> usize {
+    let mut visited = HashSet::from([v]);
+    let mut stack = vec![v];
+    while let Some(u) = stack.pop() {
+        for w in g.successors(u) {
+            if g.vertex_length(w) < node_len_thr && visited.insert(w) {
+                stack.push(w);
+            }
+        }
+    }
+    visited.len()
+}
+
+fn local_search(c: &mut Criterion) {
+    let g = bubble_chain(100_000);
+    let node_len_thr = 50_000;
+    let starts: Vec<Vertex> = (0..g.node_cnt()).step_by(97).map(Vertex::forward).collect();
+
+    let mut group = c.benchmark_group("local_search");
+    group.bench_function("hash_set", |b| {
+        b.iter(|| {
+            for &v in &starts {
+                black_box(hash_set_search(&g, v, node_len_thr));
+            }
+        })
+    });
+    group.bench_function("vertex_set_reused", |b| {
+        let mut buffer = VertexSet::for_graph(&g);
+        b.iter(|| {
+            for &v in &starts {
+                let mut dfs = DFS::new(&g, TraversalDirection::FORWARD, None);
+                dfs.set_max_node_len(node_len_thr);
+                buffer.clear();
+                dfs.set_blocked(std::mem::take(&mut buffer));
+                dfs.run_from(v);
+                black_box(dfs.exit_order().len());
+                buffer = dfs.take_blocked();
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, local_search);
+criterion_main!(benches);
@@ -0,0 +1,168 @@
+use crate::graph::*;
+use crate::trio::TrioGroup;
+use crate::PathRecord;
+use log::{info, warn};
+use std::io::{Error, ErrorKind};
+
+//Manual edits of the reported paths. Edit script has one edit per line (tab or space separated):
+//  join <path1> <path2> [<gap_size>] -- connect the end of path1 to the start of path2
+//      (via the graph link or via the gap of specified size, joined path keeps the name of path1)
+//  break <path> <node> -- split the path after (the first occurrence of) the node
+//      (parts get names <path>_1 and <path>_2)
+//Lines starting with '#' are ignored
+#[derive(Debug, PartialEq)]
+pub enum PathEdit {
+    Join {
+        first: String,
+        second: String,
+        gap_size: Option<i64>,
+    },
+    Break {
+        path: String,
+        node: String,
+    },
+}
+
+fn invalid<S: Into<String>>(msg: S) -> Error {
+    Error::new(ErrorKind::InvalidData, msg.into())
+}
+
+pub fn parse_path_edits(edits_str: &str) -> Result<Vec<PathEdit>, Error> {
+    let mut edits = Vec::new();
+    for line in edits_str.lines() {
+        let split: Vec<&str> = line.split_whitespace().collect();
+        if split.is_empty() || split[0].starts_with('#') {
+            continue;
+        }
+        let edit = match split[..] {
+            ["join", first, second] => PathEdit::Join {
+                first: String::from(first),
+                second: String::from(second),
+                gap_size: None,
+            },
+            ["join", first, second, gap_size] => PathEdit::Join {
+                first: String::from(first),
+                second: String::from(second),
+                gap_size: Some(
+                    gap_size
+                        .parse()
+                        .map_err(|_| invalid(format!("Invalid gap size in '{line}'")))?,
+                ),
+            },
+            ["break", path, node] => PathEdit::Break {
+                path: String::from(path),
+                node: String::from(node),
+            },
+            _ => return Err(invalid(format!("Invalid path edit '{line}'"))),
+        };
+        edits.push(edit);
+    }
+    Ok(edits)
+}
+
+fn find_record(records: &[PathRecord], name: &str) -> Result<usize, Error> {
+    records
+        .iter()
+        .position(|r| r.name == name)
+        .ok_or_else(|| invalid(format!("Path {name} not found")))
+}
+
+//applies the edits in order (later edits can refer to paths produced by earlier ones)
+//joins without specified gap size have to correspond to graph links
+pub fn apply_path_edits(
+    g: &Graph,
+    mut records: Vec<PathRecord>,
+    edits: &[PathEdit],
+) -> Result<Vec<PathRecord>, Error> {
+    for edit in edits {
+        match edit {
+            PathEdit::Join {
+                first,
+                second,
+                gap_size,
+            } => {
+                if first == second {
+                    return Err(invalid(format!("Can't join path {first} to itself")));
+                }
+                let i = find_record(&records, first)?;
+                let j = find_record(&records, second)?;
+                let PathRecord {
+                    path: p2,
+                    group: group2,
+                    ..
+                } = records[j].clone();
+                let PathRecord {
+                    path: p1,
+                    group: group1,
+                    unused,
+                    ..
+                } = &mut records[i];
+                if let (Some(x), Some(y)) = (*group1, group2) {
+                    if TrioGroup::incompatible(x, y) {
+                        return Err(invalid(format!(
+                            "Can't join paths {first} and {second} of incompatible groups"
+                        )));
+                    }
+                }
+                if p2.vertices().iter().any(|v| p1.in_path(v.node_id)) {
+                    return Err(invalid(format!(
+                        "Can't join paths {first} and {second} sharing nodes"
+                    )));
+                }
+                let link = match (gap_size, g.connector(p1.end(), p2.start())) {
+                    (None, Some(l)) => GeneralizedLink::LINK(l),
+                    (None, None) => {
+                        return Err(invalid(format!(
+                            "No link between {} and {} (end of {first} and start of {second}), \
+                            specify gap size to join them",
+                            g.v_str(p1.end()),
+                            g.v_str(p2.start())
+                        )))
+                    }
+                    (Some(gap_size), o_l) => {
+                        if o_l.is_some() {
+                            warn!(
+                                "Joining {first} and {second} via gap, although link {} -> {} exists",
+                                g.v_str(p1.end()),
+                                g.v_str(p2.start())
+                            );
+                        }
+                        GeneralizedLink::GAP(GapInfo {
+                            start: p1.end(),
+                            end: p2.start(),
+                            gap_size: *gap_size,
+                            info: String::from("manual"),
+                        })
+                    }
+                };
+                p1.append_general(link);
+                p1.merge_in(p2);
+                *group1 = TrioGroup::optional_blend(*group1, group2);
+                //no longer a trivial path
+                *unused = false;
+                records.remove(j);
+                info!("Joined {first} and {second}");
+            }
+            PathEdit::Break { path, node } => {
+                let i = find_record(&records, path)?;
+                let PathRecord { path: p, group, .. } = &records[i];
+                let node_id = g
+                    .try_name2id(node)
+                    .ok_or_else(|| invalid(format!("Node {node} is not in the graph")))?;
+                let idx = p
+                    .vertices()
+                    .iter()
+                    .position(|v| v.node_id == node_id)
+                    .ok_or_else(|| invalid(format!("Node {node} is not in path {path}")))?;
+                let (prefix, suffix) = p.split_after(idx).ok_or_else(|| {
+                    invalid(format!("Node {node} is the last node of path {path}"))
+                })?;
+                let group = *group;
+                records[i] = PathRecord::new(format!("{path}_1"), prefix, group);
+                records.insert(i + 1, PathRecord::new(format!("{path}_2"), suffix, group));
+                info!("Split {path} after {node}");
+            }
+        }
+    }
+    Ok(records)
+}
@@ -1,6 +1,10 @@
-use log::warn;
-use std::collections::HashMap;
+use itertools::Itertools;
+use log::{info, warn};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::io::{BufRead, Cursor, Write};
 use std::str;
+use std::sync::OnceLock;
 
 #[derive(Copy, Clone, Debug, PartialEq, PartialOrd, Eq, Ord, Hash)]
 pub enum Direction {
@@ -16,19 +20,14 @@ impl Direction {
         }
     }
 
-    fn parse_char(c: char) -> Direction {
-        match c {
-            '+' => Self::FORWARD,
-            '-' => Self::REVERSE,
-            _ => panic!("Unknown direction {c}"),
+    fn try_parse(s: &str) -> Option<Direction> {
+        match s {
+            "+" => Some(Self::FORWARD),
+            "-" => Some(Self::REVERSE),
+            _ => None,
         }
     }
 
-    fn parse(s: &str) -> Direction {
-        assert!(s.len() == 1, "Unknown direction {s}");
-        Self::parse_char(s.chars().next().unwrap())
-    }
-
     pub fn str(d: Direction) -> &'static str {
         match d {
             Self::FORWARD => "+",
@@ -52,12 +51,176 @@ impl Direction {
     }
 }
 
+#[derive(Debug)]
+pub enum ParseErrorKind {
+    Io(std::io::Error),
+    MissingField(&'static str),
+    MissingLength(String),
+    ZeroLength(String),
+    NonAsciiSequence(String),
+    DuplicateSegment(String),
+    UnknownSegment(String),
+    InvalidDirection(String),
+    InvalidTag(String),
+    //overlap size, start and end vertices
+    TooLongOverlap(usize, String, String),
+    //path name and the problem description
+    InvalidPath(String, String),
+}
+
+impl fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "{e}"),
+            Self::MissingField(field) => write!(f, "missing {field} field"),
+            Self::MissingLength(name) => {
+                write!(
+                    f,
+                    "neither sequence nor LN tag provided for segment '{name}'"
+                )
+            }
+            Self::ZeroLength(name) => write!(f, "zero length segment '{name}'"),
+            Self::NonAsciiSequence(name) => {
+                write!(f, "non-ASCII characters in sequence of segment '{name}'")
+            }
+            Self::DuplicateSegment(name) => write!(f, "duplicate segment '{name}'"),
+            Self::UnknownSegment(name) => write!(f, "link references unknown segment '{name}'"),
+            Self::InvalidDirection(d) => write!(f, "invalid orientation '{d}'"),
+            Self::InvalidTag(tag) => write!(f, "couldn't parse tag '{tag}'"),
+            Self::TooLongOverlap(overlap, start, end) => write!(
+                f,
+                "invalid (too long) overlap of size {overlap} between {start} and {end}"
+            ),
+            Self::InvalidPath(name, problem) => write!(f, "invalid path '{name}': {problem}"),
+        }
+    }
+}
+
+//GFA parsing error with (1-based) number of the problematic line
+#[derive(Debug)]
+pub struct ParseError {
+    pub line: usize,
+    pub kind: ParseErrorKind,
+}
+
+impl ParseError {
+    fn new(line: usize, kind: ParseErrorKind) -> Self {
+        ParseError { line, kind }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.kind)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+//value of an optional GFA tag
+#[derive(Clone, Debug, PartialEq)]
+pub enum TagValue {
+    Int(i64),
+    Float(f64),
+    Char(char),
+    String(String),
+    //other types (H, B, J) are kept as raw strings
+    Other(char, String),
+}
+
+impl TagValue {
+    //None if the value doesn't match the type
+    pub fn parse(tag_type: &str, value: &str) -> Option<TagValue> {
+        match tag_type {
+            "i" => value.parse().ok().map(TagValue::Int),
+            "f" => value.parse().ok().map(TagValue::Float),
+            "A" => {
+                let mut chars = value.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => Some(TagValue::Char(c)),
+                    _ => None,
+                }
+            }
+            "Z" => Some(TagValue::String(String::from(value))),
+            _ => {
+                let mut chars = tag_type.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => Some(TagValue::Other(c, String::from(value))),
+                    _ => None,
+                }
+            }
+        }
+    }
+
+    pub fn type_char(&self) -> char {
+        match self {
+            TagValue::Int(_) => 'i',
+            TagValue::Float(_) => 'f',
+            TagValue::Char(_) => 'A',
+            TagValue::String(_) => 'Z',
+            TagValue::Other(c, _) => *c,
+        }
+    }
+
+    pub fn as_int(&self) -> Option<i64> {
+        match self {
+            TagValue::Int(i) => Some(*i),
+            _ => None,
+        }
+    }
+
+    //integer values are converted
+    pub fn as_float(&self) -> Option<f64> {
+        match self {
+            TagValue::Int(i) => Some(*i as f64),
+            TagValue::Float(f) => Some(*f),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            TagValue::String(s) => Some(s),
+            _ => None,
+        }
+    }
+}
+
+//value without the type
+impl fmt::Display for TagValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TagValue::Int(i) => write!(f, "{i}"),
+            TagValue::Float(x) => write!(f, "{x}"),
+            TagValue::Char(c) => write!(f, "{c}"),
+            TagValue::String(s) | TagValue::Other(_, s) => write!(f, "{s}"),
+        }
+    }
+}
+
+pub type Tags = HashMap<String, TagValue>;
+
 #[derive(Clone)]
 pub struct Node {
     //node size
     pub name: String,
     pub length: usize,
+    //NaN if the graph doesn't provide coverage for the node
     pub coverage: f64,
+    //only stored if requested while reading the graph
+    pub sequence: Option<String>,
+    //optional GFA tags
+    pub tags: Tags,
+}
+
+impl Node {
+    pub fn tag(&self, key: &str) -> Option<&TagValue> {
+        self.tags.get(key)
+    }
+
+    pub fn has_coverage(&self) -> bool {
+        !self.coverage.is_nan()
+    }
 }
 
 //TODO which ones are redundant?
@@ -96,7 +259,7 @@ impl Vertex {
 //TODO separate 'links' and 'edges'
 //links will have overlap size, CIGAR, etc
 //edges will represent a Vertex pair
-#[derive(Copy, Clone, Debug, PartialEq, PartialOrd)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Hash)]
 pub struct Link {
     pub start: Vertex,
     pub end: Vertex,
@@ -112,6 +275,12 @@ impl Link {
         }
     }
 
+    //link from a vertex to its own reverse-complement (e.g. a+ -> a-)
+    //such link is its own reverse-complement and is only stored once
+    pub fn is_palindromic(&self) -> bool {
+        self.start == self.end.rc()
+    }
+
     //fn is_canonical(&self) -> bool {
     //    self <= &self.rc()
     //}
@@ -125,14 +294,50 @@ impl Link {
     //}
 }
 
+//GFA parsing options (all disabled by default)
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ReadOptions<'a> {
+    //only keep the first of the links joining the same pair of vertices
+    pub collapse_multi_edges: bool,
+    //reduce overlaps exceeding the length of the shorter node instead of failing
+    pub normalize_overlaps: bool,
+    pub keep_sequences: bool,
+    //remove nodes fully contained within their neighbours
+    pub collapse_containments: bool,
+    //node lengths (by name) override LN tags and sequence lengths (or fill them in if both are absent)
+    pub node_lengths: Option<&'a HashMap<String, usize>>,
+}
+
+impl ReadOptions<'_> {
+    //multi-edges are collapsed and too long overlaps are normalized
+    pub fn sanitize() -> Self {
+        Self {
+            collapse_multi_edges: true,
+            normalize_overlaps: true,
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Clone)]
 pub struct Graph {
     nodes: Vec<Node>,
-    //TODO storage is excessive, should only store neighbor
-    //incoming & outgoing links for every node
-    incoming_links: Vec<Vec<Link>>,
-    outgoing_links: Vec<Vec<Link>>,
+    //outgoing links of every vertex, indexed by vertex_idx
+    //every link is stored next to its start, its reverse-complement next to the start of the latter
+    //(incoming links of the vertex are reverse-complements of the outgoing links of its rc)
+    links: Vec<Vec<Link>>,
     //TODO switch to &str and figure out how to work with lifetimes
     name2ids: HashMap<String, usize>,
+    //optional GFA tags of the links, keyed by (start, end) of the link as it was added
+    link_tags: HashMap<(Vertex, Vertex), Tags>,
+    //raw overlap CIGARs of the links, only stored if different from <overlap>M
+    link_cigars: HashMap<(Vertex, Vertex), String>,
+    //named paths provided in the input GFA (as P- or W-lines)
+    input_paths: Vec<(String, Path)>,
+    //non-trivial SCCs, computed on demand and reset whenever nodes or links are changed
+    scc_cache: OnceLock<Vec<Vec<Vertex>>>,
+    //number of links (each link is counted together with its reverse-complement)
+    link_cnt: usize,
 }
 
 //TODO think about useful iterators and reimplement this one via composition
@@ -142,6 +347,7 @@ struct AllLinkIter<'a> {
     curr_node: usize,
     incoming_flag: bool,
     pos: usize,
+    remaining: usize,
 }
 
 impl<'a> AllLinkIter<'a> {
@@ -151,6 +357,7 @@ impl<'a> AllLinkIter<'a> {
             curr_node: 0,
             incoming_flag: true,
             pos: 0,
+            remaining: g.link_cnt,
         }
     }
 }
@@ -161,13 +368,14 @@ impl<'a> Iterator for AllLinkIter<'a> {
     fn next(&mut self) -> Option<Self::Item> {
         while self.curr_node < self.g.node_cnt() {
             if self.incoming_flag {
-                let links = &self.g.incoming_links[self.curr_node];
+                let links = self.g.vertex_links(Vertex::reverse(self.curr_node));
                 assert!(self.pos <= links.len());
                 if self.pos < links.len() {
-                    let link = links[self.pos];
+                    let link = links[self.pos].rc();
                     assert!(link.end.node_id == self.curr_node);
                     self.pos += 1;
                     if link.end < link.start {
+                        self.remaining -= 1;
                         return Some(link);
                     }
                 } else {
@@ -175,13 +383,14 @@ impl<'a> Iterator for AllLinkIter<'a> {
                     self.pos = 0;
                 }
             } else {
-                let links = &self.g.outgoing_links[self.curr_node];
+                let links = self.g.vertex_links(Vertex::forward(self.curr_node));
                 assert!(self.pos <= links.len());
                 if self.pos < links.len() {
                     let link = links[self.pos];
                     assert!(link.start.node_id == self.curr_node);
                     self.pos += 1;
                     if link.start <= link.end {
+                        self.remaining -= 1;
                         return Some(link);
                     }
                 } else {
@@ -193,8 +402,14 @@ impl<'a> Iterator for AllLinkIter<'a> {
         }
         None
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
 }
 
+impl ExactSizeIterator for AllLinkIter<'_> {}
+
 struct VertexIter<'a> {
     g: &'a Graph,
     curr_node: usize,
@@ -228,8 +443,16 @@ impl<'a> Iterator for VertexIter<'a> {
         }
         None
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining =
+            2 * (self.g.node_cnt() - self.curr_node) - if self.forward_flag { 0 } else { 1 };
+        (remaining, Some(remaining))
+    }
 }
 
+impl ExactSizeIterator for VertexIter<'_> {}
+
 impl Default for Graph {
     fn default() -> Self {
         Self::new()
@@ -240,212 +463,867 @@ impl Graph {
     pub fn new() -> Graph {
         Graph {
             nodes: Vec::new(),
-            incoming_links: Vec::new(),
-            outgoing_links: Vec::new(),
+            links: Vec::new(),
             name2ids: HashMap::new(),
+            link_tags: HashMap::new(),
+            link_cigars: HashMap::new(),
+            input_paths: Vec::new(),
+            scc_cache: OnceLock::new(),
+            link_cnt: 0,
         }
     }
 
+    pub(crate) fn scc_cache(&self) -> &OnceLock<Vec<Vec<Vertex>>> {
+        &self.scc_cache
+    }
+
     pub fn node_cnt(&self) -> usize {
         self.nodes.len()
     }
 
-    pub fn node_iter(&self) -> std::slice::Iter<Node> {
+    pub fn node_iter(&self) -> std::slice::Iter<'_, Node> {
         self.nodes.iter()
     }
 
     pub fn add_node(&mut self, node: Node) -> usize {
         //TODO rewrite without cloning with lifetimes
         let node_id = self.nodes.len();
+        self.scc_cache.take();
         self.name2ids.insert(node.name.clone(), node_id);
         self.nodes.push(node);
-        self.incoming_links.push(Vec::new());
-        self.outgoing_links.push(Vec::new());
+        self.links.push(Vec::new());
+        self.links.push(Vec::new());
         node_id
     }
 
     pub fn add_link(&mut self, link: Link) {
+        self.scc_cache.take();
+        self.link_cnt += 1;
         //FIXME Currently doesn't check that every link is represented only once
-        //TODO Think of some nice 'views' for vectors that will reverse complement everything put
-        //there
-        match link.start.direction {
-            Direction::FORWARD => self.outgoing_links[link.start.node_id].push(link),
-            Direction::REVERSE => self.incoming_links[link.start.node_id].push(link.rc()),
-        };
+        self.links[Self::vertex_idx(link.start)].push(link);
+        if link != link.rc() {
+            self.links[Self::vertex_idx(link.end.rc())].push(link.rc());
+        }
+    }
 
-        if link == link.rc() {
-            return;
-        };
+    fn vertex_idx(v: Vertex) -> usize {
+        match v.direction {
+            Direction::FORWARD => 2 * v.node_id,
+            Direction::REVERSE => 2 * v.node_id + 1,
+        }
+    }
+
+    fn vertex_links(&self, v: Vertex) -> &Vec<Link> {
+        &self.links[Self::vertex_idx(v)]
+    }
 
-        match link.end.direction {
-            Direction::FORWARD => self.incoming_links[link.end.node_id].push(link),
-            Direction::REVERSE => self.outgoing_links[link.end.node_id].push(link.rc()),
+    //removes the link (together with its reverse-complement), O(degree)
+    pub fn remove_link(&mut self, link: &Link) {
+        let link = *link;
+        self.scc_cache.take();
+        //return the number of removed copies
+        let mut remove = |l: Link| {
+            let links = &mut self.links[Self::vertex_idx(l.start)];
+            let before = links.len();
+            links.retain(|x| *x != l);
+            before - links.len()
         };
+        //every copy of the link is stored next to its start
+        let removed = remove(link);
+        if link != link.rc() {
+            remove(link.rc());
+        }
+        self.link_cnt -= removed;
+        for key in [(link.start, link.end), (link.rc().start, link.rc().end)] {
+            self.link_tags.remove(&key);
+            self.link_cigars.remove(&key);
+        }
+    }
+
+    //removes all links incident to the node and its name from the index, O(degree)
+    //node id stays reserved (node count doesn't change) until compact() is called,
+    //input paths through the node are dropped
+    pub fn remove_node(&mut self, node_id: usize) {
+        let v = Vertex::forward(node_id);
+        let incident = self
+            .outgoing_edges(v)
+            .iter()
+            .copied()
+            .chain(self.incoming_edges(v))
+            .collect_vec();
+        for l in incident {
+            self.remove_link(&l);
+        }
+        if !self.is_removed(node_id) {
+            self.name2ids.remove(&self.nodes[node_id].name);
+        }
+        self.input_paths.retain(|(_, p)| !p.in_path(node_id));
+    }
+
+    pub fn is_removed(&self, node_id: usize) -> bool {
+        self.name2ids.get(&self.nodes[node_id].name) != Some(&node_id)
+    }
+
+    //renumbers the remaining nodes after removals (preserving their relative order)
+    //returns new ids of the nodes (None for removed ones)
+    pub fn compact(&mut self) -> Vec<Option<usize>> {
+        let kept: HashSet<usize> = (0..self.node_cnt())
+            .filter(|&node_id| !self.is_removed(node_id))
+            .collect();
+        let mut next_id = 0;
+        let new_ids = (0..self.node_cnt())
+            .map(|node_id| {
+                kept.contains(&node_id).then(|| {
+                    next_id += 1;
+                    next_id - 1
+                })
+            })
+            .collect();
+        *self = self.subgraph(&kept);
+        new_ids
+    }
+
+    pub fn set_link_tags(&mut self, link: Link, tags: Tags) {
+        self.link_tags.remove(&(link.rc().start, link.rc().end));
+        self.link_tags.insert((link.start, link.end), tags);
+    }
+
+    //tags of the link (or of its reverse-complement)
+    pub fn link_tags(&self, link: Link) -> Option<&Tags> {
+        self.link_tags
+            .get(&(link.start, link.end))
+            .or_else(|| self.link_tags.get(&(link.rc().start, link.rc().end)))
+    }
+
+    pub fn link_tag(&self, link: Link, key: &str) -> Option<&TagValue> {
+        self.link_tags(link)?.get(key)
+    }
+
+    pub fn set_link_cigar(&mut self, link: Link, cigar: &str) {
+        self.link_cigars.remove(&(link.rc().start, link.rc().end));
+        self.link_cigars
+            .insert((link.start, link.end), String::from(cigar));
+    }
+
+    //raw CIGAR of the link (reversed if it was provided for the reverse-complement link)
+    pub fn link_cigar(&self, link: Link) -> Option<String> {
+        match self.link_cigars.get(&(link.start, link.end)) {
+            Some(cigar) => Some(cigar.clone()),
+            None => self
+                .link_cigars
+                .get(&(link.rc().start, link.rc().end))
+                .map(|cigar| reverse_cigar(cigar)),
+        }
+    }
+
+    pub fn input_paths(&self) -> &[(String, Path)] {
+        &self.input_paths
     }
 
     //FIXME add this check within add_link function
     fn check_links(&self) {
-        assert!(self.nodes.len() == self.incoming_links.len());
-        assert!(self.nodes.len() == self.outgoing_links.len());
-        for (node_id, _) in self.all_nodes().enumerate() {
-            let v = Vertex::forward(node_id);
-            assert!(
-                self.incoming_links[node_id]
-                    .iter()
-                    .filter(|l| l.end != v)
-                    .count()
-                    == 0,
-                "Problem with incoming links for node {}",
-                self.nodes[node_id].name
-            );
+        assert!(2 * self.nodes.len() == self.links.len());
+        for v in self.all_vertices() {
             assert!(
-                self.outgoing_links[node_id]
-                    .iter()
-                    .filter(|l| l.start != v)
-                    .count()
-                    == 0,
-                "Problem with incoming links for node {}",
-                self.nodes[node_id].name
+                self.vertex_links(v).iter().all(|l| l.start == v),
+                "Problem with outgoing links for vertex {}",
+                self.v_str(v)
             );
         }
     }
 
-    //TODO switch to iterator?
-    fn parse_tag<T: str::FromStr>(fields: &[&str], prefix: &str) -> Option<T> {
+    fn parse_tag<T: str::FromStr>(
+        fields: &[&str],
+        prefix: &str,
+    ) -> Result<Option<T>, ParseErrorKind> {
+        match fields.iter().find(|s| s.starts_with(prefix)) {
+            None => Ok(None),
+            Some(s) => s[prefix.len()..]
+                .parse::<T>()
+                .map(Some)
+                .map_err(|_| ParseErrorKind::InvalidTag(String::from(*s))),
+        }
+    }
+
+    fn parse_tags(fields: &[&str]) -> Tags {
         fields
             .iter()
-            .filter(|s| s.starts_with(prefix))
-            .map(|s| match s[prefix.len()..].parse::<T>() {
-                Ok(t) => t,
-                Err(_) => panic!("Couldn't parse tag {s}"),
+            .filter_map(|s| {
+                let mut split = s.trim().splitn(3, ':');
+                let key = split.next()?;
+                let tag_type = split.next()?;
+                match TagValue::parse(tag_type, split.next()?) {
+                    Some(value) => Some((String::from(key), value)),
+                    None => {
+                        warn!("Ignoring malformed tag {s}");
+                        None
+                    }
+                }
             })
-            .next()
+            .collect()
+    }
+
+    //overlap size on the start vertex (total length of M/D/N/=/X operations)
+    //None if CIGAR is invalid
+    fn parse_overlap(cigar: &str) -> Option<usize> {
+        let mut overlap = 0;
+        for (len, op) in cigar_ops(cigar)? {
+            match op {
+                'M' | 'D' | 'N' | '=' | 'X' => overlap += len,
+                'I' | 'S' | 'H' | 'P' => {}
+                _ => return None,
+            }
+        }
+        Some(overlap)
+    }
+
+    //panics on parsing errors
+    pub fn custom_read(graph_str: &str, options: &ReadOptions) -> Graph {
+        Self::custom_read_from(Cursor::new(graph_str), options).unwrap_or_else(|e| panic!("{e}"))
     }
 
-    fn parse_overlap(cigar: &str) -> usize {
-        assert!(cigar.ends_with('M'), "Invalid overlap {cigar}");
-        let ovl = &cigar[..(cigar.len() - 1)];
-        ovl.trim().parse().expect("Invalid overlap")
+    pub fn read_from<R: BufRead>(reader: R) -> Result<Graph, ParseError> {
+        Self::custom_read_from(reader, &ReadOptions::default())
     }
 
-    //TODO switch to something iterable
-    pub fn custom_read(
-        graph_str: &str,
-        collapse_multi_edges: bool,
-        normalize_overlaps: bool,
-    ) -> Graph {
+    //parses GFA line by line, segments are added right away,
+    //links are processed after all segments are known
+    pub fn custom_read_from<R: BufRead>(
+        reader: R,
+        options: &ReadOptions,
+    ) -> Result<Graph, ParseError> {
         let mut g = Self::new();
+        //(line number, line)
+        let mut link_lines = Vec::new();
+        let mut path_lines = Vec::new();
 
-        for line in graph_str.lines() {
+        for (i, line) in reader.lines().enumerate() {
+            let line_num = i + 1;
+            let line = line.map_err(|e| ParseError::new(line_num, ParseErrorKind::Io(e)))?;
             if line.starts_with("S\t") {
-                let split: Vec<&str> = line.split('\t').collect();
-                //println!("Node line {:?}", split);
-                let name = String::from(split[1]);
-                let tags = &split[3..split.len()];
-                let length = if split[2] != "*" {
-                    split[2].trim().len()
-                } else {
-                    Self::parse_tag(tags, "LN:i:").expect("Neither sequence nor LN tag provided")
-                };
-                assert!(length > 0);
-                let coverage = match Self::parse_tag::<usize>(tags, "RC:i:")
-                    .or_else(|| Self::parse_tag::<usize>(tags, "FC:i:"))
-                {
-                    None => Self::parse_tag(tags, "ll:f:").unwrap_or(0.),
-                    Some(raw_cnt) => raw_cnt as f64 / length as f64,
-                };
-                g.add_node(Node {
-                    name,
-                    length,
-                    coverage,
-                });
+                let node =
+                    Self::parse_node_line(&line, options.keep_sequences, options.node_lengths)
+                        .map_err(|kind| ParseError::new(line_num, kind))?;
+                if g.name2ids.contains_key(&node.name) {
+                    return Err(ParseError::new(
+                        line_num,
+                        ParseErrorKind::DuplicateSegment(node.name),
+                    ));
+                }
+                g.add_node(node);
+            } else if line.starts_with("L\t") {
+                link_lines.push((line_num, line));
+            } else if line.starts_with("P\t") || line.starts_with("W\t") {
+                path_lines.push((line_num, line));
             }
         }
 
-        for line in graph_str.lines() {
-            if line.starts_with("L\t") {
-                let split: Vec<&str> = line.trim().split('\t').collect();
-                //println!("Link line {:?}", split);
-                let start = Vertex {
-                    node_id: g.name2id(split[1]),
-                    direction: Direction::parse(split[2]),
-                };
-                let end = Vertex {
-                    node_id: g.name2id(split[3]),
-                    direction: Direction::parse(split[4]),
-                };
-                let mut overlap = Self::parse_overlap(split[5]);
-                if collapse_multi_edges {
-                    if let Some(connect) = g.connector(start, end) {
-                        if connect.overlap != overlap {
-                            warn!("Multiple links connecting {} and {} with different overlap sizes ({} and {})"
-                                    , g.v_str(start), g.v_str(end), overlap, connect.overlap)
-                        }
-                        continue;
+        let no_cov_cnt = g.all_nodes().filter(|n| !n.has_coverage()).count();
+        if no_cov_cnt > 0 {
+            warn!(
+                "Coverage (RC/FC/KC/ll/dp tags) not provided for {} out of {} nodes",
+                no_cov_cnt,
+                g.node_cnt()
+            );
+        }
+
+        let mut containments = Vec::new();
+        let mut unknown_overlap_cnt = 0;
+        for (line_num, line) in link_lines {
+            let err = |kind| ParseError::new(line_num, kind);
+            let split: Vec<&str> = line.trim().split('\t').collect();
+            //println!("Link line {:?}", split);
+            if split.len() < 6 {
+                return Err(err(ParseErrorKind::MissingField("overlap")));
+            }
+            let vertex = |name: &str, direction: &str| -> Result<Vertex, ParseError> {
+                Ok(Vertex {
+                    node_id: *g
+                        .name2ids
+                        .get(name)
+                        .ok_or_else(|| err(ParseErrorKind::UnknownSegment(String::from(name))))?,
+                    direction: Direction::try_parse(direction).ok_or_else(|| {
+                        err(ParseErrorKind::InvalidDirection(String::from(direction)))
+                    })?,
+                })
+            };
+            let start = vertex(split[1], split[2])?;
+            let end = vertex(split[3], split[4])?;
+            let cigar = split[5].trim();
+            let mut overlap = match Self::parse_overlap(cigar) {
+                Some(overlap) => overlap,
+                None => {
+                    if cigar == "*" {
+                        unknown_overlap_cnt += 1;
+                    } else {
+                        warn!("Invalid overlap CIGAR '{cigar}' on line {line_num}, assuming 0");
                     }
+                    0
                 }
-                let max_ovl = std::cmp::min(g.vertex_length(start), g.vertex_length(end)) - 1;
-                if overlap > max_ovl {
-                    assert!(
-                        normalize_overlaps,
-                        "Invalid (too long) overlap of size {} between {} and {}",
+            };
+            if options.collapse_multi_edges {
+                if let Some(connect) = g.connector(start, end) {
+                    if connect.overlap != overlap {
+                        warn!("Multiple links connecting {} and {} with different overlap sizes ({} and {})"
+                                , g.v_str(start), g.v_str(end), overlap, connect.overlap)
+                    }
+                    continue;
+                }
+            }
+            let link = Link {
+                start,
+                end,
+                overlap,
+            };
+            if g.is_full_containment(link) {
+                warn!(
+                    "Overlap of size {} covers both {} and {} (full containment)",
+                    overlap,
+                    g.v_str(start),
+                    g.v_str(end)
+                );
+                containments.push(link);
+                if options.collapse_containments {
+                    continue;
+                }
+            }
+            let max_ovl = std::cmp::min(g.vertex_length(start), g.vertex_length(end)) - 1;
+            if overlap > max_ovl {
+                if !options.normalize_overlaps {
+                    return Err(err(ParseErrorKind::TooLongOverlap(
                         overlap,
                         g.v_str(start),
-                        g.v_str(end)
-                    );
-                    warn!(
-                        "Normalizing overlap between {} and {} ({} -> {})",
-                        g.v_str(start),
                         g.v_str(end),
-                        overlap,
-                        max_ovl
+                    )));
+                }
+                warn!(
+                    "Normalizing overlap between {} and {} ({} -> {})",
+                    g.v_str(start),
+                    g.v_str(end),
+                    overlap,
+                    max_ovl
+                );
+                overlap = max_ovl;
+            }
+            let trivial_cigar = overlap == link.overlap && cigar == format!("{overlap}M");
+            let link = Link { overlap, ..link };
+            g.add_link(link);
+            //normalized overlaps and unknown/invalid CIGARs are not kept
+            if !trivial_cigar && overlap == link.overlap && Self::parse_overlap(cigar).is_some() {
+                g.set_link_cigar(link, cigar);
+            }
+            let tags = Self::parse_tags(&split[6..]);
+            if !tags.is_empty() {
+                g.set_link_tags(link, tags);
+            }
+        }
+        if unknown_overlap_cnt > 0 {
+            warn!("Overlap size not specified ('*') for {unknown_overlap_cnt} links, assuming 0");
+        }
+        for (line_num, line) in path_lines {
+            let parsed = if line.starts_with('P') {
+                Self::parse_path_line(&g, &line)
+            } else {
+                Self::parse_walk_line(&g, &line)
+            };
+            //paths revisiting segments can't be represented and are skipped
+            if let Some((name, path)) = parsed.map_err(|kind| ParseError::new(line_num, kind))? {
+                g.input_paths.push((name, path));
+            }
+        }
+        g.check_links();
+        if options.collapse_containments && !containments.is_empty() {
+            g = g.collapse_containments(&containments);
+            g.check_links();
+        }
+        Ok(g)
+    }
+
+    //P <name> <seg1>+,<seg2>-,... <overlaps (comma-separated CIGARs) or *>
+    fn parse_path_line(g: &Graph, line: &str) -> Result<Option<(String, Path)>, ParseErrorKind> {
+        let split: Vec<&str> = line.trim().split('\t').collect();
+        let name = *split
+            .get(1)
+            .ok_or(ParseErrorKind::MissingField("path name"))?;
+        let invalid = |problem: String| ParseErrorKind::InvalidPath(String::from(name), problem);
+        let segments = split
+            .get(2)
+            .ok_or(ParseErrorKind::MissingField("segment names"))?;
+        let mut vertices = Vec::new();
+        for s in segments.split(',') {
+            if s.len() < 2 || !s.is_char_boundary(s.len() - 1) {
+                return Err(invalid(format!("invalid oriented segment '{s}'")));
+            }
+            let (seg, o) = s.split_at(s.len() - 1);
+            vertices.push(Vertex {
+                node_id: *g
+                    .name2ids
+                    .get(seg)
+                    .ok_or_else(|| invalid(format!("unknown segment '{seg}'")))?,
+                direction: Direction::try_parse(o)
+                    .ok_or_else(|| invalid(format!("invalid orientation '{o}'")))?,
+            });
+        }
+        let overlaps = match split.get(3).map(|s| s.trim()) {
+            None | Some("*") => None,
+            Some(overlaps) => {
+                let overlaps = overlaps
+                    .split(',')
+                    .map(|cigar| {
+                        Self::parse_overlap(cigar)
+                            .ok_or_else(|| invalid(format!("invalid overlap '{cigar}'")))
+                    })
+                    .collect::<Result<Vec<usize>, ParseErrorKind>>()?;
+                if overlaps.len() + 1 != vertices.len() {
+                    return Err(invalid(format!(
+                        "{} overlaps provided for {} segments",
+                        overlaps.len(),
+                        vertices.len()
+                    )));
+                }
+                Some(overlaps)
+            }
+        };
+        let path = Self::path_through(g, name, &vertices, overlaps.as_deref());
+        Ok(path.map(|p| (String::from(name), p)))
+    }
+
+    //overlaps are taken from the graph links if not provided
+    //None (with a warning) if the path visits some segment multiple times
+    fn path_through(
+        g: &Graph,
+        name: &str,
+        vertices: &[Vertex],
+        overlaps: Option<&[usize]>,
+    ) -> Option<Path> {
+        let mut path = Path::new(vertices[0]);
+        for (i, (&v, &w)) in vertices.iter().tuple_windows().enumerate() {
+            if path.in_path(w.node_id) {
+                warn!(
+                    "Skipping path {name}: segment '{}' used multiple times",
+                    g.name(w.node_id)
+                );
+                return None;
+            }
+            let overlap = match (overlaps, g.connector(v, w)) {
+                (Some(overlaps), _) => overlaps[i],
+                (None, Some(l)) => l.overlap,
+                (None, None) => 0,
+            };
+            if g.connector(v, w).is_none() {
+                warn!(
+                    "Path {name} goes through {} -> {}, which isn't a link in the graph",
+                    g.v_str(v),
+                    g.v_str(w)
+                );
+            }
+            path.append(Link {
+                start: v,
+                end: w,
+                overlap,
+            });
+        }
+        Some(path)
+    }
+
+    //W <sample> <haplotype index> <seq id> <start> <end> <walk (>seg1<seg2...)>
+    //path is named <sample>#<haplotype index>#<seq id> (PanSN)
+    fn parse_walk_line(g: &Graph, line: &str) -> Result<Option<(String, Path)>, ParseErrorKind> {
+        let split: Vec<&str> = line.trim().split('\t').collect();
+        if split.len() < 7 {
+            return Err(ParseErrorKind::MissingField("walk"));
+        }
+        let name = format!("{}#{}#{}", split[1], split[2], split[3]);
+        let invalid = |problem: String| ParseErrorKind::InvalidPath(name.clone(), problem);
+        let walk = split[6];
+        if !walk.starts_with(['>', '<']) {
+            return Err(invalid(format!("invalid walk '{walk}'")));
+        }
+        let mut vertices = Vec::new();
+        for (i, o) in walk.match_indices(['>', '<']) {
+            let seg = walk[(i + 1)..].split(['>', '<']).next().unwrap();
+            vertices.push(Vertex {
+                node_id: *g
+                    .name2ids
+                    .get(seg)
+                    .ok_or_else(|| invalid(format!("unknown segment '{seg}'")))?,
+                direction: if o == ">" {
+                    Direction::FORWARD
+                } else {
+                    Direction::REVERSE
+                },
+            });
+        }
+        let path = Self::path_through(g, &name, &vertices, None);
+        Ok(path.map(|p| (name, p)))
+    }
+
+    fn parse_node_line(
+        line: &str,
+        keep_sequences: bool,
+        node_lengths: Option<&HashMap<String, usize>>,
+    ) -> Result<Node, ParseErrorKind> {
+        let split: Vec<&str> = line.split('\t').collect();
+        //println!("Node line {:?}", split);
+        let name = String::from(
+            *split
+                .get(1)
+                .filter(|s| !s.is_empty())
+                .ok_or(ParseErrorKind::MissingField("segment name"))?,
+        );
+        let tags = if split.len() > 3 { &split[3..] } else { &[] };
+        //'*' (or missing/empty field) means that sequence isn't provided
+        let seq = split
+            .get(2)
+            .map(|s| s.trim())
+            .filter(|&s| !s.is_empty() && s != "*");
+        let ln = Self::parse_tag::<usize>(tags, "LN:i:")?;
+        let length_override = node_lengths.and_then(|m| m.get(&name)).copied();
+        let length = match (length_override, seq, ln) {
+            (Some(length), seq, _) => {
+                if let Some(seq) = seq.filter(|seq| seq.len() != length) {
+                    warn!(
+                        "Provided length {length} differs from sequence length {} for segment {name}",
+                        seq.len()
                     );
-                    overlap = max_ovl;
                 }
-                g.add_link(Link {
+                length
+            }
+            (None, Some(seq), Some(ln)) if seq.len() != ln => {
+                warn!(
+                    "LN tag value {ln} differs from sequence length {} for segment {name}, using the latter",
+                    seq.len()
+                );
+                seq.len()
+            }
+            (None, Some(seq), _) => seq.len(),
+            (None, None, Some(ln)) => ln,
+            (None, None, None) => return Err(ParseErrorKind::MissingLength(name)),
+        };
+        if length == 0 {
+            return Err(ParseErrorKind::ZeroLength(name));
+        }
+        //read (RC/FC) or k-mer (KC) counts are normalized by length
+        let per_base = |raw_cnt: usize| raw_cnt as f64 / length as f64;
+        //tags are checked in order of preference, lower priority ones are only parsed if needed
+        let coverage = if let Some(raw_cnt) = Self::parse_tag::<usize>(tags, "RC:i:")? {
+            per_base(raw_cnt)
+        } else if let Some(raw_cnt) = Self::parse_tag::<usize>(tags, "FC:i:")? {
+            per_base(raw_cnt)
+        } else if let Some(cov) = Self::parse_tag(tags, "ll:f:")? {
+            cov
+        } else if let Some(cov) = Self::parse_tag(tags, "dp:f:")? {
+            cov
+        } else if let Some(kmer_cnt) = Self::parse_tag::<usize>(tags, "KC:i:")? {
+            per_base(kmer_cnt)
+        } else {
+            f64::NAN
+        };
+        let sequence = if keep_sequences {
+            //stored sequences are sliced by byte offsets (see Path::sequence)
+            if seq.is_some_and(|s| !s.is_ascii()) {
+                return Err(ParseErrorKind::NonAsciiSequence(name));
+            }
+            seq.map(String::from)
+        } else {
+            None
+        };
+        Ok(Node {
+            name,
+            length,
+            coverage,
+            sequence,
+            tags: Self::parse_tags(tags),
+        })
+    }
+
+    //overlap covers the full length of both endpoints (nodes are effectively identical)
+    pub fn is_full_containment(&self, l: Link) -> bool {
+        l.start.node_id != l.end.node_id
+            && l.overlap >= std::cmp::max(self.vertex_length(l.start), self.vertex_length(l.end))
+    }
+
+    //removes the end node of every full containment link,
+    //redirecting its links to the (corresponding vertex of) the start node
+    fn collapse_containments(&self, containments: &[Link]) -> Graph {
+        let mut remap: HashMap<Vertex, Vertex> = HashMap::new();
+        let resolve = |remap: &HashMap<Vertex, Vertex>, mut v: Vertex| {
+            while let Some(&w) = remap.get(&v) {
+                v = w;
+            }
+            v
+        };
+        for l in containments {
+            let target = resolve(&remap, l.start);
+            let contained = resolve(&remap, l.end);
+            if target.node_id != contained.node_id {
+                remap.insert(contained, target);
+                remap.insert(contained.rc(), target.rc());
+            }
+        }
+
+        let mut g = Graph::new();
+        for (node_id, n) in self.all_nodes().enumerate() {
+            if !remap.contains_key(&Vertex::forward(node_id)) {
+                g.add_node(n.clone());
+            }
+        }
+        let new_vertex = |v: Vertex| {
+            let v = resolve(&remap, v);
+            Vertex {
+                node_id: g.name2id(self.name(v.node_id)),
+                direction: v.direction,
+            }
+        };
+        let mut links = Vec::new();
+        for l in self.all_links() {
+            let (start, end) = (new_vertex(l.start), new_vertex(l.end));
+            //links between collapsed nodes
+            if start.node_id == end.node_id && l.start.node_id != l.end.node_id {
+                continue;
+            }
+            links.push((
+                Link {
                     start,
                     end,
-                    overlap,
-                });
+                    overlap: l.overlap,
+                },
+                self.link_tags(l),
+                self.link_cigar(l),
+            ));
+        }
+        let mut input_paths = Vec::new();
+        for (name, path) in &self.input_paths {
+            if path.vertices().iter().any(|v| remap.contains_key(v)) {
+                warn!("Input path {name} goes through collapsed nodes and will be ignored");
+                continue;
+            }
+            let mut new_path = Path::new(new_vertex(path.start()));
+            for l in path.links() {
+                if let GeneralizedLink::LINK(l) = l {
+                    new_path.append(Link {
+                        start: new_vertex(l.start),
+                        end: new_vertex(l.end),
+                        overlap: l.overlap,
+                    });
+                }
             }
+            input_paths.push((name.clone(), new_path));
         }
+        for (l, tags, cigar) in links {
+            if g.connector(l.start, l.end).is_none() {
+                g.add_link(l);
+                if let Some(tags) = tags {
+                    g.set_link_tags(l, tags.clone());
+                }
+                if let Some(cigar) = cigar {
+                    g.set_link_cigar(l, &cigar);
+                }
+            }
+        }
+        g.input_paths = input_paths;
+        info!(
+            "Collapsed {} contained nodes",
+            self.node_cnt() - g.node_cnt()
+        );
+        g
+    }
+
+    //induced subgraph on the given nodes (with links and input paths entirely within it)
+    //node ids are remapped (preserving relative order), names are kept
+    pub fn subgraph(&self, nodes: &HashSet<usize>) -> Graph {
+        let mut g = Graph::new();
+        for (node_id, n) in self.all_nodes().enumerate() {
+            if nodes.contains(&node_id) {
+                g.add_node(n.clone());
+            }
+        }
+        let new_vertex = |v: Vertex| Vertex {
+            node_id: g.name2id(self.name(v.node_id)),
+            direction: v.direction,
+        };
+        let inside = |l: &Link| nodes.contains(&l.start.node_id) && nodes.contains(&l.end.node_id);
+        let links = self
+            .all_links()
+            .filter(inside)
+            .map(|l| {
+                (
+                    Link {
+                        start: new_vertex(l.start),
+                        end: new_vertex(l.end),
+                        overlap: l.overlap,
+                    },
+                    self.link_tags(l),
+                    self.link_cigar(l),
+                )
+            })
+            .collect_vec();
+        let input_paths = self
+            .input_paths
+            .iter()
+            .filter(|(_, p)| p.vertices().iter().all(|v| nodes.contains(&v.node_id)))
+            .map(|(name, p)| {
+                let mut new_path = Path::new(new_vertex(p.start()));
+                for l in p.links() {
+                    match l {
+                        GeneralizedLink::LINK(l) => new_path.append(Link {
+                            start: new_vertex(l.start),
+                            end: new_vertex(l.end),
+                            overlap: l.overlap,
+                        }),
+                        GeneralizedLink::GAP(gap) => {
+                            new_path.append_general(GeneralizedLink::GAP(GapInfo {
+                                start: new_vertex(gap.start),
+                                end: new_vertex(gap.end),
+                                ..gap.clone()
+                            }))
+                        }
+                    }
+                }
+                (name.clone(), new_path)
+            })
+            .collect_vec();
+        for (l, tags, cigar) in links {
+            g.add_link(l);
+            if let Some(tags) = tags {
+                g.set_link_tags(l, tags.clone());
+            }
+            if let Some(cigar) = cigar {
+                g.set_link_cigar(l, &cigar);
+            }
+        }
+        g.input_paths = input_paths;
         g.check_links();
         g
     }
 
+    //nodes reachable from the given one via at most 'radius' links (in either direction)
+    pub fn neighborhood(&self, node_id: usize, radius: usize) -> HashSet<usize> {
+        let mut visited = HashSet::from([node_id]);
+        let mut frontier = vec![node_id];
+        for _ in 0..radius {
+            let mut next = Vec::new();
+            for &n in &frontier {
+                let v = Vertex::forward(n);
+                for l in self
+                    .outgoing_edges(v)
+                    .iter()
+                    .copied()
+                    .chain(self.incoming_edges(v))
+                {
+                    for w in [l.start.node_id, l.end.node_id] {
+                        if visited.insert(w) {
+                            next.push(w);
+                        }
+                    }
+                }
+            }
+            if next.is_empty() {
+                break;
+            }
+            frontier = next;
+        }
+        visited
+    }
+
     pub fn as_gfa(&self) -> String {
         let mut gfa = String::new();
 
         for n in self.all_nodes() {
-            gfa += &format!(
-                "S\t{}\t*\tLN:i:{}\tRC:i:{}\tll:f:{:.1}\n",
-                n.name,
-                n.length,
-                (n.coverage * n.length as f64).round() as u64,
-                n.coverage
-            );
+            gfa += &format!("S\t{}\t*\tLN:i:{}", n.name, n.length);
+            if n.has_coverage() {
+                gfa += &format!(
+                    "\tRC:i:{}\tll:f:{:.1}",
+                    (n.coverage * n.length as f64).round() as u64,
+                    n.coverage
+                );
+            }
+            gfa += "\n";
         }
 
         for l in self.all_links() {
-            gfa += &format!(
-                "L\t{}\t{}\t{}\t{}\t{}M\n",
-                self.node(l.start.node_id).name,
-                Direction::str(l.start.direction),
-                self.node(l.end.node_id).name,
-                Direction::str(l.end.direction),
-                l.overlap
-            );
+            gfa += &self.link_gfa_line(l);
+            gfa += "\n";
         }
 
         gfa
     }
 
+    //S and L lines with preserved tags (ordered by key)
+    //LN tag is always (re-)computed, coverage is reported as ll tag unless already present
+    pub fn write_gfa<W: Write>(&self, w: &mut W) -> Result<(), std::io::Error> {
+        let write_tags = |w: &mut W, tags: &Tags| -> Result<(), std::io::Error> {
+            let mut keys: Vec<&String> = tags.keys().collect();
+            keys.sort();
+            for key in keys {
+                let value = &tags[key];
+                write!(w, "\t{}:{}:{}", key, value.type_char(), value)?;
+            }
+            Ok(())
+        };
+        for n in self.all_nodes() {
+            write!(
+                w,
+                "S\t{}\t{}\tLN:i:{}",
+                n.name,
+                n.sequence.as_deref().unwrap_or("*"),
+                n.length
+            )?;
+            let coverage_tagged = ["RC", "FC", "ll", "dp", "KC"]
+                .iter()
+                .any(|&key| n.tags.contains_key(key));
+            if n.has_coverage() && !coverage_tagged {
+                write!(w, "\tll:f:{:.1}", n.coverage)?;
+            }
+            let mut tags = n.tags.clone();
+            tags.remove("LN");
+            write_tags(w, &tags)?;
+            writeln!(w)?;
+        }
+        for l in self.all_links() {
+            write!(w, "{}", self.link_gfa_line(l))?;
+            if let Some(tags) = self.link_tags(l) {
+                write_tags(w, tags)?;
+            }
+            writeln!(w)?;
+        }
+        for (name, path) in &self.input_paths {
+            let overlaps = if path.len() == 1 {
+                String::from("*")
+            } else {
+                path.links()
+                    .iter()
+                    .map(|l| format!("{}M", l.overlap()))
+                    .join(",")
+            };
+            writeln!(w, "P\t{}\t{}\t{}", name, path.print(self), overlaps)?;
+        }
+        Ok(())
+    }
+
+    //replaces the previous value if present
+    pub fn set_node_tag(&mut self, node_id: usize, key: &str, value: TagValue) {
+        self.nodes[node_id].tags.insert(String::from(key), value);
+    }
+
+    pub fn link_gfa_line(&self, l: Link) -> String {
+        format!(
+            "L\t{}\t{}\t{}\t{}\t{}",
+            self.node(l.start.node_id).name,
+            Direction::str(l.start.direction),
+            self.node(l.end.node_id).name,
+            Direction::str(l.end.direction),
+            self.link_cigar(l)
+                .unwrap_or_else(|| format!("{}M", l.overlap))
+        )
+    }
+
     pub fn read(graph_str: &str) -> Self {
-        Self::custom_read(graph_str, false, false)
+        Self::custom_read(graph_str, &ReadOptions::default())
     }
 
     pub fn read_sanitize(graph_str: &str) -> Self {
-        Self::custom_read(graph_str, true, true)
+        Self::custom_read(graph_str, &ReadOptions::sanitize())
     }
 
     //fn get_vertex(&self, name: &str, direction: Direction) -> Vertex {
@@ -453,10 +1331,6 @@ impl Graph {
     //    Vertex {node_id, direction}
     //}
 
-    fn rc(links: &[Link]) -> Vec<Link> {
-        links.iter().map(|x| x.rc()).collect()
-    }
-
     pub fn node(&self, node_id: usize) -> &Node {
         &self.nodes[node_id]
     }
@@ -469,6 +1343,71 @@ impl Graph {
         self.node_length(v.node_id)
     }
 
+    //length of the node excluding the longest incoming and outgoing overlaps
+    //(sequence not shared with any of the neighbors), zero if overlaps cover the node
+    pub fn unique_length(&self, node_id: usize) -> usize {
+        let v = Vertex::forward(node_id);
+        let max_ovl = |ovls: &mut dyn Iterator<Item = usize>| ovls.max().unwrap_or(0);
+        self.node_length(node_id).saturating_sub(
+            max_ovl(&mut self.incoming_edges(v).map(|l| l.overlap))
+                + max_ovl(&mut self.outgoing_edges(v).iter().map(|l| l.overlap)),
+        )
+    }
+
+    //coverage re-normalized to the unique (non-overlapped) portion of the node
+    //reported coverage is averaged over the entire node length,
+    //while the bases within overlaps are shared with the neighbors
+    //falls back to raw coverage if overlaps cover the entire node
+    pub fn effective_coverage(&self, node_id: usize) -> f64 {
+        let n = self.node(node_id);
+        match self.unique_length(node_id) {
+            0 => n.coverage,
+            unique_len => n.coverage * n.length as f64 / unique_len as f64,
+        }
+    }
+
+    //None if sequence wasn't stored
+    pub fn vertex_sequence(&self, v: Vertex) -> Option<String> {
+        let seq = self.node(v.node_id).sequence.as_ref()?;
+        match v.direction {
+            Direction::FORWARD => Some(seq.clone()),
+            Direction::REVERSE => Some(reverse_complement(seq)),
+        }
+    }
+
+    //node sequence equal to its own reverse-complement (both vertices spell the same sequence)
+    //always false if sequence wasn't stored
+    pub fn has_palindromic_sequence(&self, node_id: usize) -> bool {
+        self.node(node_id)
+            .sequence
+            .as_ref()
+            .is_some_and(|seq| reverse_complement(seq) == *seq)
+    }
+
+    //node that walks can traverse in both orientations, leading to ambiguous walks:
+    //either its sequence is palindromic or it has a link to its own reverse-complement
+    pub fn is_palindromic(&self, node_id: usize) -> bool {
+        let v = Vertex::forward(node_id);
+        self.has_palindromic_sequence(node_id)
+            || self
+                .outgoing_edges(v)
+                .iter()
+                .copied()
+                .chain(self.incoming_edges(v))
+                .any(|l| l.is_palindromic())
+    }
+
+    //nodes with palindromic sequence
+    pub fn palindromic_nodes(&self) -> Vec<usize> {
+        (0..self.node_cnt())
+            .filter(|&node_id| self.has_palindromic_sequence(node_id))
+            .collect()
+    }
+
+    pub fn palindromic_links(&self) -> Vec<Link> {
+        self.all_links().filter(|l| l.is_palindromic()).collect()
+    }
+
     pub fn node_by_name(&self, name: &str) -> &Node {
         &self.nodes[self.name2id(name)]
     }
@@ -477,52 +1416,81 @@ impl Graph {
         &self.node(node_id).name
     }
 
-    //TODO switch to iterators when learn enough Rust :)
-    pub fn outgoing_edge_cnt(&self, v: Vertex) -> usize {
-        match v.direction {
-            Direction::FORWARD => self.outgoing_links[v.node_id].len(),
-            Direction::REVERSE => self.incoming_links[v.node_id].len(),
-        }
+    pub fn out_degree(&self, v: Vertex) -> usize {
+        self.vertex_links(v).len()
     }
 
-    //TODO switch to iterators when learn enough Rust :)
-    pub fn outgoing_edges(&self, v: Vertex) -> Vec<Link> {
-        match v.direction {
-            Direction::FORWARD => self.outgoing_links[v.node_id].clone(),
-            Direction::REVERSE => Self::rc(&self.incoming_links[v.node_id]),
-        }
+    pub fn in_degree(&self, v: Vertex) -> usize {
+        self.out_degree(v.rc())
+    }
+
+    #[deprecated(note = "use out_degree")]
+    pub fn outgoing_edge_cnt(&self, v: Vertex) -> usize {
+        self.out_degree(v)
     }
 
+    #[deprecated(note = "use in_degree")]
     pub fn incoming_edge_cnt(&self, v: Vertex) -> usize {
-        self.outgoing_edge_cnt(v.rc())
+        self.in_degree(v)
     }
 
-    //TODO switch to iterators when learn enough Rust :)
-    pub fn incoming_edges(&self, v: Vertex) -> Vec<Link> {
-        match v.direction {
-            Direction::FORWARD => self.incoming_links[v.node_id].clone(),
-            Direction::REVERSE => Self::rc(&self.outgoing_links[v.node_id]),
-        }
+    //end vertices of the outgoing links (without allocating link vector)
+    pub fn successors(&self, v: Vertex) -> impl Iterator<Item = Vertex> + '_ {
+        self.vertex_links(v).iter().map(|l| l.end)
+    }
+
+    //start vertices of the incoming links
+    pub fn predecessors(&self, v: Vertex) -> impl Iterator<Item = Vertex> + '_ {
+        self.successors(v.rc()).map(|w| w.rc())
     }
 
+    pub fn outgoing_edges(&self, v: Vertex) -> &[Link] {
+        self.vertex_links(v)
+    }
+
+    //reverse-complements of the outgoing links of v.rc() (without allocating link vector)
+    pub fn incoming_edges(&self, v: Vertex) -> impl ExactSizeIterator<Item = Link> + Clone + '_ {
+        self.vertex_links(v.rc()).iter().map(|l| l.rc())
+    }
+
+    pub fn try_name2id(&self, name: &str) -> Option<usize> {
+        self.name2ids.get(name).copied()
+    }
+
+    //panics if there is no such node
     pub fn name2id(&self, name: &str) -> usize {
-        match self.name2ids.get(name) {
-            Some(&id) => id,
-            None => panic!("Node {name} is not in the graph"),
-        }
+        self.try_name2id(name)
+            .unwrap_or_else(|| panic!("Node {name} is not in the graph"))
     }
 
     //TODO iterate over references
-    pub fn all_links(&self) -> impl Iterator<Item = Link> + '_ {
+    pub fn all_links(&self) -> impl ExactSizeIterator<Item = Link> + '_ {
         AllLinkIter::new(self)
     }
 
-    pub fn all_nodes(&self) -> impl Iterator<Item = &Node> + '_ {
+    pub fn all_nodes(&self) -> impl ExactSizeIterator<Item = &Node> + '_ {
         self.nodes.iter()
     }
 
+    //length-weighted mean coverage of the nodes satisfying the predicate (on node id)
+    //nodes without coverage are ignored, 0 if none of the nodes has coverage
+    pub fn weighted_mean_cov(&self, predicate: impl Fn(usize) -> bool) -> f64 {
+        let mut total_len = 0;
+        let mut total_cov = 0.;
+        for (node_id, n) in self.nodes.iter().enumerate() {
+            if n.has_coverage() && predicate(node_id) {
+                total_len += n.length;
+                total_cov += n.coverage * (n.length as f64);
+            }
+        }
+        if total_len == 0 {
+            return 0.;
+        }
+        total_cov / total_len as f64
+    }
+
     //TODO iterate over references
-    pub fn all_vertices(&self) -> impl Iterator<Item = Vertex> + '_ {
+    pub fn all_vertices(&self) -> impl ExactSizeIterator<Item = Vertex> + '_ {
         VertexIter::new(self)
     }
 
@@ -532,13 +1500,13 @@ impl Graph {
     }
 
     pub fn link_cnt(&self) -> usize {
-        self.all_links().count()
+        self.link_cnt
     }
 
     //note that the graph supports multi-edges,
     // if they are present returns only the first one
     pub fn connector(&self, v: Vertex, w: Vertex) -> Option<Link> {
-        self.outgoing_edges(v).into_iter().find(|&l| l.end == w)
+        self.outgoing_edges(v).iter().copied().find(|&l| l.end == w)
     }
 
     pub fn v_str_format(&self, v: Vertex, gaf: bool) -> String {
@@ -558,6 +1526,87 @@ impl Graph {
     }
 }
 
+fn complement(c: char) -> char {
+    match c {
+        'A' => 'T',
+        'C' => 'G',
+        'G' => 'C',
+        'T' => 'A',
+        'a' => 't',
+        'c' => 'g',
+        'g' => 'c',
+        't' => 'a',
+        'R' => 'Y',
+        'Y' => 'R',
+        'K' => 'M',
+        'M' => 'K',
+        'B' => 'V',
+        'V' => 'B',
+        'D' => 'H',
+        'H' => 'D',
+        'r' => 'y',
+        'y' => 'r',
+        'k' => 'm',
+        'm' => 'k',
+        'b' => 'v',
+        'v' => 'b',
+        'd' => 'h',
+        'h' => 'd',
+        //N, S, W are self-complementary
+        _ => c,
+    }
+}
+
+//(length, operation) pairs, None if CIGAR is malformed
+fn cigar_ops(cigar: &str) -> Option<Vec<(usize, char)>> {
+    let mut ops = Vec::new();
+    let mut len_start = 0;
+    for (i, c) in cigar.char_indices() {
+        if !c.is_ascii_digit() {
+            ops.push((cigar[len_start..i].parse().ok()?, c));
+            len_start = i + 1;
+        }
+    }
+    if ops.is_empty() || len_start != cigar.len() {
+        return None;
+    }
+    Some(ops)
+}
+
+//CIGAR of the reverse-complement link (operations reversed, insertions and deletions swapped)
+pub fn reverse_cigar(cigar: &str) -> String {
+    match cigar_ops(cigar) {
+        None => String::from(cigar),
+        Some(ops) => ops
+            .iter()
+            .rev()
+            .map(|&(len, op)| {
+                let op = match op {
+                    'I' => 'D',
+                    'D' => 'I',
+                    op => op,
+                };
+                format!("{len}{op}")
+            })
+            .collect(),
+    }
+}
+
+pub fn reverse_complement(seq: &str) -> String {
+    seq.chars().rev().map(complement).collect()
+}
+
+//path printing options (see Path::print_format_ext)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PathFormat {
+    //GAF ([<>]<name1>)+ format instead of comma-separated <name1>[+-]
+    pub gaf: bool,
+    //start of circular paths is repeated in the end (only in GAF format)
+    pub repeat_circular_start: bool,
+    //gaps are printed as [N<gap_size>], without the info
+    pub scaffold_gaps: bool,
+}
+
 #[derive(Clone, Debug, PartialEq, PartialOrd)]
 pub struct GapInfo {
     pub start: Vertex,
@@ -622,7 +1671,74 @@ impl GeneralizedLink {
     }
 }
 
-#[derive(Clone)]
+//error of parsing a path from its (GAF-style) string representation
+#[derive(Debug, PartialEq)]
+pub enum PathParseError {
+    Empty,
+    InvalidToken(String),
+    UnknownNode(String),
+    NoLink(String, String),
+    RepeatedNode(String),
+}
+
+impl fmt::Display for PathParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "empty path"),
+            Self::InvalidToken(t) => write!(f, "invalid path token '{t}'"),
+            Self::UnknownNode(name) => write!(f, "unknown node '{name}'"),
+            Self::NoLink(v, w) => write!(f, "no link between {v} and {w}"),
+            Self::RepeatedNode(name) => write!(f, "node '{name}' used multiple times"),
+        }
+    }
+}
+
+impl std::error::Error for PathParseError {}
+
+//error of concatenating paths (see Path::try_concat)
+#[derive(Debug, PartialEq)]
+pub enum ConcatError {
+    NoLink(String, String),
+    SharedNode(String),
+}
+
+impl fmt::Display for ConcatError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::NoLink(v, w) => write!(f, "no link between {v} and {w}"),
+            Self::SharedNode(name) => write!(f, "node '{name}' is present in both paths"),
+        }
+    }
+}
+
+impl std::error::Error for ConcatError {}
+
+//inconsistency of the path with the graph (see Path::validate)
+#[derive(Debug, PartialEq)]
+pub enum PathError {
+    //node id is out of the graph range
+    UnknownNode(usize),
+    //link at given position doesn't join the consecutive path vertices
+    Detached(usize),
+    //link (start, end, overlap) is absent from the graph
+    MissingLink(String, String, usize),
+}
+
+impl fmt::Display for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::UnknownNode(id) => write!(f, "unknown node id {id}"),
+            Self::Detached(i) => write!(f, "link #{i} doesn't join consecutive path vertices"),
+            Self::MissingLink(v, w, ovl) => {
+                write!(f, "no link between {v} and {w} with overlap {ovl}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for PathError {}
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct Path {
     v_storage: Vec<Vertex>,
     l_storage: Vec<GeneralizedLink>,
@@ -685,12 +1801,17 @@ impl Path {
     }
 
     //TODO rename to rc?:write!
-    pub fn reverse_complement(self) -> Path {
-        //TODO optimize since consuming self
-        Path {
-            v_storage: self.v_storage.iter().rev().map(|v| v.rc()).collect(),
-            l_storage: self.l_storage.iter().rev().map(|l| l.rc()).collect(),
+    pub fn reverse_complement(mut self) -> Path {
+        //reusing storage since consuming self
+        self.v_storage.reverse();
+        for v in self.v_storage.iter_mut() {
+            *v = v.rc();
+        }
+        self.l_storage.reverse();
+        for l in self.l_storage.iter_mut() {
+            *l = l.rc();
         }
+        self
     }
 
     pub fn trim(&mut self, step: usize) {
@@ -703,16 +1824,35 @@ impl Path {
         }
     }
 
+    //trims the path so that it ends with v
+    //returns false (and keeps path intact) if v is not in the path
+    //NB: trimming to the start vertex leaves single-vertex path
     pub fn trim_to(&mut self, v: &Vertex) -> bool {
-        //TODO optimize
-        if self.v_storage.contains(v) {
-            while self.v_storage.last().unwrap() != v {
-                self.v_storage.pop();
-                self.l_storage.pop();
+        match self.v_storage.iter().position(|x| x == v) {
+            Some(idx) => {
+                self.v_storage.truncate(idx + 1);
+                self.l_storage.truncate(idx);
+                true
             }
-            return true;
+            None => false,
+        }
+    }
+
+    //splits into the prefix ending at position idx and the remaining suffix
+    //None if the suffix would be empty
+    pub fn split_after(&self, idx: usize) -> Option<(Path, Path)> {
+        if idx + 1 >= self.len() {
+            return None;
         }
-        false
+        let prefix = Path {
+            v_storage: self.v_storage[..=idx].to_vec(),
+            l_storage: self.l_storage[..idx].to_vec(),
+        };
+        let suffix = Path {
+            v_storage: self.v_storage[(idx + 1)..].to_vec(),
+            l_storage: self.l_storage[(idx + 1)..].to_vec(),
+        };
+        Some((prefix, suffix))
     }
 
     //TODO rename
@@ -755,6 +1895,25 @@ impl Path {
         }
     }
 
+    //appends the link connecting the end of the path to the start of other, followed by other
+    //path is left unchanged if there is no such link or if the paths share nodes
+    pub fn try_concat(&mut self, other: &Path, g: &Graph) -> Result<(), ConcatError> {
+        let l = g
+            .connector(self.end(), other.start())
+            .ok_or_else(|| ConcatError::NoLink(g.v_str(self.end()), g.v_str(other.start())))?;
+        if let Some(v) = other.v_storage.iter().find(|v| self.in_path(v.node_id)) {
+            return Err(ConcatError::SharedNode(String::from(g.name(v.node_id))));
+        }
+        self.append(l);
+        self.merge_in(other.clone());
+        Ok(())
+    }
+
+    //true if some link connects the end of the path back to its start
+    pub fn is_circular(&self, g: &Graph) -> bool {
+        g.connector(self.end(), self.start()).is_some()
+    }
+
     pub fn print(&self, g: &Graph) -> String {
         //self.v_storage.iter().map(|&v| g.v_str(v)).join(",")
         self.print_format(g, false)
@@ -766,6 +1925,17 @@ impl Path {
     //}
 
     pub fn print_format(&self, g: &Graph, gaf: bool) -> String {
+        self.print_format_ext(
+            g,
+            PathFormat {
+                gaf,
+                ..Default::default()
+            },
+        )
+    }
+
+    pub fn print_format_ext(&self, g: &Graph, format: PathFormat) -> String {
+        let gaf = format.gaf;
         let delim = if gaf { "" } else { "," };
         let mut ans = String::new();
         for (i, &v) in self.v_storage.iter().enumerate() {
@@ -773,7 +1943,11 @@ impl Path {
                 match &self.l_storage[i - 1] {
                     GeneralizedLink::GAP(gap_info) => {
                         ans += delim;
-                        ans += &format!("[N{}N:{}]", gap_info.gap_size, gap_info.info);
+                        if format.scaffold_gaps {
+                            ans += &format!("[N{}]", gap_info.gap_size);
+                        } else {
+                            ans += &format!("[N{}N:{}]", gap_info.gap_size, gap_info.info);
+                        }
                     }
                     GeneralizedLink::LINK(_) => {}
                 };
@@ -783,15 +1957,195 @@ impl Path {
             }
             ans += &g.v_str_format(v, gaf);
         }
+        if gaf && format.repeat_circular_start && self.is_circular(g) {
+            ans += &g.v_str_format(self.start(), true);
+        }
         ans
     }
 
+    //inverse of print_format(g, true): '>name'/'<name' tokens, possibly separated by gaps '[N<size>N:<info>]'
+    //consecutive vertices have to be connected by a graph link
+    pub fn parse_gaf(g: &Graph, s: &str) -> Result<Path, PathParseError> {
+        let invalid = |t: &str| PathParseError::InvalidToken(String::from(t));
+        let mut path: Option<Path> = None;
+        let mut gap: Option<(i64, String)> = None;
+        let mut rest = s.trim();
+        while !rest.is_empty() {
+            if rest.starts_with('[') {
+                let end = rest.find(']').ok_or_else(|| invalid(rest))? + 1;
+                let token = &rest[..end];
+                rest = &rest[end..];
+                if gap.is_some() || path.is_none() {
+                    return Err(invalid(token));
+                }
+                let (size, info) = token[1..(end - 1)]
+                    .split_once(':')
+                    .ok_or_else(|| invalid(token))?;
+                let size = size
+                    .strip_prefix('N')
+                    .and_then(|x| x.strip_suffix('N'))
+                    .and_then(|x| x.parse().ok())
+                    .ok_or_else(|| invalid(token))?;
+                gap = Some((size, String::from(info)));
+                continue;
+            }
+            let direction = match rest.chars().next() {
+                Some('>') => Direction::FORWARD,
+                Some('<') => Direction::REVERSE,
+                _ => return Err(invalid(rest)),
+            };
+            let end = rest[1..]
+                .find(['>', '<', '['])
+                .map_or(rest.len(), |i| i + 1);
+            let name = &rest[1..end];
+            rest = &rest[end..];
+            let v = Vertex {
+                node_id: g
+                    .try_name2id(name)
+                    .ok_or_else(|| PathParseError::UnknownNode(String::from(name)))?,
+                direction,
+            };
+            match path.as_mut() {
+                None => path = Some(Path::new(v)),
+                Some(p) => {
+                    if p.in_path(v.node_id) {
+                        return Err(PathParseError::RepeatedNode(String::from(name)));
+                    }
+                    match gap.take() {
+                        Some((gap_size, info)) => p.append_general(GeneralizedLink::GAP(GapInfo {
+                            start: p.end(),
+                            end: v,
+                            gap_size,
+                            info,
+                        })),
+                        None => {
+                            p.append(g.connector(p.end(), v).ok_or_else(|| {
+                                PathParseError::NoLink(g.v_str(p.end()), g.v_str(v))
+                            })?)
+                        }
+                    }
+                }
+            }
+        }
+        if gap.is_some() {
+            return Err(invalid(s));
+        }
+        path.ok_or(PathParseError::Empty)
+    }
+
+    //GFA W-line(s) for the path, walk is formatted as in GAF
+    //gaps can't be represented within a walk, so gapped path is split into several lines
+    //(separated by newline), with start/end coordinates accounting for gap sizes
+    pub fn to_walk_line(&self, g: &Graph, sample: &str, hap: usize, seqid: &str) -> String {
+        let walk_line = |start: i64, end: i64, vertices: &[Vertex]| {
+            format!(
+                "W\t{}\t{}\t{}\t{}\t{}\t{}",
+                sample,
+                hap,
+                seqid,
+                start,
+                end,
+                vertices.iter().map(|&v| g.v_str_format(v, true)).join("")
+            )
+        };
+        let mut lines = Vec::new();
+        let mut seg_start = 0;
+        let mut seg_first = 0;
+        let mut pos = g.vertex_length(self.v_storage[0]) as i64;
+        for (i, l) in self.l_storage.iter().enumerate() {
+            if let GeneralizedLink::GAP(_) = l {
+                lines.push(walk_line(seg_start, pos, &self.v_storage[seg_first..=i]));
+                seg_start = pos - l.overlap();
+                seg_first = i + 1;
+            }
+            pos += g.vertex_length(self.v_storage[i + 1]) as i64 - l.overlap();
+        }
+        lines.push(walk_line(seg_start, pos, &self.v_storage[seg_first..]));
+        lines.join("\n")
+    }
+
+    //start positions of the path vertices within the path sequence
+    //(accounting for overlaps and gaps of the links)
+    pub fn node_offsets<'a>(&'a self, g: &'a Graph) -> impl Iterator<Item = i64> + 'a {
+        let steps = self
+            .v_storage
+            .iter()
+            .zip(&self.l_storage)
+            .map(|(&v, l)| g.vertex_length(v) as i64 - l.overlap());
+        std::iter::once(0).chain(steps.scan(0, |start, step| {
+            *start += step;
+            Some(*start)
+        }))
+    }
+
+    //length of the spelled sequence: overlaps are subtracted and gap sizes are added
+    //(used for all the length-based reports and thresholds, e.g. haplo-path N50 and max path length)
     pub fn total_length(&self, g: &Graph) -> usize {
-        let mut tot_length = g.vertex_length(self.v_storage[0]) as i64;
+        let last_offset = self.node_offsets(g).last().unwrap();
+        (last_offset + g.vertex_length(self.end()) as i64) as usize
+    }
+
+    //sum of the node lengths, ignoring overlaps and gaps
+    //(matches total_length for paths with blunt joins and without gaps)
+    pub fn raw_length(&self, g: &Graph) -> usize {
+        self.v_storage.iter().map(|&v| g.vertex_length(v)).sum()
+    }
+
+    //index of the first link which either doesn't join the consecutive path vertices
+    //or (if not a gap) is absent from the graph (e.g. has different overlap size)
+    pub fn first_inconsistent_link(&self, g: &Graph) -> Option<usize> {
+        self.l_storage.iter().enumerate().position(|(i, gl)| {
+            gl.start() != self.v_storage[i]
+                || gl.end() != self.v_storage[i + 1]
+                || match gl {
+                    GeneralizedLink::LINK(l) => !g.outgoing_edges(l.start).contains(l),
+                    GeneralizedLink::GAP(_) => false,
+                }
+        })
+    }
+
+    //non-panicking check that path vertices belong to the graph and that
+    //all (non-gap) links join consecutive vertices and are present in the graph
+    pub fn validate(&self, g: &Graph) -> Result<(), PathError> {
+        if let Some(v) = self.v_storage.iter().find(|v| v.node_id >= g.node_cnt()) {
+            return Err(PathError::UnknownNode(v.node_id));
+        }
+        let Some(i) = self.first_inconsistent_link(g) else {
+            return Ok(());
+        };
+        match &self.l_storage[i] {
+            GeneralizedLink::LINK(l)
+                if l.start == self.v_storage[i] && l.end == self.v_storage[i + 1] =>
+            {
+                Err(PathError::MissingLink(
+                    g.v_str(l.start),
+                    g.v_str(l.end),
+                    l.overlap,
+                ))
+            }
+            _ => Err(PathError::Detached(i)),
+        }
+    }
+
+    //overlaps are trimmed from the beginning of the next node,
+    //gaps are filled with Ns (or trimmed if gap size is negative)
+    //returns None if any of the nodes lacks stored sequence
+    pub fn sequence(&self, g: &Graph) -> Option<String> {
+        let mut seq = g.vertex_sequence(self.start())?;
         for l in &self.l_storage {
-            tot_length += g.vertex_length(l.end()) as i64 - l.overlap();
+            let next = g.vertex_sequence(l.end())?;
+            let ovl = l.overlap();
+            if ovl >= 0 {
+                //overlap is in bases, i.e. bytes of the ASCII sequence (checked on load)
+                let next = next.as_bytes();
+                let trimmed = &next[std::cmp::min(ovl as usize, next.len())..];
+                seq.push_str(&String::from_utf8_lossy(trimmed));
+            } else {
+                seq.extend(std::iter::repeat_n('N', (-ovl) as usize));
+                seq.push_str(&next);
+            }
         }
-        tot_length as usize
+        Some(seq)
     }
 
     pub fn check_subpath(&self, other: &Path, start_pos: usize) -> bool {
@@ -1,6 +1,15 @@
+use crate::graph::*;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
 pub mod dfs;
 pub mod scc;
+pub mod stats;
 pub mod superbubble;
+pub mod wcc;
+
+pub use stats::{coverage_histogram, estimate_haploid_peak, GraphStats};
+pub use wcc::weakly_connected;
 
 pub fn only_or_none<T>(mut iter: impl Iterator<Item = T>) -> Option<T> {
     let e = iter.next()?;
@@ -9,3 +18,180 @@ pub fn only_or_none<T>(mut iter: impl Iterator<Item = T>) -> Option<T> {
         _ => None,
     }
 }
+
+//Longest (by total sequence length) path from one vertex to another,
+// which does not visit any node twice (in either orientation).
+//Exhaustive search, so number of nodes in the path is bounded by max_nodes
+// and the number of path extensions considered is bounded by max_steps to stay tractable.
+//Returns None if 'to' is unreachable within the node bound or if max_steps is exceeded.
+pub fn longest_simple_path(
+    g: &Graph,
+    from: Vertex,
+    to: Vertex,
+    max_nodes: usize,
+    max_steps: usize,
+) -> Option<Path> {
+    if max_nodes == 0 {
+        return None;
+    }
+    let mut best: Option<(i64, Path)> = None;
+    let mut path = Path::new(from);
+    let mut length = g.vertex_length(from) as i64;
+    //for every path vertex: index of the next outgoing link to try
+    // and the length added by the link leading to the vertex
+    let mut stack = vec![(0, 0)];
+    let mut steps = 0;
+    while let Some((next_link, added_len)) = stack.last_mut() {
+        let v = path.end();
+        let links = g.outgoing_edges(v);
+        if v == to || path.len() == max_nodes || *next_link == links.len() {
+            if v == to && best.as_ref().is_none_or(|(best_len, _)| length > *best_len) {
+                best = Some((length, path.clone()));
+            }
+            length -= *added_len;
+            stack.pop();
+            if !stack.is_empty() {
+                path.trim(1);
+            }
+            continue;
+        }
+        let l = links[*next_link];
+        *next_link += 1;
+        if path.in_path(l.end.node_id) {
+            continue;
+        }
+        steps += 1;
+        if steps > max_steps {
+            return None;
+        }
+        let l_len = g.vertex_length(l.end) as i64 - l.overlap as i64;
+        length += l_len;
+        path.append(l);
+        stack.push((0, l_len));
+    }
+    best.map(|(_, p)| p)
+}
+
+//All paths from one vertex to another, which do not visit any node twice (in either orientation)
+// and have total sequence length of at most max_len.
+//Search stops after max_count paths are found, returned paths are sorted by length.
+//Iterative (explicit stack) depth-first enumeration, so long paths are handled.
+pub fn simple_paths(
+    g: &Graph,
+    from: Vertex,
+    to: Vertex,
+    max_len: usize,
+    max_count: usize,
+) -> Vec<Path> {
+    let length = g.vertex_length(from);
+    if length > max_len || max_count == 0 {
+        return Vec::new();
+    }
+    let mut path = Path::new(from);
+    if from == to {
+        return vec![path];
+    }
+    let mut found = Vec::new();
+    //length of the path up to the vertex, its outgoing links and the index of the next one to consider
+    let mut stack = vec![(length, g.outgoing_edges(from), 0)];
+
+    while let Some((length, outgoing, i)) = stack.last_mut() {
+        if found.len() == max_count {
+            break;
+        }
+        if *i == outgoing.len() {
+            stack.pop();
+            if !stack.is_empty() {
+                path.trim(1);
+            }
+            continue;
+        }
+        let l = outgoing[*i];
+        *i += 1;
+        if path.in_path(l.end.node_id) {
+            continue;
+        }
+        let ext_length = *length + g.vertex_length(l.end) - l.overlap;
+        if ext_length > max_len {
+            continue;
+        }
+        path.append(l);
+        if l.end == to {
+            found.push((ext_length, path.clone()));
+            path.trim(1);
+        } else {
+            stack.push((ext_length, g.outgoing_edges(l.end), 0));
+        }
+    }
+    found.sort_by_key(|(length, _)| *length);
+    found.into_iter().map(|(_, p)| p).collect()
+}
+
+//Longest (by total sequence length) path starting from the vertex,
+// computed by dynamic programming in topological order of the vertices reachable from it.
+//Returns None if a cycle is reachable (or if some node is reachable in both orientations).
+pub fn longest_dag_path(g: &Graph, start: Vertex) -> Option<Path> {
+    let mut dfs = dfs::DFS::new_forward(g);
+    dfs.run_from(start);
+    let reachable = dfs.visited();
+    if reachable.iter().any(|v| reachable.contains(&v.rc())) {
+        return None;
+    }
+    let in_sccs = scc::strongly_connected(g)
+        .into_iter()
+        .flatten()
+        .collect::<HashSet<Vertex>>();
+    if reachable.iter().any(|v| in_sccs.contains(v)) {
+        return None;
+    }
+
+    //vertex to length of the longest path ending in it and the last link of that path
+    let mut best: HashMap<Vertex, (usize, Option<Link>)> = HashMap::new();
+    best.insert(start, (g.vertex_length(start), None));
+    let mut end = start;
+    for &v in dfs.exit_order().iter().rev() {
+        let length = best[&v].0;
+        if length > best[&end].0 {
+            end = v;
+        }
+        for &l in g.outgoing_edges(v) {
+            let ext_length = length + g.vertex_length(l.end) - l.overlap;
+            if best.get(&l.end).is_none_or(|&(x, _)| ext_length > x) {
+                best.insert(l.end, (ext_length, Some(l)));
+            }
+        }
+    }
+
+    let mut rc_p = Path::new(end.rc());
+    while let Some(l) = best[&rc_p.end().rc()].1 {
+        rc_p.append(l.rc());
+    }
+    Some(rc_p.reverse_complement())
+}
+
+//Length of the shortest walk from the end of 'from' to the end of 'to'
+// (sum of link-adjusted lengths enode_len - overlap over the traversed links, 0 if from == to).
+//Dijkstra search is not continued beyond 'max', returns None if 'to' is not reached within it.
+pub fn shortest_distance(g: &Graph, from: Vertex, to: Vertex, max: usize) -> Option<usize> {
+    let mut dists: HashMap<Vertex, usize> = HashMap::new();
+    let mut queue = BinaryHeap::new();
+    dists.insert(from, 0);
+    queue.push(Reverse((0, from)));
+    while let Some(Reverse((d, v))) = queue.pop() {
+        if v == to {
+            return Some(d);
+        }
+        if d > dists[&v] {
+            continue;
+        }
+        for &l in g.outgoing_edges(v) {
+            let next_d = d + g.vertex_length(l.end).saturating_sub(l.overlap);
+            if next_d > max || dists.get(&l.end).is_some_and(|&x| x <= next_d) {
+                continue;
+            }
+            dists.insert(l.end, next_d);
+            queue.push(Reverse((next_d, l.end)));
+        }
+    }
+    None
+}
@@ -10,7 +10,77 @@ pub enum TraversalDirection {
     //UNDIRECTED,
 }
 
-//TODO replace sets with arrays
+//set of vertices backed by a flag vector indexed by node_id * 2 + direction
+//inserted vertices are remembered, so clearing takes time proportional to the set size
+//and the allocation can be reused across searches
+#[derive(Default, Clone)]
+pub struct VertexSet {
+    flags: Vec<bool>,
+    inserted: Vec<Vertex>,
+}
+
+impl VertexSet {
+    pub fn new() -> VertexSet {
+        Self::default()
+    }
+
+    //preallocated for all vertices of the graph
+    pub fn for_graph(g: &Graph) -> VertexSet {
+        VertexSet {
+            flags: vec![false; g.node_cnt() * 2],
+            inserted: Vec::new(),
+        }
+    }
+
+    fn idx(v: Vertex) -> usize {
+        v.node_id * 2 + v.direction as usize
+    }
+
+    pub fn contains(&self, v: &Vertex) -> bool {
+        self.flags.get(Self::idx(*v)).copied().unwrap_or(false)
+    }
+
+    //returns true if vertex was not present
+    pub fn insert(&mut self, v: Vertex) -> bool {
+        let i = Self::idx(v);
+        if i >= self.flags.len() {
+            self.flags.resize(i + 1, false);
+        }
+        if self.flags[i] {
+            return false;
+        }
+        self.flags[i] = true;
+        self.inserted.push(v);
+        true
+    }
+
+    pub fn clear(&mut self) {
+        for v in self.inserted.drain(..) {
+            self.flags[Self::idx(v)] = false;
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.inserted.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inserted.is_empty()
+    }
+
+    //in order of insertion
+    pub fn iter(&self) -> impl Iterator<Item = Vertex> + '_ {
+        self.inserted.iter().copied()
+    }
+}
+
+impl Extend<Vertex> for VertexSet {
+    fn extend<T: IntoIterator<Item = Vertex>>(&mut self, iter: T) {
+        for v in iter {
+            self.insert(v);
+        }
+    }
+}
 
 //TODO pass functions giving neighbour iterators to allow more flexibility (directions, subgraphs, length boundaries, etc)
 //TODO use within trio_walk
@@ -18,7 +88,7 @@ pub struct DFS<'a> {
     g: &'a Graph,
     direction: TraversalDirection,
     visit_f: Option<&'a dyn Fn(Vertex) -> bool>,
-    blocked: HashSet<Vertex>,
+    blocked: VertexSet,
     boundary: HashSet<Vertex>,
     tout: Vec<Vertex>,
     node_len_thr: usize,
@@ -34,7 +104,8 @@ impl<'a> DFS<'a> {
             g,
             direction,
             visit_f,
-            blocked: HashSet::new(),
+            //grows on demand, reuse a cleared set via set_blocked/take_blocked for repeated local searches
+            blocked: VertexSet::new(),
             boundary: HashSet::new(),
             tout: Vec::new(),
             node_len_thr: usize::MAX,
@@ -50,7 +121,7 @@ impl<'a> DFS<'a> {
     }
 
     //TODO make consume self and return new DFS
-    pub fn set_blocked(&mut self, blocked: HashSet<Vertex>) {
+    pub fn set_blocked(&mut self, blocked: VertexSet) {
         self.blocked = blocked;
     }
 
@@ -67,29 +138,37 @@ impl<'a> DFS<'a> {
     //TODO use iterators
     fn neighbors(&self, v: Vertex) -> Vec<Vertex> {
         match self.direction {
-            TraversalDirection::FORWARD => self.g.outgoing_edges(v).iter().map(|l| l.end).collect(),
-            TraversalDirection::REVERSE => {
-                self.g.incoming_edges(v).iter().map(|l| l.start).collect()
-            }
+            TraversalDirection::FORWARD => self.g.successors(v).collect(),
+            TraversalDirection::REVERSE => self.g.predecessors(v).collect(),
         }
     }
 
+    //iterative (explicit stack) to handle deep graphs, vertex exit order is the same as for recursive version
     pub fn run_from(&mut self, v: Vertex) {
         assert!(!self.blocked.contains(&v));
         self.blocked.insert(v);
-
-        for w in self.neighbors(v) {
+        //vertex, its neighbors and the index of the next neighbor to consider
+        let mut stack = vec![(v, self.neighbors(v), 0)];
+
+        while let Some((v, neighbors, i)) = stack.last_mut() {
+            if *i == neighbors.len() {
+                self.tout.push(*v);
+                stack.pop();
+                continue;
+            }
+            let w = neighbors[*i];
+            *i += 1;
             if !self.blocked.contains(&w)
                 && (self.visit_f.is_none() || self.visit_f.unwrap()(w))
                 && self.g.vertex_length(w) < self.node_len_thr
             {
-                self.run_from(w);
+                self.blocked.insert(w);
+                let w_neighbors = self.neighbors(w);
+                stack.push((w, w_neighbors, 0));
             } else {
                 self.boundary.insert(w);
             }
         }
-
-        self.tout.push(v);
     }
 
     //TODO maybe rename into topsort?
@@ -104,7 +183,7 @@ impl<'a> DFS<'a> {
     }
 
     //includes visited and initially blocked
-    pub fn take_blocked(self) -> HashSet<Vertex> {
+    pub fn take_blocked(self) -> VertexSet {
         self.blocked
     }
 
@@ -187,7 +266,7 @@ impl ShortNodeComponent {
 
         //if not a source consider it's incoming edges
         if !is_source {
-            if g.incoming_edge_cnt(v) == 0 {
+            if g.in_degree(v) == 0 {
                 assert!(g.vertex_length(v) < length_threshold);
                 self.has_deadends = true;
             }
@@ -200,11 +279,11 @@ impl ShortNodeComponent {
 
         //if not a sink consider outgoing edges
         if !is_sink {
-            if g.outgoing_edge_cnt(v) == 0 {
+            if g.out_degree(v) == 0 {
                 assert!(g.vertex_length(v) < length_threshold);
                 self.has_deadends = true;
             }
-            for o_l in g.outgoing_edges(v) {
+            for &o_l in g.outgoing_edges(v) {
                 if o_l != l {
                     self.consider(g, o_l.end, o_l, length_threshold);
                 }
@@ -233,7 +312,7 @@ impl ShortNodeComponent {
             inner: HashSet::new(),
         };
 
-        for o_l in g.outgoing_edges(v) {
+        for &o_l in g.outgoing_edges(v) {
             component.consider(g, o_l.end, o_l, length_threshold);
         }
         component
@@ -263,13 +342,13 @@ impl ShortNodeComponent {
             let mut component = ShortNodeComponent {
                 sources: HashSet::new(),
                 sinks: HashSet::new(),
-                has_deadends: (g.outgoing_edge_cnt(v) == 0 || g.incoming_edge_cnt(v) == 0),
+                has_deadends: (g.out_degree(v) == 0 || g.in_degree(v) == 0),
                 inner: std::iter::once(v).collect(),
             };
             for i_l in g.incoming_edges(v) {
                 component.consider(g, i_l.start, i_l, length_threshold);
             }
-            for o_l in g.outgoing_edges(v) {
+            for &o_l in g.outgoing_edges(v) {
                 component.consider(g, o_l.end, o_l, length_threshold);
             }
             component
@@ -5,17 +5,25 @@ use itertools::Itertools;
 use log::debug;
 use std::collections::{HashMap, HashSet};
 
-//Implementing Kosaraju-Sharir algorithm
 //'trivial' SCCs of individual vertices are not reported
 //NB. Loop of single vertex is considered 'NON-trivial'
+//Result is cached within the graph until it is modified
 pub fn strongly_connected(graph: &Graph) -> Vec<Vec<Vertex>> {
+    graph
+        .scc_cache()
+        .get_or_init(|| compute_strongly_connected(graph))
+        .clone()
+}
+
+//Implementing Kosaraju-Sharir algorithm
+fn compute_strongly_connected(graph: &Graph) -> Vec<Vec<Vertex>> {
     let mut non_trivial_sccs: Vec<Vec<Vertex>> = Vec::new();
     let is_loop = |v: Vertex| graph.outgoing_edges(v).iter().any(|l| l.end == v);
 
     // run DFS on direct edges
     let mut dfs = dfs::DFS::new_forward(graph);
     dfs.run();
-    let mut used: HashSet<Vertex> = HashSet::new();
+    let mut used = dfs::VertexSet::for_graph(graph);
     // consider vertices in decreasing order of exit times (latest exit times first)
     for &v in dfs.exit_order().iter().rev() {
         if !used.contains(&v) {
@@ -41,6 +49,114 @@ pub fn strongly_connected(graph: &Graph) -> Vec<Vec<Vertex>> {
     non_trivial_sccs
 }
 
+fn canonical(l: Link) -> Link {
+    if l <= l.rc() {
+        l
+    } else {
+        l.rc()
+    }
+}
+
+//Heuristic (NOT minimum) feedback link set.
+//Within every non-trivial SCC runs DFS restricted to the links inside the component
+// and collects the 'back' links (leading to a vertex on the current DFS stack).
+//Since removing a link also removes its reverse-complement,
+// only one of the SCC and its reverse-complement is processed,
+// and links with already removed reverse-complement are ignored (self-conjugate SCCs).
+//Removal of reported links makes every component (and so the graph) acyclic.
+//Links are reported once (in canonical orientation).
+pub fn feedback_link_set(g: &Graph) -> Vec<Link> {
+    let mut vertex_2_scc = HashMap::new();
+    for (scc_id, vertices) in strongly_connected(g).iter().enumerate() {
+        for &v in vertices {
+            vertex_2_scc.insert(v, scc_id);
+        }
+    }
+
+    let mut feedback = HashSet::new();
+    let mut processed_sccs = HashSet::new();
+    let mut visited: HashSet<Vertex> = HashSet::new();
+    let mut on_stack: HashSet<Vertex> = HashSet::new();
+
+    let mut starts = vertex_2_scc.keys().copied().collect_vec();
+    starts.sort();
+    for s in starts {
+        let scc_id = vertex_2_scc[&s];
+        if visited.contains(&s) || processed_sccs.contains(&scc_id) {
+            continue;
+        }
+        processed_sccs.insert(scc_id);
+        processed_sccs.insert(vertex_2_scc[&s.rc()]);
+
+        let inner_links = |v: Vertex| {
+            g.outgoing_edges(v)
+                .iter()
+                .copied()
+                .filter(|l| vertex_2_scc.get(&l.end) == Some(&scc_id))
+                .collect_vec()
+        };
+        visited.insert(s);
+        on_stack.insert(s);
+        let mut stack = vec![(s, inner_links(s), 0)];
+        while let Some((v, links, pos)) = stack.last_mut() {
+            if *pos == links.len() {
+                on_stack.remove(v);
+                stack.pop();
+                continue;
+            }
+            let l = links[*pos];
+            *pos += 1;
+            if feedback.contains(&canonical(l)) {
+                continue;
+            }
+            if on_stack.contains(&l.end) {
+                feedback.insert(canonical(l));
+            } else if visited.insert(l.end) {
+                on_stack.insert(l.end);
+                stack.push((l.end, inner_links(l.end), 0));
+            }
+        }
+    }
+    let mut feedback = feedback.into_iter().collect_vec();
+    feedback.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    feedback
+}
+
+//Node-level view of non-trivial SCCs (more than one node or a self-loop).
+//Components are reported once for an SCC and its reverse-complement,
+//node ids within components are sorted, components are sorted by their first node.
+pub fn nontrivial_sccs(g: &Graph) -> Vec<Vec<usize>> {
+    strongly_connected(g)
+        .iter()
+        .map(|vertices| {
+            vertices
+                .iter()
+                .map(|v| v.node_id)
+                .sorted()
+                .dedup()
+                .collect_vec()
+        })
+        .sorted()
+        .dedup()
+        .collect()
+}
+
+//one line per component: id, node count, total length and comma-separated node names
+pub fn format_sccs(g: &Graph, sccs: &[Vec<usize>]) -> String {
+    sccs.iter()
+        .enumerate()
+        .map(|(i, nodes)| {
+            format!(
+                "scc_{}\t{}\t{}\t{}\n",
+                i,
+                nodes.len(),
+                nodes.iter().map(|&n| g.node(n).length).sum::<usize>(),
+                nodes.iter().map(|&n| &g.node(n).name).join(",")
+            )
+        })
+        .collect()
+}
+
 pub fn nodes_in_sccs(_g: &Graph, sccs: &[Vec<Vertex>]) -> HashSet<usize> {
     HashSet::from_iter(sccs.iter().flat_map(|comp| comp.iter().map(|v| v.node_id)))
 }
@@ -129,11 +245,13 @@ pub fn condensation(
                     scc_vertices.iter().map(|&w| graph.v_str(w)).join("")
                 )
             }
+            //total length of the member nodes (each counted once for self-conjugate scc)
             let length = scc_vertices
                 .iter()
-                .map(|w| graph.node(w.node_id).length)
-                .max()
-                .unwrap();
+                .map(|w| w.node_id)
+                .unique()
+                .map(|node_id| graph.node(node_id).length)
+                .sum();
             let name = format!(
                 "scc_{}_vcnt_{}_init_{}",
                 scc_id,
@@ -145,6 +263,8 @@ pub fn condensation(
                 name,
                 length,
                 coverage: 0.,
+                sequence: None,
+                tags: HashMap::new(),
             });
             update_old_2_new(scc_vertices, cnd_id);
         } else {
@@ -174,6 +294,18 @@ pub fn condensation(
     (condensation, old_2_new)
 }
 
+//Condensation of the graph with all (non-trivial) SCCs collapsed and loops removed,
+//so that the result is acyclic.
+//Also returns mapping of original node ids to the node ids of condensation.
+pub fn condense(graph: &Graph) -> (Graph, Vec<usize>) {
+    let sccs = strongly_connected(graph);
+    let (condensation, old_2_new) = condensation(graph, &sccs, true);
+    let node_mapping = (0..graph.node_cnt())
+        .map(|node_id| old_2_new[&Vertex::forward(node_id)].node_id)
+        .collect();
+    (condensation, node_mapping)
+}
+
 pub struct LocalizedTangle {
     pub entrance: Link,
     pub exit: Link,
@@ -184,12 +316,7 @@ pub struct LocalizedTangle {
 //subtracts minimal incoming overlap from every vertex and takes sum
 pub fn estimate_size_no_mult(tangle: &LocalizedTangle, g: &Graph) -> usize {
     let shortest_incoming_overlap = |v: Vertex| {
-        return g
-            .incoming_edges(v)
-            .iter()
-            .map(|l| l.overlap)
-            .min()
-            .unwrap_or(0);
+        return g.incoming_edges(v).map(|l| l.overlap).min().unwrap_or(0);
     };
 
     tangle
@@ -220,8 +347,8 @@ fn find_localized(g: &Graph, non_trivial_scc: &[Vertex]) -> Option<LocalizedTang
     //TODO think where this check should be performed
     //Also checking that entrance and exit
     //are the only ways to go from corresponding vertices
-    let entrance = only_or_none(g.outgoing_edges(entrance.start).into_iter())?;
-    let exit = only_or_none(g.incoming_edges(exit.end).into_iter())?;
+    let entrance = only_or_none(g.outgoing_edges(entrance.start).iter().copied())?;
+    let exit = only_or_none(g.incoming_edges(exit.end))?;
 
     //guard against potential tricky strand-switching case
     if entrance.start.node_id == exit.end.node_id {
@@ -0,0 +1,161 @@
+use super::{scc, wcc};
+use crate::graph::*;
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+
+//Basic summary of the graph
+pub struct GraphStats {
+    pub node_cnt: usize,
+    pub link_cnt: usize,
+    pub total_length: usize,
+    pub n50: usize,
+    //length-weighted, over the nodes with known coverage (None if there are no such nodes)
+    pub coverage_mean: Option<f64>,
+    pub coverage_median: Option<f64>,
+    pub wcc_cnt: usize,
+    //number of non-trivial strongly connected components (see scc::nontrivial_sccs)
+    pub scc_cnt: usize,
+    //number of nodes within non-trivial strongly connected components
+    pub scc_node_cnt: usize,
+}
+
+//minimal value such that values not smaller than it account for at least half of the total weight
+//(values sorted in decreasing order, weights are not required to be equal to values)
+fn weighted_median<T: Copy>(
+    mut value_weights: Vec<(T, usize)>,
+    cmp: impl Fn(&T, &T) -> std::cmp::Ordering,
+) -> Option<T> {
+    value_weights.sort_by(|a, b| cmp(&b.0, &a.0));
+    let total: usize = value_weights.iter().map(|&(_, w)| w).sum();
+    let mut acc = 0;
+    for (v, w) in value_weights {
+        acc += w;
+        if 2 * acc >= total {
+            return Some(v);
+        }
+    }
+    None
+}
+
+//0 if there are no lengths
+pub fn n50(lengths: impl Iterator<Item = usize>) -> usize {
+    weighted_median(lengths.map(|l| (l, l)).collect(), |a, b| a.cmp(b)).unwrap_or(0)
+}
+
+//minimal length such that lengths not smaller than it sum up to at least half of the genome size
+//None if the total length is below half of the genome size
+pub fn ng50(lengths: impl Iterator<Item = usize>, genome_size: usize) -> Option<usize> {
+    let mut lengths: Vec<usize> = lengths.collect();
+    lengths.sort_unstable_by(|a, b| b.cmp(a));
+    let mut acc = 0;
+    for l in lengths {
+        acc += l;
+        if 2 * acc >= genome_size {
+            return Some(l);
+        }
+    }
+    None
+}
+
+//length-weighted histogram of node coverage: (bin start, total length of nodes in the bin)
+//only non-empty bins are reported, in increasing order
+//(nodes without coverage or with negative/non-finite coverage are ignored)
+pub fn coverage_histogram(g: &Graph, bin_width: f64) -> Vec<(f64, usize)> {
+    assert!(bin_width > 0., "Bin width should be positive");
+    let mut weights: BTreeMap<u64, usize> = BTreeMap::new();
+    for n in g
+        .all_nodes()
+        .filter(|n| n.has_coverage() && n.coverage.is_finite() && n.coverage >= 0.)
+    {
+        //saturating conversion for extreme coverage to bin width ratios
+        let bin = (n.coverage / bin_width).floor() as u64;
+        *weights.entry(bin).or_insert(0) += n.length;
+    }
+    weights
+        .into_iter()
+        .map(|(i, w)| (i as f64 * bin_width, w))
+        .collect()
+}
+
+//primary (length-weighted) mode of the coverage distribution, reported as the center of the heaviest bin
+//bin width is taken relative to the median coverage, so the estimate doesn't depend on the sequencing depth
+//in diploid graphs the (haploid) heterozygous nodes are expected to dominate by length
+//None if there are no nodes with positive coverage
+pub fn estimate_haploid_peak(g: &Graph) -> Option<f64> {
+    const RELATIVE_BIN_WIDTH: f64 = 0.1;
+    let median = weighted_median(
+        g.all_nodes()
+            .filter(|n| n.has_coverage())
+            .map(|n| (n.coverage, n.length))
+            .collect(),
+        |a, b| a.total_cmp(b),
+    )?;
+    if median <= 0. {
+        return None;
+    }
+    let bin_width = median * RELATIVE_BIN_WIDTH;
+    //earliest among the heaviest bins
+    let (start, weight) = coverage_histogram(g, bin_width)
+        .into_iter()
+        .rev()
+        .max_by_key(|&(_, w)| w)?;
+    if weight == 0 {
+        return None;
+    }
+    Some(start + bin_width / 2.)
+}
+
+impl GraphStats {
+    pub fn compute(g: &Graph) -> GraphStats {
+        let total_length = g.all_nodes().map(|n| n.length).sum();
+        let n50 = n50(g.all_nodes().map(|n| n.length));
+
+        let cov_nodes: Vec<&Node> = g.all_nodes().filter(|n| n.has_coverage()).collect();
+        let cov_length: usize = cov_nodes.iter().map(|n| n.length).sum();
+        let coverage_mean = if cov_length > 0 {
+            Some(
+                cov_nodes
+                    .iter()
+                    .map(|n| n.coverage * n.length as f64)
+                    .sum::<f64>()
+                    / cov_length as f64,
+            )
+        } else {
+            None
+        };
+        let coverage_median = weighted_median(
+            cov_nodes.iter().map(|n| (n.coverage, n.length)).collect(),
+            |a, b| a.total_cmp(b),
+        );
+
+        let sccs = scc::nontrivial_sccs(g);
+        let scc_nodes: HashSet<usize> = sccs.iter().flatten().copied().collect();
+
+        GraphStats {
+            node_cnt: g.node_cnt(),
+            link_cnt: g.link_cnt(),
+            total_length,
+            n50,
+            coverage_mean,
+            coverage_median,
+            wcc_cnt: wcc::weakly_connected(g).len(),
+            scc_cnt: sccs.len(),
+            scc_node_cnt: scc_nodes.len(),
+        }
+    }
+}
+
+impl fmt::Display for GraphStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let cov_str = |c: Option<f64>| c.map_or(String::from("NA"), |c| format!("{c:.2}"));
+        writeln!(f, "Nodes\t{}", self.node_cnt)?;
+        writeln!(f, "Links\t{}", self.link_cnt)?;
+        writeln!(f, "Total length\t{}", self.total_length)?;
+        writeln!(f, "N50\t{}", self.n50)?;
+        writeln!(f, "Mean coverage\t{}", cov_str(self.coverage_mean))?;
+        writeln!(f, "Median coverage\t{}", cov_str(self.coverage_median))?;
+        writeln!(f, "Weakly connected components\t{}", self.wcc_cnt)?;
+        writeln!(f, "Non-trivial SCCs\t{}", self.scc_cnt)?;
+        write!(f, "Nodes in SCCs\t{}", self.scc_node_cnt)
+    }
+}
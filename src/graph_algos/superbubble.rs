@@ -1,8 +1,12 @@
 use crate::graph::*;
-use log::debug;
+use itertools::Itertools;
+use log::{debug, warn};
+use rayon::prelude::*;
+use std::borrow::Cow;
 use std::cmp;
 use std::collections::HashMap;
 use std::collections::HashSet;
+use std::collections::VecDeque;
 
 type DistRange = (usize, usize);
 
@@ -25,8 +29,16 @@ impl Superbubble {
     fn link_dist_range(&self, l: Link, g: &Graph) -> Option<DistRange> {
         let &r = self.reached_vertices.get(&l.start)?;
         let enode_len = g.vertex_length(l.end);
-        assert!(enode_len >= l.overlap);
-        Some(shift_range(r, enode_len - l.overlap))
+        //malformed (e.g. containment-style) links shouldn't abort the whole search
+        if l.overlap > enode_len {
+            warn!(
+                "Overlap {} of link {} exceeds length {} of its end node, assuming zero contribution",
+                l.overlap,
+                g.l_str(l),
+                enode_len
+            );
+        }
+        Some(shift_range(r, enode_len.saturating_sub(l.overlap)))
     }
 
     pub fn longest_path(&self, g: &Graph) -> Path {
@@ -73,13 +85,130 @@ impl Superbubble {
         rc_p.reverse_complement()
     }
 
+    //path maximizing the total coverage of its nodes (missing coverage counts as 0)
+    pub fn heaviest_path(&self, g: &Graph) -> Path {
+        let cov = |v: Vertex| {
+            let c = g.node(v.node_id).coverage;
+            if c.is_nan() {
+                0.
+            } else {
+                c
+            }
+        };
+        let inner_link =
+            |l: &Link| self.reached_vertices.contains_key(&l.start) && l.start != self.end_vertex();
+
+        //processing vertices in topological order
+        let mut remaining_incoming: HashMap<Vertex, usize> = self
+            .reached_vertices
+            .keys()
+            .map(|&v| (v, g.incoming_edges(v).filter(|l| inner_link(l)).count()))
+            .collect();
+        let mut weights: HashMap<Vertex, f64> = HashMap::new();
+        weights.insert(self.start_vertex, cov(self.start_vertex));
+        let mut can_be_processed = vec![self.start_vertex];
+        while let Some(v) = can_be_processed.pop() {
+            if v == self.end_vertex() {
+                continue;
+            }
+            let v_weight = weights[&v];
+            for &l in g.outgoing_edges(v) {
+                let w = l.end;
+                //skipping self-loop on the start vertex
+                if w == self.start_vertex {
+                    continue;
+                }
+                let Some(rem_inc) = remaining_incoming.get_mut(&w) else {
+                    continue;
+                };
+                let w_weight = weights.entry(w).or_insert(f64::NEG_INFINITY);
+                *w_weight = f64::max(*w_weight, v_weight + cov(w));
+                *rem_inc -= 1;
+                if *rem_inc == 0 {
+                    can_be_processed.push(w);
+                }
+            }
+        }
+
+        let mut v = self.end_vertex();
+        let mut rc_p = Path::new(v.rc());
+        'outer: while v != self.start_vertex {
+            let v_weight = weights[&v];
+            for l in g.incoming_edges(v) {
+                if let Some(&u_weight) = weights.get(&l.start).filter(|_| inner_link(&l)) {
+                    if u_weight + cov(v) == v_weight {
+                        rc_p.append(l.rc());
+                        v = l.start;
+                        continue 'outer;
+                    }
+                }
+            }
+            panic!("Couldn't recover bubble path");
+        }
+        rc_p.reverse_complement()
+    }
+
+    //all paths from start to end vertex (at most max_paths of them), sorted by length
+    //NB: if the cap is reached, returned paths are not necessarily the shortest ones
+    pub fn all_paths(&self, g: &Graph, max_paths: usize) -> Vec<Path> {
+        let mut paths = Vec::new();
+        let mut stack = vec![Path::new(self.start_vertex)];
+        while let Some(p) = stack.pop() {
+            if paths.len() == max_paths {
+                break;
+            }
+            let v = p.end();
+            if v == self.end_vertex() {
+                paths.push(p);
+                continue;
+            }
+            for &l in g.outgoing_edges(v) {
+                //also skipping self-loop on the start vertex
+                if l.end != self.start_vertex && self.reached_vertices.contains_key(&l.end) {
+                    let mut ext = p.clone();
+                    ext.append(l);
+                    stack.push(ext);
+                }
+            }
+        }
+        paths.sort_by_cached_key(|p| p.total_length(g));
+        paths
+    }
+
+    //in sorted order
     pub fn vertices(&self) -> impl Iterator<Item = &Vertex> + '_ {
-        self.reached_vertices.keys()
+        self.reached_vertices.keys().sorted()
+    }
+
+    //two alternative paths (shorter first) if the bubble consists of exactly two
+    //vertex-disjoint paths between its start and end, None otherwise
+    pub fn biallelic_paths(&self, g: &Graph) -> Option<(Path, Path)> {
+        let start_links = g.outgoing_edges(self.start_vertex);
+        if start_links.len() != 2
+            || self
+                .inner_vertices()
+                .any(|&v| g.in_degree(v) != 1 || g.out_degree(v) != 1)
+        {
+            return None;
+        }
+        let allele_path = |l: Link| {
+            let mut p = Path::from_link(l);
+            while p.end() != self.end_vertex() {
+                p.append(g.outgoing_edges(p.end())[0]);
+            }
+            p
+        };
+        let (p1, p2) = (allele_path(start_links[0]), allele_path(start_links[1]));
+        if p1.total_length(g) <= p2.total_length(g) {
+            Some((p1, p2))
+        } else {
+            Some((p2, p1))
+        }
     }
 
+    //in sorted order
     pub fn inner_vertices(&self) -> impl Iterator<Item = &Vertex> + '_ {
-        self.reached_vertices
-            .keys()
+        self.vertices()
             .filter(|&v| *v != self.start_vertex() && *v != self.end_vertex())
     }
 
@@ -107,6 +236,7 @@ impl Superbubble {
 //TODO can be heavily optimized (e.g. no maps, sets, etc)
 //TODO support other weights -- currently using max length
 //Maybe update to pseudo-code from miniasm paper?
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct SbSearchParams {
     pub max_length: usize,
     pub max_diff: usize,
@@ -114,17 +244,47 @@ pub struct SbSearchParams {
 }
 
 impl SbSearchParams {
-    //all usize values should probably default to max values
-    //FIXME provide builder
     pub fn unrestricted() -> SbSearchParams {
-        SbSearchParams {
-            max_length: usize::MAX,
-            max_diff: usize::MAX,
-            max_count: usize::MAX,
+        Self::builder().build()
+    }
+
+    //all limits default to usize::MAX
+    pub fn builder() -> SbSearchParamsBuilder {
+        SbSearchParamsBuilder {
+            params: SbSearchParams {
+                max_length: usize::MAX,
+                max_diff: usize::MAX,
+                max_count: usize::MAX,
+            },
         }
     }
 }
 
+pub struct SbSearchParamsBuilder {
+    params: SbSearchParams,
+}
+
+impl SbSearchParamsBuilder {
+    pub fn max_length(mut self, max_length: usize) -> Self {
+        self.params.max_length = max_length;
+        self
+    }
+
+    pub fn max_diff(mut self, max_diff: usize) -> Self {
+        self.params.max_diff = max_diff;
+        self
+    }
+
+    pub fn max_count(mut self, max_count: usize) -> Self {
+        self.params.max_count = max_count;
+        self
+    }
+
+    pub fn build(self) -> SbSearchParams {
+        self.params
+    }
+}
+
 pub fn find_superbubble(g: &Graph, v: Vertex, params: &SbSearchParams) -> Option<Superbubble> {
     find_superbubble_subgraph(g, v, params, None)
 }
@@ -143,47 +303,50 @@ pub fn find_superbubble_subgraph(
         }
     };
 
+    //palindromic start (by sequence or by a link to its own reverse-complement)
+    //is indistinguishable from its reverse-complement
+    if g.is_palindromic(s.node_id) {
+        debug!("Palindromic starting vertex {}", g.v_str(s));
+        return None;
+    }
+
     let mut bubble = Superbubble {
         start_vertex: s,
         reached_vertices: HashMap::new(),
         end_vertex: None,
     };
 
-    let outgoing_edge_cnt = |v| match consider_vertex_f {
-        None => g.outgoing_edge_cnt(v),
+    let out_degree = |v| match consider_vertex_f {
+        None => g.out_degree(v),
         Some(avail) => g.outgoing_edges(v).iter().filter(|l| avail(l.end)).count(),
     };
 
-    let incoming_edge_cnt = |v| match consider_vertex_f {
-        None => g.incoming_edge_cnt(v),
-        Some(avail) => g
-            .incoming_edges(v)
-            .iter()
-            .filter(|l| avail(l.start))
-            .count(),
+    let in_degree = |v| match consider_vertex_f {
+        None => g.in_degree(v),
+        Some(avail) => g.incoming_edges(v).filter(|l| avail(l.start)).count(),
     };
 
     let outgoing_edges = |v| match consider_vertex_f {
-        None => g.outgoing_edges(v),
-        Some(avail) => g
-            .outgoing_edges(v)
-            .iter()
-            .copied()
-            .filter(|l| avail(l.end))
-            .collect(),
+        None => Cow::Borrowed(g.outgoing_edges(v)),
+        Some(avail) => Cow::Owned(
+            g.outgoing_edges(v)
+                .iter()
+                .copied()
+                .filter(|l| avail(l.end))
+                .collect::<Vec<_>>(),
+        ),
     };
 
     let _incoming_edges = |v| match consider_vertex_f {
-        None => g.incoming_edges(v),
-        Some(avail) => g
-            .incoming_edges(v)
-            .iter()
-            .copied()
-            .filter(|l| avail(l.start))
-            .collect(),
+        None => Cow::<[Link]>::Owned(g.incoming_edges(v).collect::<Vec<_>>()),
+        Some(avail) => Cow::Owned(
+            g.incoming_edges(v)
+                .filter(|l| avail(l.start))
+                .collect::<Vec<_>>(),
+        ),
     };
 
-    if outgoing_edge_cnt(bubble.start_vertex) < 2
+    if out_degree(bubble.start_vertex) < 2
         //same check, but excluding loops
         || outgoing_edges(bubble.start_vertex).iter().filter(|l| l.start != l.end).count() < 2
     {
@@ -210,24 +373,27 @@ pub fn find_superbubble_subgraph(
         let v = can_be_processed.pop().unwrap();
         debug!("Adding vertex {} to the bubble", g.v_str(v));
 
-        if outgoing_edge_cnt(v) == 0 {
+        if out_degree(v) == 0 {
             debug!("Hit dead-end");
             return None;
         }
 
         debug!("Looking at neighbors");
-        for l in outgoing_edges(v) {
+        for &l in outgoing_edges(v).iter() {
             let w = l.end;
             if w == bubble.start_vertex {
+                if v != bubble.start_vertex {
+                    //no loops involving the start vertex
+                    return None;
+                } else {
+                    //unless self-loop
+                    continue;
+                }
+            }
+
+            if w == v {
+                //self-loop on the inner vertex
                 return None;
-                //FIXME re-enable after dealing with usage wrt start/end symmetry absense
-                //if v != self.start_vertex {
-                //    //no loops involiving the start vertex
-                //    return false;
-                //} else {
-                //    //unless self-loop
-                //    continue;
-                //}
             }
 
             if !bubble.reached_vertices.contains_key(&w) {
@@ -239,7 +405,10 @@ pub fn find_superbubble_subgraph(
                     return None;
                 }
                 not_ready_cnt += 1;
-                remaining_incoming.insert(w, incoming_edge_cnt(w));
+                //self-loop on the end vertex is allowed (symmetric to the start vertex case),
+                //self-loops on inner vertices are rejected when they are processed
+                let self_loop_cnt = g.incoming_edges(w).filter(|l| l.start == w).count();
+                remaining_incoming.insert(w, in_degree(w) - self_loop_cnt);
                 bubble
                     .reached_vertices
                     .insert(w, bubble.link_dist_range(l, g).unwrap());
@@ -261,11 +430,10 @@ pub fn find_superbubble_subgraph(
             }
         }
 
+        //NB: single reached vertex with incoming links from outside of the bubble
+        //(can_be_processed.len() == 0 && not_ready_cnt == 1) is NOT considered as the end,
+        //since it doesn't form a (classic) superbubble with the start vertex
         if can_be_processed.len() == 1 && not_ready_cnt == 0 {
-            //FIXME second case is not a classic one, check that it works!
-            //Also needs more work to get final vertex!!!
-            //|| (can_be_processed.len() == 0 && not_ready_cnt == 1)
-            //process last vertex?
             let t = can_be_processed.pop().unwrap();
             debug!("End node found! Vertex {}", g.v_str(t));
 
@@ -304,13 +472,20 @@ pub fn find_superbubble_subgraph(
 }
 
 pub fn find_all_outer(g: &Graph, params: &SbSearchParams) -> Vec<Superbubble> {
+    //searches from different vertices are independent, run them in parallel
+    let vertices: Vec<Vertex> = g.all_vertices().collect();
+    let candidates: Vec<(Vertex, Option<Superbubble>)> = vertices
+        .into_par_iter()
+        .map(|v| (v, find_superbubble(g, v, params)))
+        .collect();
+
     let mut used_starts = HashSet::new();
     let mut start_2_bubble = HashMap::new();
-    for v in g.all_vertices() {
+    for (v, bubble) in candidates {
         if used_starts.contains(&v) {
             continue;
         }
-        if let Some(bubble) = find_superbubble(g, v, params) {
+        if let Some(bubble) = bubble {
             //used_starts.insert(bubble.start_vertex());
             used_starts.insert(bubble.end_vertex().rc());
             assert!(!start_2_bubble.contains_key(&bubble.end_vertex().rc()));
@@ -323,7 +498,12 @@ pub fn find_all_outer(g: &Graph, params: &SbSearchParams) -> Vec<Superbubble> {
             start_2_bubble.insert(v, bubble);
         }
     }
-    start_2_bubble.into_values().collect()
+    //ordering by start vertex to keep output independent of hashing
+    start_2_bubble
+        .into_iter()
+        .sorted_by_key(|(v, _)| *v)
+        .map(|(_, bubble)| bubble)
+        .collect()
 }
 
 pub type BubbleChain = Vec<Superbubble>;
@@ -358,6 +538,38 @@ pub fn find_maximal_chain(g: &Graph, mut init_v: Vertex, params: &SbSearchParams
     find_chain_ahead(g, init_v, params)
 }
 
+//maximal chain of superbubbles passing through the node (as inner or boundary vertex)
+//bubble containing the node is searched from the vertices reachable backwards (in either orientation)
+//None if the node doesn't belong to any bubble
+pub fn find_chain_through(
+    g: &Graph,
+    node_id: usize,
+    params: &SbSearchParams,
+) -> Option<BubbleChain> {
+    for v in [Vertex::forward(node_id), Vertex::reverse(node_id)] {
+        let mut visited = HashSet::from([v]);
+        let mut queue = VecDeque::from([v]);
+        while let Some(u) = queue.pop_front() {
+            if let Some(bubble) = find_superbubble(g, u, params) {
+                if bubble.vertices().any(|w| w.node_id == node_id) {
+                    return Some(find_maximal_chain(g, u, params));
+                }
+            }
+            if visited.len() > params.max_count {
+                break;
+            }
+            for l in g.incoming_edges(u) {
+                if visited.insert(l.start) {
+                    queue.push_back(l.start);
+                }
+            }
+        }
+    }
+    None
+}
+
+//covers the graph with non-overlapping maximal chains of superbubbles
+//(every outer bubble is considered at most once, deduplicated by its boundary nodes)
 pub fn find_maximal_chains(g: &Graph, params: &SbSearchParams) -> Vec<BubbleChain> {
     let mut considered_start_nodes = HashSet::new();
     let mut maximal_chains = Vec::new();
@@ -379,6 +591,9 @@ pub fn find_maximal_chains(g: &Graph, params: &SbSearchParams) -> Vec<BubbleChai
 
 //will need adjustment if ever 'start' can be same as 'end' in superbubble
 pub fn length_range(chain: &[Superbubble], g: &Graph) -> DistRange {
+    if chain.len() == 1 {
+        return chain[0].length_range(g);
+    }
     let mut tot_min = 0;
     let mut tot_max = 0;
     for bubble in chain {
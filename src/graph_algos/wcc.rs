@@ -0,0 +1,39 @@
+use crate::graph::*;
+use std::collections::HashMap;
+
+fn find(parent: &mut [usize], mut x: usize) -> usize {
+    while parent[x] != x {
+        //path halving
+        parent[x] = parent[parent[x]];
+        x = parent[x];
+    }
+    x
+}
+
+//Weakly connected components (every link connects its endpoint nodes regardless of orientation)
+//Union-find over node ids, singletons form their own components.
+//Components are ordered by their minimal node id, node ids within component are sorted.
+pub fn weakly_connected(g: &Graph) -> Vec<Vec<usize>> {
+    let mut parent: Vec<usize> = (0..g.node_cnt()).collect();
+    for l in g.all_links() {
+        let (a, b) = (
+            find(&mut parent, l.start.node_id),
+            find(&mut parent, l.end.node_id),
+        );
+        if a != b {
+            parent[a.max(b)] = a.min(b);
+        }
+    }
+
+    let mut comp_idx: HashMap<usize, usize> = HashMap::new();
+    let mut components: Vec<Vec<usize>> = Vec::new();
+    for node_id in 0..g.node_cnt() {
+        let root = find(&mut parent, node_id);
+        let idx = *comp_idx.entry(root).or_insert_with(|| {
+            components.push(Vec::new());
+            components.len() - 1
+        });
+        components[idx].push(node_id);
+    }
+    components
+}
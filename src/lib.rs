@@ -1,27 +1,46 @@
-use log::{debug, info, warn};
+use flate2::read::MultiGzDecoder;
+use itertools::Itertools;
+use log::{debug, error, info, warn};
+use serde::Serialize;
 use std::collections::HashMap;
 use std::error::Error;
 use std::fs;
 use std::fs::File;
-use std::io::{BufWriter, Write};
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
 use std::{collections::HashSet, path::PathBuf};
 use trio_walk::HaploSearchSettings;
 
 //tests don't compile without the pub
 //FIXME what to do?
+pub mod curation;
 pub mod graph;
 pub mod graph_algos;
+pub mod path_sink;
 pub mod pseudo_hap;
 pub mod trio;
 pub mod trio_walk;
 
 pub use graph::*;
+use path_sink::{
+    write_to_sinks, AgpSink, BedSink, FastaSink, GroupSplitSink, HapFastaSink, PathSink, TsvSink,
+    UnusedFilterSink, WalkSink,
+};
 
 use crate::trio::{
     assign_short_node_tangles, GroupAssignmentSettings, TangleAssignmentSettings, TrioGroup,
 };
 use crate::trio_walk::HaploSearcher;
 
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum RedundantPathMode {
+    /// Report all paths
+    Keep,
+    /// Report all paths, but warn about redundant ones
+    Flag,
+    /// Exclude redundant paths
+    Drop,
+}
+
 //TODO use PathBuf
 #[derive(clap::Args, Debug)]
 pub struct TrioSettings {
@@ -29,10 +48,33 @@ pub struct TrioSettings {
     #[clap(short, long)]
     graph: PathBuf,
 
+    /// Replace invalid UTF-8 sequences in the GFA (with a warning) instead of failing
+    #[clap(long)]
+    lossy_utf8: bool,
+
+    /// Collapse nodes joined by links with overlap covering both of them (full containment) into one
+    #[clap(long)]
+    collapse_contained: bool,
+
+    /// TSV file with node name and length columns, overriding (or providing missing) lengths from the GFA
+    #[clap(long)]
+    node_lengths: Option<PathBuf>,
+
     /// Parental markers file
     #[clap(short, long)]
     markers: PathBuf,
 
+    /// Comma separated node, maternal and paternal count columns of the markers file,
+    /// given as 1-based column numbers or header names (default: 1,2,3)
+    #[clap(long)]
+    marker_columns: Option<String>,
+
+    /// TSV file with node name and group (MATERNAL/PATERNAL/HOMOZYGOUS/ISSUE) columns,
+    /// forcing the groups of the nodes irrespective of markers, homozygous node detection and propagation
+    /// (reported with 'Override' info in the annotation outputs)
+    #[clap(long)]
+    assignment_overrides: Option<PathBuf>,
+
     /// Marker-based annotation output file
     #[clap(long)]
     init_assign: Option<PathBuf>,
@@ -45,18 +87,128 @@ pub struct TrioSettings {
     #[clap(long)]
     final_assign: Option<PathBuf>,
 
+    /// Final annotation output in JSON format
+    /// (array of objects with name, length, mat, pat, assignment and color fields)
+    #[clap(long)]
+    json: Option<PathBuf>,
+
+    /// Output graph in GFA format with final node annotation added as 'hp' (group) and 'CL' (color) tags,
+    /// e.g. for visualization in Bandage
+    #[clap(long)]
+    annotated_gfa: Option<PathBuf>,
+
+    /// Output nodes, parental group of which was changed during homozygous node detection
+    #[clap(long)]
+    homozygous_diff: Option<PathBuf>,
+
+    /// Output candidate heterozygous pairs of long nodes missed by the graph structure (for manual review)
+    #[clap(long)]
+    missed_het_pairs: Option<PathBuf>,
+
+    /// Trace decisions made while growing the haplo-path seeded from the node (see --trace-output)
+    #[clap(long)]
+    trace_node: Option<String>,
+
+    /// Output file for the --trace-node trace
+    #[clap(long, default_value = "trace.tsv")]
+    trace_output: PathBuf,
+
+    /// Reverse-complement reported paths, so that offsets on the dominant rGFA reference (SN/SO tags) increase
+    #[clap(long)]
+    orient_to_reference: bool,
+
+    /// Handling of haplo-paths, nodes of which are all contained in another path of the same haplotype
+    #[clap(long, value_enum, default_value_t = RedundantPathMode::Keep)]
+    redundant_paths: RedundantPathMode,
+
+    /// Manual path edits (join/break) to apply before output, see curation module for the format
+    #[clap(long)]
+    path_edits: Option<PathBuf>,
+
     /// Comma separated haplotype names to be used in outputs (default: "mat,pat")
     #[clap(long, default_value_t = String::from("mat,pat"))]
     hap_names: String,
 
-    /// Marker-assisted extracted haplo-paths
+    /// Marker-assisted extracted haplo-paths ("-" for stdout)
     #[clap(long, short)]
     paths: Option<PathBuf>,
 
+    /// Prefix for per-haplotype path outputs (same format as --paths): <prefix>.hap1.paths (maternal),
+    /// <prefix>.hap2.paths (paternal), <prefix>.shared.paths (homozygous nodes not used by haplo-paths)
+    /// and <prefix>.unassigned.paths
+    #[clap(long)]
+    group_paths: Option<String>,
+
+    /// Do not list trivial paths for nodes not used by haplo-paths in --paths output
+    /// (other outputs are not affected)
+    #[clap(long)]
+    skip_unused: bool,
+
+    /// Only list trivial paths for unused nodes of at least this length in --paths output
+    #[clap(long, default_value_t = 0)]
+    min_unused_len: usize,
+
     /// Use GAF ([<>]<name1>)+ format for paths
     #[clap(long)]
     gaf_format: bool,
 
+    /// In GAF format, repeat the first node in the end of circular paths
+    #[clap(long)]
+    repeat_circular_start: bool,
+
+    /// Print gaps (jumps) within paths as [N<estimated size>] scaffold tokens
+    #[clap(long)]
+    scaffold_gaps: bool,
+
+    /// Output paths as GFA W-lines (with provided sample name) instead of the TSV format
+    #[clap(long)]
+    walk_sample: Option<String>,
+
+    /// Output heuristic set of links, removal of which makes the graph acyclic (as GFA L-lines)
+    #[clap(long)]
+    feedback_links: Option<PathBuf>,
+
+    /// Report distinct values of the specified node tag (e.g. 'cl') along every path
+    /// and the fraction of tagged sequence carrying the dominant value
+    #[clap(long)]
+    path_tag: Option<String>,
+
+    /// Prefix for haplotype FASTA outputs (<prefix>.hap1.fasta for maternal and <prefix>.hap2.fasta for paternal).
+    /// Requires sequences in the GFA
+    #[clap(long)]
+    hap_fasta: Option<String>,
+
+    /// AGP output with every reported path as a scaffold (gaps of estimated size between components)
+    #[clap(long)]
+    agp: Option<PathBuf>,
+
+    /// BED output with coordinates of every node within the (sequence of the) reported paths
+    #[clap(long)]
+    bed: Option<PathBuf>,
+
+    /// FASTA output with sequences of all reported paths (one record per path).
+    /// Requires sequences in the GFA
+    #[clap(long)]
+    fasta: Option<PathBuf>,
+
+    /// Add 'confidence' column (0-1) to the paths output, see --confidence-weights
+    #[clap(long)]
+    path_confidence: bool,
+
+    /// Comma separated weights of marker purity, marker density, monochromatic and gap-free components of path confidence
+    /// (non-negative, at least one of them positive)
+    #[clap(long, default_value = "1,1,1,1", value_parser = ConfidenceSettings::parse_weights)]
+    confidence_weights: [f64; 4],
+
+    /// Output homozygous nodes not covered by haplo-paths to <prefix>.shared.fasta instead of both haplotype files
+    #[clap(long)]
+    shared_fasta: bool,
+
+    /// Output biallelic superbubbles as VCF-like records (requires sequences in the GFA).
+    /// Coordinates are taken from rGFA SN/SO tags when available, otherwise relative to the bubble start vertex
+    #[clap(long)]
+    bubble_vcf: Option<PathBuf>,
+
     /// Minimal number of parent-specific markers required for assigning parental group to a node
     #[clap(long, default_value_t = 10)]
     marker_cnt: usize,
@@ -65,6 +217,11 @@ pub struct TrioSettings {
     #[clap(long, default_value_t = 10_000)]
     marker_sparsity: usize,
 
+    /// Require at least <value> parent-specific markers per kb instead of --marker-cnt markers
+    /// (density is then reported in the info column of annotation outputs)
+    #[clap(long)]
+    marker_density: Option<f64>,
+
     /// Sets minimal marker excess for assigning a parental group to <value>:1
     #[clap(long, default_value_t = 5.0)]
     marker_ratio: f64,
@@ -78,7 +235,15 @@ pub struct TrioSettings {
     #[clap(long, default_value_t = 1.5)]
     suspect_homozygous_cov_coeff: f64,
 
+    /// Also classify nodes with coverage above <coeff> * <weighted mean coverage of nodes assigned to parental groups>
+    /// as homozygous, e.g. 1.75 for collapsed nodes with doubled coverage (length restrictions still apply).
+    /// Falls back to the primary coverage peak of the graph if parental nodes lack coverage.
+    /// Not used by default
+    #[clap(long)]
+    doubled_cov_coeff: Option<f64>,
+
     /// Longer nodes can not be classified as homozygous
+    /// (consider lowering, e.g. to 20000, for small genomes)
     #[clap(long, default_value_t = 2_000_000)]
     max_homozygous_len: usize,
 
@@ -87,6 +252,22 @@ pub struct TrioSettings {
     #[clap(long, default_value_t = 500_000)]
     solid_len: usize,
 
+    /// Minimal length of nodes to launch haplo-path search from (default: solid_len)
+    #[clap(long)]
+    init_node_len: Option<usize>,
+
+    /// Stop extending haplo-path once its total length exceeds the value (no limit by default)
+    #[clap(long)]
+    max_path_length: Option<usize>,
+
+    /// (Haploid) genome size, enables reporting NG50 of the haplo-paths of each haplotype
+    #[clap(long)]
+    genome_size: Option<usize>,
+
+    /// Report haplo-path search progress every <value> processed seed nodes (0 disables)
+    #[clap(long, default_value_t = 1000)]
+    progress_interval: usize,
+
     /// Sets minimal marker excess for assigning a parental group of solid nodes to <value>:1.
     /// Must be <= marker_ratio (by default == marker_ratio)
     #[clap(long)]
@@ -151,10 +332,24 @@ pub struct TrioSettings {
     #[clap(long, default_value_t = 5000)]
     default_gap_size: usize,
 
+    /// Estimate the size of gaps between ambiguously connected haplo-paths by the shortest walk between them
+    /// (otherwise default_gap_size is used)
+    #[clap(long)]
+    estimate_ambig_gaps: bool,
+
+    /// Largest gap size searched for by --estimate-ambig-gaps, longer gaps get default_gap_size
+    #[clap(long, default_value_t = 50_000)]
+    ambig_gap_search_limit: usize,
+
     /// Assign tangles flanked by solid nodes from the same class
     #[clap(long)]
     assign_tangles: bool,
 
+    /// Assign unassigned nodes to the group of the majority of trusted (see trusted_len) assigned nodes
+    /// within the specified number of links
+    #[clap(long)]
+    propagation_rounds: Option<usize>,
+
     /// Allow dead-end nodes in the tangles
     #[clap(long)]
     tangle_allow_deadend: bool,
@@ -166,6 +361,20 @@ pub struct TrioSettings {
     /// Prevent reassignment of nodes
     #[clap(long)]
     tangle_prevent_reassign: bool,
+
+    /// Break ties between equally good bubble-filling connectors at random (by default the smaller node id wins)
+    #[clap(long)]
+    random_ties: bool,
+
+    /// Seed of the random number generator. It is only consulted by --random-ties.
+    /// Given the same inputs, settings and seed the outputs are byte-identical (irrespective of --threads)
+    #[clap(long, default_value_t = 0)]
+    seed: u64,
+
+    /// Only report the number of eligible seed nodes and strongly connected component statistics
+    /// (based on the initial parental group assignment) and exit without the path search
+    #[clap(long)]
+    plan: bool,
 }
 
 impl TrioSettings {
@@ -194,45 +403,568 @@ impl TrioSettings {
         assert!(self.good_side_cov_gap >= 0.);
         assert!(self.solid_homozygous_cov_coeff >= 0.);
     }
+
+    fn path_format(&self) -> PathFormat {
+        PathFormat {
+            gaf: self.gaf_format,
+            repeat_circular_start: self.repeat_circular_start,
+            scaffold_gaps: self.scaffold_gaps,
+        }
+    }
+
+    //None if unused nodes shouldn't be reported
+    fn unused_len_threshold(&self) -> Option<usize> {
+        if self.skip_unused {
+            None
+        } else {
+            Some(self.min_unused_len)
+        }
+    }
+}
+
+//by '.gz' extension or (for misnamed files) by gzip magic bytes
+fn is_gzipped(input_fn: &PathBuf) -> Result<bool, std::io::Error> {
+    if input_fn.extension().is_some_and(|ext| ext == "gz") {
+        return Ok(true);
+    }
+    let mut magic = [0u8; 2];
+    match File::open(input_fn)?.read_exact(&mut magic) {
+        Ok(()) => Ok(magic == [0x1f, 0x8b]),
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+//buffered reader over the (transparently decompressed) input file
+pub fn open_input(input_fn: &PathBuf) -> Result<Box<dyn BufRead>, std::io::Error> {
+    let file = File::open(input_fn)?;
+    if is_gzipped(input_fn)? {
+        info!("Decompressing gzipped {}", input_fn.display());
+        Ok(Box::new(BufReader::new(MultiGzDecoder::new(file))))
+    } else {
+        Ok(Box::new(BufReader::new(file)))
+    }
+}
+
+//buffered reader over the (transparently decompressed) GFA file
+pub fn open_gfa(graph_fn: &PathBuf) -> Result<Box<dyn BufRead>, std::io::Error> {
+    open_input(graph_fn)
+}
+
+//buffered writer to the file, "-" stands for stdout
+pub fn create_output<P: AsRef<std::path::Path>>(
+    output_fn: P,
+) -> Result<Box<dyn Write>, std::io::Error> {
+    if output_fn.as_ref() == std::path::Path::new("-") {
+        Ok(Box::new(BufWriter::new(std::io::stdout())))
+    } else {
+        Ok(Box::new(BufWriter::new(File::create(output_fn)?)))
+    }
+}
+
+//strict UTF-8 by default, otherwise invalid sequences are replaced (reporting affected lines)
+pub fn read_gfa_text(graph_fn: &PathBuf, lossy_utf8: bool) -> Result<String, std::io::Error> {
+    if !lossy_utf8 && !is_gzipped(graph_fn)? {
+        return fs::read_to_string(graph_fn);
+    }
+    let mut bytes = Vec::new();
+    open_gfa(graph_fn)?.read_to_end(&mut bytes)?;
+    if !lossy_utf8 {
+        return String::from_utf8(bytes)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e));
+    }
+    let invalid_lines = bytes
+        .split(|&b| b == b'\n')
+        .enumerate()
+        .filter(|(_, line)| std::str::from_utf8(line).is_err())
+        .map(|(i, _)| (i + 1).to_string())
+        .collect_vec();
+    if !invalid_lines.is_empty() {
+        warn!(
+            "Replaced invalid UTF-8 sequences in {} line(s) of {}: {}",
+            invalid_lines.len(),
+            graph_fn.to_str().unwrap(),
+            invalid_lines.join(",")
+        );
+    }
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+#[derive(clap::Args, Debug)]
+pub struct StatsSettings {
+    /// GFA file
+    #[clap(short, long)]
+    graph: PathBuf,
+
+    /// Replace invalid UTF-8 sequences in the GFA (with a warning) instead of failing
+    #[clap(long)]
+    lossy_utf8: bool,
+
+    /// Output file (stdout if not provided)
+    #[clap(short, long)]
+    output: Option<PathBuf>,
+
+    /// File to list non-trivial strongly connected components (cyclic regions avoided by haplo-path search)
+    #[clap(long)]
+    sccs: Option<PathBuf>,
+
+    /// TSV file with node name and length columns, overriding (or providing missing) lengths from the GFA
+    #[clap(long)]
+    node_lengths: Option<PathBuf>,
 }
 
-fn read_graph(graph_fn: &PathBuf) -> Result<Graph, Box<dyn Error>> {
+impl StatsSettings {
+    //statistics are printed to stdout if no output file is provided
+    pub fn writes_to_stdout(&self) -> bool {
+        self.output.is_none()
+    }
+}
+
+pub fn run_stats(settings: &StatsSettings) -> Result<(), Box<dyn Error>> {
+    let g = read_graph(
+        &settings.graph,
+        ReadOptions::default(),
+        settings.lossy_utf8,
+        settings.node_lengths.as_ref(),
+    )?;
+    let stats = graph_algos::GraphStats::compute(&g);
+    match &settings.output {
+        Some(output) => {
+            info!("Writing graph statistics to {}", output.to_str().unwrap());
+            fs::write(output, format!("{stats}\n"))?;
+        }
+        None => println!("{stats}"),
+    }
+    if let Some(sccs_fn) = &settings.sccs {
+        info!(
+            "Writing non-trivial strongly connected components to {}",
+            sccs_fn.to_str().unwrap()
+        );
+        let sccs = graph_algos::scc::nontrivial_sccs(&g);
+        fs::write(sccs_fn, graph_algos::scc::format_sccs(&g, &sccs))?;
+    }
+    Ok(())
+}
+
+//size of the global thread pool used by parallel steps (e.g. bubble search), 0 means all available cores
+//NB: can only be initialized once
+pub fn init_thread_pool(threads: usize) -> Result<(), Box<dyn Error>> {
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .build_global()?;
+    Ok(())
+}
+
+//tab-separated node name and length per line (empty lines and lines starting with '#' are skipped)
+pub fn read_node_lengths<P: AsRef<std::path::Path>>(
+    lengths_fn: P,
+) -> Result<HashMap<String, usize>, std::io::Error> {
+    let mut lengths = HashMap::new();
+    for (i, line) in fs::read_to_string(lengths_fn)?.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut split = line.split('\t');
+        let name = split.next().unwrap();
+        match split.next().and_then(|l| l.trim().parse::<usize>().ok()) {
+            Some(length) if length > 0 => {
+                lengths.insert(String::from(name), length);
+            }
+            _ => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("invalid node length record on line {}: '{line}'", i + 1),
+                ))
+            }
+        }
+    }
+    Ok(lengths)
+}
+
+//on top of the provided options, multi-edges are collapsed and too long overlaps normalized
+//(node lengths are taken from node_lengths_fn if provided)
+fn read_graph(
+    graph_fn: &PathBuf,
+    options: ReadOptions,
+    lossy_utf8: bool,
+    node_lengths_fn: Option<&PathBuf>,
+) -> Result<Graph, Box<dyn Error>> {
     info!("Reading graph from {}", graph_fn.to_str().unwrap());
-    let g = Graph::read_sanitize(&fs::read_to_string(graph_fn)?);
+    //lossy decoding needs the whole text, otherwise the graph is parsed while streaming
+    let reader: Box<dyn BufRead> = if lossy_utf8 {
+        Box::new(std::io::Cursor::new(read_gfa_text(graph_fn, true)?))
+    } else {
+        open_gfa(graph_fn)?
+    };
+    let node_lengths = match node_lengths_fn {
+        Some(lengths_fn) => {
+            info!("Reading node lengths from {}", lengths_fn.to_str().unwrap());
+            Some(read_node_lengths(lengths_fn)?)
+        }
+        None => None,
+    };
+    let options = ReadOptions {
+        collapse_multi_edges: true,
+        normalize_overlaps: true,
+        node_lengths: node_lengths.as_ref(),
+        ..options
+    };
+    let g = Graph::custom_read_from(reader, &options)?;
+    if let Some(node_lengths) = &node_lengths {
+        let absent = node_lengths
+            .keys()
+            .filter(|name| g.try_name2id(name).is_none())
+            .sorted()
+            .collect_vec();
+        if !absent.is_empty() {
+            warn!(
+                "Ignoring lengths of {} node(s) absent from the graph (e.g. {})",
+                absent.len(),
+                absent[0]
+            );
+        }
+        let missing = g
+            .all_nodes()
+            .filter(|n| !node_lengths.contains_key(&n.name))
+            .map(|n| &n.name)
+            .collect_vec();
+        if !missing.is_empty() {
+            warn!(
+                "Lengths of {} node(s) not provided, using lengths from the graph (e.g. {})",
+                missing.len(),
+                missing[0]
+            );
+        }
+    }
 
     info!("Graph read successfully");
     info!("Node count: {}", g.node_cnt());
     info!("Link count: {}", g.link_cnt());
+    report_palindromes(&g);
     Ok(g)
 }
 
-fn output_coloring(
+//(chrom, pos, ref, alt) of the biallelic bubble, REF (shorter path) and ALT include a preceding anchor base
+//chrom and pos (1-based) come from rGFA SN/SO tags of the forward start vertex if present,
+//otherwise position is relative to the start vertex (used as chrom)
+//None if bubble isn't biallelic, sequences are missing or alleles are identical
+pub fn bubble_variant(
     g: &Graph,
+    bubble: &graph_algos::superbubble::Superbubble,
+) -> Option<(String, usize, String, String)> {
+    let (ref_path, alt_path) = bubble.biallelic_paths(g)?;
+    let ref_seq = ref_path.sequence(g)?;
+    let alt_seq = alt_path.sequence(g)?;
+    if ref_seq == alt_seq {
+        return None;
+    }
+    let min_len = std::cmp::min(ref_seq.len(), alt_seq.len());
+    let prefix = ref_seq
+        .bytes()
+        .zip(alt_seq.bytes())
+        .take_while(|(a, b)| a == b)
+        .count();
+    if prefix == 0 {
+        return None;
+    }
+    let suffix = ref_seq
+        .bytes()
+        .rev()
+        .zip(alt_seq.bytes().rev())
+        .take(min_len - prefix)
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let start = bubble.start_vertex();
+    let n = g.node(start.node_id);
+    let rgfa_pos = match (
+        start.direction,
+        n.tag("SN").and_then(TagValue::as_str),
+        n.tag("SO").and_then(TagValue::as_int),
+    ) {
+        (Direction::FORWARD, Some(sn), Some(so)) => usize::try_from(so).ok().map(|so| (sn, so)),
+        _ => None,
+    };
+    let (chrom, pos) = match rgfa_pos {
+        Some((sn, so)) => (String::from(sn), so + prefix),
+        None => (g.v_str(start), prefix),
+    };
+    Some((
+        chrom,
+        pos,
+        String::from(&ref_seq[(prefix - 1)..(ref_seq.len() - suffix)]),
+        String::from(&alt_seq[(prefix - 1)..(alt_seq.len() - suffix)]),
+    ))
+}
+
+fn write_bubble_vcf(g: &Graph, file_name: &PathBuf) -> Result<(), std::io::Error> {
+    let mut output = create_output(file_name)?;
+    writeln!(output, "##fileformat=VCFv4.2")?;
+    writeln!(
+        output,
+        "##INFO=<ID=BUBBLE,Number=2,Type=String,Description=\"Start and end vertices of the superbubble\">"
+    )?;
+    writeln!(output, "#CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO")?;
+    let mut bubbles = graph_algos::superbubble::find_all_outer(
+        g,
+        &graph_algos::superbubble::SbSearchParams::unrestricted(),
+    );
+    bubbles.sort_by_key(|b| b.start_vertex());
+    let mut written = 0;
+    for bubble in &bubbles {
+        if let Some((chrom, pos, ref_allele, alt_allele)) = bubble_variant(g, bubble) {
+            writeln!(
+                output,
+                "{}\t{}\t.\t{}\t{}\t.\t.\tBUBBLE={},{}",
+                chrom,
+                pos,
+                ref_allele,
+                alt_allele,
+                g.v_str(bubble.start_vertex()),
+                g.v_str(bubble.end_vertex())
+            )?;
+            written += 1;
+        } else {
+            debug!(
+                "Skipping bubble {} -> {}",
+                g.v_str(bubble.start_vertex()),
+                g.v_str(bubble.end_vertex())
+            );
+        }
+    }
+    info!("Reported {} out of {} bubbles", written, bubbles.len());
+    Ok(())
+}
+
+//palindromes break forward/reverse-complement duality, they are excluded from bubble starts
+fn report_palindromes(g: &Graph) {
+    let nodes = (0..g.node_cnt())
+        .filter(|&node_id| g.is_palindromic(node_id))
+        .collect_vec();
+    if !nodes.is_empty() {
+        warn!(
+            "Palindromic nodes ({}): {}",
+            nodes.len(),
+            nodes.iter().map(|&node_id| g.name(node_id)).join(",")
+        );
+    }
+    let links = g.palindromic_links();
+    if !links.is_empty() {
+        warn!(
+            "Palindromic links ({}): {}",
+            links.len(),
+            links.iter().map(|&l| g.l_str(l)).join(",")
+        );
+    }
+}
+
+fn write_feedback_links(g: &Graph, file_name: &PathBuf) -> Result<(), std::io::Error> {
+    let mut output = create_output(file_name)?;
+    for l in graph_algos::scc::feedback_link_set(g) {
+        writeln!(output, "{}", g.link_gfa_line(l))?;
+    }
+    Ok(())
+}
+
+fn group_color(group: TrioGroup) -> &'static str {
+    match group {
+        TrioGroup::PATERNAL => "#8888FF",
+        TrioGroup::MATERNAL => "#FF8888",
+        TrioGroup::ISSUE => "#FFDE24",
+        TrioGroup::HOMOZYGOUS => "#7900D6",
+    }
+}
+
+fn write_annotated_gfa(
+    mut g: Graph,
     assignments: &trio::AssignmentStorage,
     file_name: &PathBuf,
     hap_names: &(&str, &str),
 ) -> Result<(), std::io::Error> {
-    let mut output = BufWriter::new(File::create(file_name)?);
-    writeln!(output, "node\tassignment\tlength\tinfo\tcolor")?;
-    for (node_id, n) in g.all_nodes().enumerate() {
-        assert!(g.name2id(&n.name) == node_id);
-        if let Some(assign) = assignments.get(node_id) {
-            let color = match assign.group {
-                trio::TrioGroup::PATERNAL => "#8888FF",
-                trio::TrioGroup::MATERNAL => "#FF8888",
-                trio::TrioGroup::ISSUE => "#FFDE24",
-                trio::TrioGroup::HOMOZYGOUS => "#7900D6",
+    for node_id in 0..g.node_cnt() {
+        if let Some(group) = assignments.group(node_id) {
+            let group_name = group_str(Some(group), hap_names).to_uppercase();
+            g.set_node_tag(node_id, "hp", TagValue::String(group_name));
+            g.set_node_tag(
+                node_id,
+                "CL",
+                TagValue::String(String::from(group_color(group))),
+            );
+        }
+    }
+    let mut output = create_output(file_name)?;
+    g.write_gfa(&mut output)?;
+    output.flush()
+}
+
+//node annotation written one node at a time,
+//so that it can be produced within another pass over the nodes
+struct ColoringWriter<'a> {
+    output: Box<dyn Write>,
+    assignments: &'a trio::AssignmentStorage,
+    hap_names: (&'a str, &'a str),
+}
+
+impl<'a> ColoringWriter<'a> {
+    fn create(
+        file_name: &PathBuf,
+        assignments: &'a trio::AssignmentStorage,
+        hap_names: &(&'a str, &'a str),
+    ) -> Result<ColoringWriter<'a>, std::io::Error> {
+        let mut output = create_output(file_name)?;
+        writeln!(output, "node\tassignment\tlength\tinfo\tcolor\tconfidence")?;
+        Ok(ColoringWriter {
+            output,
+            assignments,
+            hap_names: *hap_names,
+        })
+    }
+
+    //nodes are expected in the order of their ids
+    fn write_node(&mut self, node_id: usize, n: &Node) -> Result<(), std::io::Error> {
+        if let Some(assign) = self.assignments.get(node_id) {
+            let color = group_color(assign.group);
+            let confidence = if assign.confidence.is_nan() {
+                String::from("NA")
+            } else {
+                format!("{:.3}", assign.confidence)
             };
             writeln!(
-                output,
-                "{}\t{}\t{}\t{}\t{}",
+                self.output,
+                "{}\t{}\t{}\t{}\t{}\t{}",
                 n.name,
-                group_str(Some(assign.group), hap_names).to_uppercase(),
+                group_str(Some(assign.group), &self.hap_names).to_uppercase(),
                 n.length,
-                assign.info,
-                color
+                self.assignments.info(node_id).unwrap(),
+                color,
+                confidence
             )?;
         }
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<(), std::io::Error> {
+        self.output.flush()
+    }
+}
+
+fn output_coloring(
+    g: &Graph,
+    assignments: &trio::AssignmentStorage,
+    file_name: &PathBuf,
+    hap_names: &(&str, &str),
+) -> Result<(), std::io::Error> {
+    let mut writer = ColoringWriter::create(file_name, assignments, hap_names)?;
+    for (node_id, n) in g.all_nodes().enumerate() {
+        assert!(g.name2id(&n.name) == node_id);
+        writer.write_node(node_id, n)?;
+    }
+    writer.finish()
+}
+
+#[derive(Serialize)]
+struct NodeAnnotation<'a> {
+    name: &'a str,
+    length: usize,
+    //see Graph::unique_length and Graph::effective_coverage
+    unique_length: usize,
+    effective_coverage: Option<f64>,
+    mat: Option<usize>,
+    pat: Option<usize>,
+    assignment: TrioGroup,
+    color: &'static str,
+}
+
+//JSON array of assigned nodes (marker counts are null for nodes without marker info,
+//coverage is null for nodes without coverage)
+pub fn write_node_annotation_json<W: Write>(
+    g: &Graph,
+    assignments: &trio::AssignmentStorage,
+    raw_cnts: &HashMap<usize, trio::TrioInfo>,
+    output: W,
+) -> Result<(), std::io::Error> {
+    let annotations: Vec<NodeAnnotation> = g
+        .all_nodes()
+        .enumerate()
+        .filter_map(|(node_id, n)| {
+            let group = assignments.group(node_id)?;
+            let info = raw_cnts.get(&node_id);
+            Some(NodeAnnotation {
+                name: &n.name,
+                length: n.length,
+                unique_length: g.unique_length(node_id),
+                effective_coverage: Some(g.effective_coverage(node_id))
+                    .filter(|_| n.has_coverage()),
+                mat: info.map(|i| i.mat),
+                pat: info.map(|i| i.pat),
+                assignment: group,
+                color: group_color(group),
+            })
+        })
+        .collect();
+    let mut output = BufWriter::new(output);
+    serde_json::to_writer_pretty(&mut output, &annotations)?;
+    writeln!(output)?;
+    output.flush()
+}
+
+fn output_assignment_diff(
+    g: &Graph,
+    before: &trio::AssignmentStorage,
+    after: &trio::AssignmentStorage,
+    file_name: &PathBuf,
+    hap_names: &(&str, &str),
+) -> Result<(), std::io::Error> {
+    let mut output = create_output(file_name)?;
+    writeln!(output, "node\tlength\tbefore\tafter")?;
+    for node_id in after.changed_nodes(before) {
+        writeln!(
+            output,
+            "{}\t{}\t{}\t{}",
+            g.name(node_id),
+            g.node_length(node_id),
+            group_str(before.group(node_id), hap_names).to_uppercase(),
+            group_str(after.group(node_id), hap_names).to_uppercase()
+        )?;
+    }
+    Ok(())
+}
+
+fn output_missed_het_pairs(
+    g: &Graph,
+    assignments: &trio::AssignmentStorage,
+    min_len: usize,
+    file_name: &PathBuf,
+    hap_names: &(&str, &str),
+) -> Result<(), std::io::Error> {
+    let pairs = trio::missed_het_pairs(
+        g,
+        assignments,
+        &trio::HetPairSettings {
+            min_len,
+            ..trio::HetPairSettings::default()
+        },
+    );
+    info!("Found {} candidate missed heterozygous pairs", pairs.len());
+    let mut output = create_output(file_name)?;
+    writeln!(
+        output,
+        "node1\tnode2\tassignment1\tassignment2\tlength1\tlength2\tcoverage1\tcoverage2"
+    )?;
+    for (n1, n2) in pairs {
+        writeln!(
+            output,
+            "{}\t{}\t{}\t{}\t{}\t{}\t{:.1}\t{:.1}",
+            g.name(n1),
+            g.name(n2),
+            group_str(assignments.group(n1), hap_names).to_uppercase(),
+            group_str(assignments.group(n2), hap_names).to_uppercase(),
+            g.node_length(n1),
+            g.node_length(n2),
+            g.node(n1).coverage,
+            g.node(n2).coverage
+        )?;
     }
     Ok(())
 }
@@ -258,6 +990,7 @@ fn augment_by_path_search_round(
 
     path_searcher.find_all();
     let node_usage = path_searcher.take_used();
+
     augment_assignments(g, assignments, &node_usage, true)
 }
 
@@ -292,15 +1025,7 @@ fn augment_assignments(
 }
 
 fn weighted_mean_solid_cov(g: &Graph, solid_len_thr: usize) -> f64 {
-    let mut total_len = 0;
-    let mut total_cov = 0.;
-    for n in g.all_nodes() {
-        if n.length >= solid_len_thr {
-            total_len += n.length;
-            total_cov += n.coverage * (n.length as f64);
-        }
-    }
-    total_cov / total_len as f64
+    g.weighted_mean_cov(|node_id| g.node_length(node_id) >= solid_len_thr)
 }
 
 fn parse_hap_names(hap_names_s: &str) -> Option<(&str, &str)> {
@@ -318,43 +1043,264 @@ fn group_str<'a>(o_g: Option<TrioGroup>, hap_names: &'a (&'a str, &'a str)) -> &
     }
 }
 
-pub fn write_paths(
+//named path together with the haplotype it was reported for (None for unassigned)
+#[derive(Clone, Debug, PartialEq)]
+pub struct PathRecord {
+    pub name: String,
+    pub path: Path,
+    pub group: Option<TrioGroup>,
+    //trivial path of a node not covered by the haplo-paths (see path_records)
+    pub unused: bool,
+}
+
+impl PathRecord {
+    pub fn new<S: Into<String>>(name: S, path: Path, group: Option<TrioGroup>) -> PathRecord {
+        PathRecord {
+            name: name.into(),
+            path,
+            group,
+            unused: false,
+        }
+    }
+}
+
+//Summary of the trio analysis results
+pub struct TrioSummary {
+    //final node assignment counts
+    pub maternal_nodes: usize,
+    pub paternal_nodes: usize,
+    pub homozygous_nodes: usize,
+    pub issue_nodes: usize,
+    //haplo-path counts
+    pub maternal_paths: usize,
+    pub paternal_paths: usize,
+    //total length of the nodes used (not used) by haplo-paths
+    pub placed_length: usize,
+    pub unused_length: usize,
+    pub path_n50: usize,
+    //per haplotype contiguity (NG50 only if genome size is provided, None if not reached)
+    pub maternal_path_n50: usize,
+    pub paternal_path_n50: usize,
+    pub maternal_path_ng50: Option<usize>,
+    pub paternal_path_ng50: Option<usize>,
+}
+
+impl TrioSummary {
+    pub fn compute(
+        g: &Graph,
+        assignments: &trio::AssignmentStorage,
+        haplo_paths: &[trio_walk::HaploPath],
+        node_usage: &trio::AssignmentStorage,
+        genome_size: Option<usize>,
+    ) -> TrioSummary {
+        let node_cnt = |group| {
+            assignments
+                .assigned()
+                .filter(|&node_id| assignments.group(node_id) == Some(group))
+                .count()
+        };
+        let path_cnt = |group| haplo_paths.iter().filter(|(_, _, g)| *g == group).count();
+        let path_lengths = |group| {
+            haplo_paths
+                .iter()
+                .filter(move |(_, _, g)| *g == group)
+                .map(|(p, _, _)| p.total_length(g))
+        };
+        let path_ng50 = |group| {
+            genome_size.and_then(|size| graph_algos::stats::ng50(path_lengths(group), size))
+        };
+        let placed_length = node_usage
+            .assigned()
+            .map(|node_id| g.node(node_id).length)
+            .sum();
+        let total_length: usize = g.all_nodes().map(|n| n.length).sum();
+        TrioSummary {
+            maternal_nodes: node_cnt(TrioGroup::MATERNAL),
+            paternal_nodes: node_cnt(TrioGroup::PATERNAL),
+            homozygous_nodes: node_cnt(TrioGroup::HOMOZYGOUS),
+            issue_nodes: node_cnt(TrioGroup::ISSUE),
+            maternal_paths: path_cnt(TrioGroup::MATERNAL),
+            paternal_paths: path_cnt(TrioGroup::PATERNAL),
+            placed_length,
+            unused_length: total_length - placed_length,
+            path_n50: graph_algos::stats::n50(
+                haplo_paths.iter().map(|(p, _, _)| p.total_length(g)),
+            ),
+            maternal_path_n50: graph_algos::stats::n50(path_lengths(TrioGroup::MATERNAL)),
+            paternal_path_n50: graph_algos::stats::n50(path_lengths(TrioGroup::PATERNAL)),
+            maternal_path_ng50: path_ng50(TrioGroup::MATERNAL),
+            paternal_path_ng50: path_ng50(TrioGroup::PATERNAL),
+        }
+    }
+
+    pub fn log(&self, hap_names: &(&str, &str)) {
+        info!(
+            "Nodes assigned {}/{}/homozygous/issue: {}/{}/{}/{}",
+            hap_names.0,
+            hap_names.1,
+            self.maternal_nodes,
+            self.paternal_nodes,
+            self.homozygous_nodes,
+            self.issue_nodes
+        );
+        info!(
+            "Haplo-paths {}/{}: {}/{}",
+            hap_names.0, hap_names.1, self.maternal_paths, self.paternal_paths
+        );
+        info!(
+            "Total length placed/unused: {}/{}",
+            self.placed_length, self.unused_length
+        );
+        info!("Haplo-path N50: {}", self.path_n50);
+        info!(
+            "Haplo-path N50 {}/{}: {}/{}",
+            hap_names.0, hap_names.1, self.maternal_path_n50, self.paternal_path_n50
+        );
+        if self.maternal_path_ng50.is_some() || self.paternal_path_ng50.is_some() {
+            let ng50_str = |ng50: Option<usize>| ng50.map_or(String::from("NA"), |l| l.to_string());
+            info!(
+                "Haplo-path NG50 {}/{}: {}/{}",
+                hap_names.0,
+                hap_names.1,
+                ng50_str(self.maternal_path_ng50),
+                ng50_str(self.paternal_path_ng50)
+            );
+        }
+    }
+}
+
+//What the path search is going to start from, see --plan
+pub struct TrioPlan {
+    //long definitely assigned nodes to launch the path search from
+    pub maternal_seeds: usize,
+    pub paternal_seeds: usize,
+    pub seed_length: usize,
+    //non-trivial strongly connected components
+    //(only small localized ones can be jumped over, see scc::find_small_localized)
+    pub scc_cnt: usize,
+    pub scc_node_cnt: usize,
+    pub skippable_scc_cnt: usize,
+}
+
+impl TrioPlan {
+    pub fn compute(
+        g: &Graph,
+        assignments: &trio::AssignmentStorage,
+        search_settings: &HaploSearchSettings,
+    ) -> TrioPlan {
+        let seeds = g
+            .all_nodes()
+            .enumerate()
+            .filter(|&(node_id, n)| {
+                n.length >= search_settings.init_node_len && assignments.is_definite(node_id)
+            })
+            .map(|(node_id, n)| (assignments.group(node_id).unwrap(), n.length))
+            .collect_vec();
+        let seed_cnt = |group| seeds.iter().filter(|(g, _)| *g == group).count();
+        let sccs = graph_algos::scc::nontrivial_sccs(g);
+        let scc_nodes: HashSet<usize> = sccs.iter().flatten().copied().collect();
+        //tangles are found in both orientations
+        let skippable_scc_cnt = graph_algos::scc::find_small_localized(
+            g,
+            &graph_algos::scc::strongly_connected(g),
+            search_settings.skippable_tangle_size,
+        )
+        .iter()
+        .map(|t| {
+            t.vertices
+                .iter()
+                .map(|v| v.node_id)
+                .sorted()
+                .dedup()
+                .collect_vec()
+        })
+        .unique()
+        .count();
+        TrioPlan {
+            maternal_seeds: seed_cnt(TrioGroup::MATERNAL),
+            paternal_seeds: seed_cnt(TrioGroup::PATERNAL),
+            seed_length: seeds.iter().map(|(_, l)| l).sum(),
+            scc_cnt: sccs.len(),
+            scc_node_cnt: scc_nodes.len(),
+            skippable_scc_cnt,
+        }
+    }
+
+    pub fn log(&self, hap_names: &(&str, &str)) {
+        info!(
+            "Seed nodes {}/{}: {}/{} (total length {})",
+            hap_names.0, hap_names.1, self.maternal_seeds, self.paternal_seeds, self.seed_length
+        );
+        info!(
+            "At most {} haplo-paths expected (one per seed node not absorbed by earlier paths)",
+            self.maternal_seeds + self.paternal_seeds
+        );
+        info!(
+            "Non-trivial SCCs: {} ({} nodes), {} of them small enough to be jumped over",
+            self.scc_cnt, self.scc_node_cnt, self.skippable_scc_cnt
+        );
+    }
+}
+
+//haplo-paths followed by trivial paths for every node not covered by them
+//(once per compatible haplotype)
+//node usage is per node id, nodes traversed in reverse orientation are not reported as unused
+pub fn path_records(
     g: &Graph,
     haplo_paths: Vec<trio_walk::HaploPath>,
     assignments: &trio::AssignmentStorage,
     node_usage: &trio::AssignmentStorage,
-    output: &PathBuf,
-    gaf_format: bool,
     hap_names: &(&str, &str),
-) -> Result<(), std::io::Error> {
-    //FIXME buffer
-    let mut output = File::create(output)?;
-    writeln!(output, "name\tpath\tassignment")?;
+) -> Vec<PathRecord> {
+    path_records_visiting(
+        g,
+        haplo_paths,
+        assignments,
+        node_usage,
+        hap_names,
+        &mut |_, _| Ok(()),
+    )
+    .expect("Trivial visitor can't fail")
+}
+
+//same as path_records, but also passes every node (in the order of node ids) to the visitor
+//within the same pass, e.g. to write per-node annotation without another scan of the graph
+pub fn path_records_visiting(
+    g: &Graph,
+    haplo_paths: Vec<trio_walk::HaploPath>,
+    assignments: &trio::AssignmentStorage,
+    node_usage: &trio::AssignmentStorage,
+    hap_names: &(&str, &str),
+    visit: &mut dyn FnMut(usize, &Node) -> Result<(), std::io::Error>,
+) -> Result<Vec<PathRecord>, std::io::Error> {
+    let mut records = Vec::new();
     for (path, node_id, group) in haplo_paths {
         assert!(path.vertices().contains(&Vertex::forward(node_id)));
         //info!("Identified {:?} path: {}", group, path.print(&g));
-        writeln!(
-            output,
-            "{}_from_{}\t{}\t{}",
-            group_str(Some(group), hap_names),
-            g.node(node_id).name,
-            path.print_format(g, gaf_format),
-            group_str(Some(group), hap_names).to_uppercase()
-        )?;
+        records.push(PathRecord::new(
+            format!(
+                "{}_from_{}",
+                group_str(Some(group), hap_names),
+                g.node(node_id).name
+            ),
+            path,
+            Some(group),
+        ));
     }
 
-    let mut write_node = |n: &Node, group: Option<TrioGroup>| {
-        writeln!(
-            output,
-            "{}_unused_{}\t{}\t{}",
-            group_str(group, hap_names),
-            n.name,
-            Direction::format_node(&n.name, Direction::FORWARD, gaf_format),
-            group_str(group, hap_names).to_uppercase()
-        )
+    let mut add_node = |node_id: usize, group: Option<TrioGroup>| {
+        records.push(PathRecord {
+            unused: true,
+            ..PathRecord::new(
+                format!("{}_unused_{}", group_str(group, hap_names), g.name(node_id)),
+                Path::new(Vertex::forward(node_id)),
+                group,
+            )
+        })
     };
 
     for (node_id, n) in g.all_nodes().enumerate() {
+        visit(node_id, n)?;
         let haplopath_assign = node_usage.group(node_id);
         match assignments.group(node_id) {
             None | Some(TrioGroup::ISSUE) => {
@@ -363,7 +1309,7 @@ pub fn write_paths(
                     "Node: {} length: {} not assigned to any haplotype (adding trivial NA path)",
                     n.name, n.length
                 );
-                write_node(g.node(node_id), None)?;
+                add_node(node_id, None);
             }
             Some(assign) => {
                 if TrioGroup::compatible(assign, TrioGroup::MATERNAL)
@@ -373,7 +1319,7 @@ pub fn write_paths(
                 {
                     debug!("Node: {} length: {} not present in MATERNAL haplo-paths (adding trivial MATERNAL path)",
                         n.name, n.length);
-                    write_node(g.node(node_id), Some(TrioGroup::MATERNAL))?;
+                    add_node(node_id, Some(TrioGroup::MATERNAL));
                 }
                 if TrioGroup::compatible(assign, TrioGroup::PATERNAL)
                     //not present in haplopaths paths or incompatible
@@ -382,17 +1328,279 @@ pub fn write_paths(
                 {
                     debug!("Node: {} length: {} not present in PATERNAL haplo-paths (adding trivial PATERNAL path)",
                         n.name, n.length);
-                    write_node(g.node(node_id), Some(TrioGroup::PATERNAL))?;
+                    add_node(node_id, Some(TrioGroup::PATERNAL));
                 }
             }
         }
     }
+    Ok(records)
+}
+
+//distinct values of the tag along the path (ordered by decreasing total length of carrying nodes)
+//and the fraction of the (tagged) sequence carrying the dominant one
+//None if none of the nodes carry the tag
+pub fn path_tag_summary(g: &Graph, path: &Path, key: &str) -> Option<(Vec<String>, f64)> {
+    let mut value_lengths: Vec<(&TagValue, usize)> = Vec::new();
+    for v in path.vertices() {
+        let n = g.node(v.node_id);
+        if let Some(value) = n.tag(key) {
+            match value_lengths.iter_mut().find(|(x, _)| *x == value) {
+                Some((_, len)) => *len += n.length,
+                None => value_lengths.push((value, n.length)),
+            }
+        }
+    }
+    //stable sort, so ties are resolved by the order along the path
+    value_lengths.sort_by_key(|&(_, len)| std::cmp::Reverse(len));
+    let total: usize = value_lengths.iter().map(|&(_, len)| len).sum();
+    let &(_, dominant) = value_lengths.first()?;
+    Some((
+        value_lengths.iter().map(|&(x, _)| x.to_string()).collect(),
+        dominant as f64 / total as f64,
+    ))
+}
+
+//rGFA reference (SN tag) covered by the longest total length of the path nodes, if any
+//path is reverse-complemented if its nodes from that reference go in the order of decreasing
+//offsets (SO tag), or (single node case) if the node is used in reverse orientation
+pub fn reference_oriented(g: &Graph, path: Path) -> Path {
+    let ref_pos = |v: &Vertex| {
+        let n = g.node(v.node_id);
+        Some((
+            n.tag("SN")?.as_str()?,
+            usize::try_from(n.tag("SO")?.as_int()?).ok()?,
+        ))
+    };
+    let mut ref_lengths: Vec<(&str, usize)> = Vec::new();
+    for v in path.vertices() {
+        if let Some((sn, _)) = ref_pos(v) {
+            match ref_lengths.iter_mut().find(|(x, _)| *x == sn) {
+                Some((_, len)) => *len += g.vertex_length(*v),
+                None => ref_lengths.push((sn, g.vertex_length(*v))),
+            }
+        }
+    }
+    let Some(&(dominant, _)) = ref_lengths.iter().max_by_key(|&&(_, len)| len) else {
+        return path;
+    };
+    let on_ref = path
+        .vertices()
+        .iter()
+        .filter_map(|v| {
+            ref_pos(v)
+                .filter(|&(sn, _)| sn == dominant)
+                .map(|(_, so)| (v, so))
+        })
+        .collect_vec();
+    let reverse = match (on_ref.first(), on_ref.last()) {
+        (Some(&(v, _)), _) if on_ref.len() == 1 => v.direction == Direction::REVERSE,
+        (Some(&(_, first_so)), Some(&(_, last_so))) => last_so < first_so,
+        _ => false,
+    };
+    if reverse {
+        path.reverse_complement()
+    } else {
+        path
+    }
+}
+
+pub struct ConfidenceSettings {
+    //weight of the fraction of target group markers among all markers on the path
+    pub purity_weight: f64,
+    //weight of the target marker density (saturates at 1 marker per full_density_sparsity bp)
+    pub density_weight: f64,
+    //weight of the 1 / (1 + <number of marker signal switches>) term
+    pub monochromatic_weight: f64,
+    //weight of the 1 / (1 + <number of gaps and inferred jumps>) term
+    pub gap_weight: f64,
+    pub full_density_sparsity: usize,
+}
+
+impl Default for ConfidenceSettings {
+    fn default() -> Self {
+        Self {
+            purity_weight: 1.,
+            density_weight: 1.,
+            monochromatic_weight: 1.,
+            gap_weight: 1.,
+            full_density_sparsity: 10_000,
+        }
+    }
+}
+
+impl ConfidenceSettings {
+    //purity, density, monochromatic and gap-free weights
+    fn parse_weights(weights_s: &str) -> Result<[f64; 4], String> {
+        let weights: Vec<f64> = weights_s
+            .split(',')
+            .map(|w| w.trim().parse::<f64>())
+            .collect::<Result<_, _>>()
+            .map_err(|e| format!("Problem while parsing confidence weights: {e}"))?;
+        let weights: [f64; 4] = weights
+            .try_into()
+            .map_err(|_| String::from("Exactly four comma separated weights expected"))?;
+        if weights.iter().any(|w| !w.is_finite() || *w < 0.) {
+            return Err(String::from(
+                "Weights should be finite non-negative numbers",
+            ));
+        }
+        if weights.iter().sum::<f64>() <= 0. {
+            return Err(String::from(
+                "At least one of the weights should be positive",
+            ));
+        }
+        Ok(weights)
+    }
+
+    fn total_weight(&self) -> f64 {
+        self.purity_weight + self.density_weight + self.monochromatic_weight + self.gap_weight
+    }
+}
+
+//confidence = (w_p * purity + w_d * density + w_m * monochromatic + w_g * gap_score) / (w_p + w_d + w_m + w_g), where
+//  purity -- target markers / all markers on the path nodes (0 if no markers)
+//  density -- min(1, target markers * full_density_sparsity / path length)
+//  monochromatic -- 1 / (1 + number of switches between target- and other-dominated nodes along the path)
+//    (nodes with no markers or balanced marker counts are ignored)
+//  gap_score -- 1 / (1 + number of gaps and inferred jumps in the path),
+//    where an inferred jump is a link chosen among several outgoing links of a vertex,
+//    which leads to a node without target markers
+//None for paths without definite (maternal/paternal) group
+pub fn path_confidence(
+    g: &Graph,
+    path: &Path,
+    group: TrioGroup,
+    raw_cnts: &HashMap<usize, trio::TrioInfo>,
+    settings: &ConfidenceSettings,
+) -> Option<f64> {
+    if !group.is_definite() {
+        return None;
+    }
+    let counts = |node_id: usize| match raw_cnts.get(&node_id) {
+        Some(info) if group == TrioGroup::MATERNAL => (info.mat, info.pat),
+        Some(info) => (info.pat, info.mat),
+        None => (0, 0),
+    };
+    let (mut target, mut other) = (0, 0);
+    let mut switches = 0;
+    let mut prev_dominant = None;
+    for v in path.vertices() {
+        let (t, o) = counts(v.node_id);
+        target += t;
+        other += o;
+        if t != o {
+            let dominant = t > o;
+            if prev_dominant.is_some_and(|d| d != dominant) {
+                switches += 1;
+            }
+            prev_dominant = Some(dominant);
+        }
+    }
+    let purity = if target + other == 0 {
+        0.
+    } else {
+        target as f64 / (target + other) as f64
+    };
+    let density = f64::min(
+        1.,
+        (target * settings.full_density_sparsity) as f64 / path.total_length(g).max(1) as f64,
+    );
+    let monochromatic = 1. / (1 + switches) as f64;
+    let jumps = path
+        .links()
+        .iter()
+        .filter(|l| match l {
+            GeneralizedLink::GAP(_) => true,
+            GeneralizedLink::LINK(l) => g.out_degree(l.start) > 1 && counts(l.end.node_id).0 == 0,
+        })
+        .count();
+    let gap_score = 1. / (1 + jumps) as f64;
+    let total_weight = settings.total_weight();
+    assert!(total_weight > 0.);
+    Some(
+        (settings.purity_weight * purity
+            + settings.density_weight * density
+            + settings.monochromatic_weight * monochromatic
+            + settings.gap_weight * gap_score)
+            / total_weight,
+    )
+}
+
+pub fn write_paths(
+    g: &Graph,
+    records: &[PathRecord],
+    output: &PathBuf,
+    gaf_format: bool,
+    hap_names: &(&str, &str),
+    tag_key: Option<&str>,
+    confidence: Option<(&HashMap<usize, trio::TrioInfo>, &ConfidenceSettings)>,
+) -> Result<(), std::io::Error> {
+    let mut sink = TsvSink::new(
+        create_output(output)?,
+        PathFormat {
+            gaf: gaf_format,
+            ..Default::default()
+        },
+        hap_names,
+        tag_key,
+        confidence,
+    )?;
+    write_to_sinks(g, records, &mut [&mut sink])
+}
+
+//integrity check that paths agree with the graph (to be run before any path output)
+pub fn check_path_links(g: &Graph, records: &[PathRecord]) -> Result<(), std::io::Error> {
+    for PathRecord { name, path, .. } in records {
+        if let Some(i) = path.first_inconsistent_link(g) {
+            let l = path.general_link_at(i);
+            error!(
+                "Path {} is inconsistent with the graph at link {}->{} (overlap {})",
+                name,
+                g.v_str(l.start()),
+                g.v_str(l.end()),
+                l.overlap()
+            );
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Path {name} is inconsistent with the graph"),
+            ));
+        }
+    }
     Ok(())
 }
 
+pub fn write_hap_fasta(
+    g: &Graph,
+    records: &[PathRecord],
+    assignments: &trio::AssignmentStorage,
+    prefix: &str,
+    separate_shared: bool,
+    hap_names: &(&str, &str),
+) -> Result<(), std::io::Error> {
+    let mut sink = HapFastaSink::create(prefix, separate_shared, assignments, hap_names)?;
+    write_to_sinks(g, records, &mut [&mut sink])
+}
+
 pub fn run_trio_analysis(settings: &TrioSettings) -> Result<(), Box<dyn Error>> {
-    let g = read_graph(&settings.graph)?;
+    let g = read_graph(
+        &settings.graph,
+        ReadOptions {
+            keep_sequences: settings.hap_fasta.is_some()
+                || settings.fasta.is_some()
+                || settings.bubble_vcf.is_some()
+                || settings.annotated_gfa.is_some(),
+            collapse_containments: settings.collapse_contained,
+            ..Default::default()
+        },
+        settings.lossy_utf8,
+        settings.node_lengths.as_ref(),
+    )?;
+    trio_analysis(&g, settings)
+}
 
+//trio analysis of the already loaded graph
+//NB: graph should keep sequences if any of the sequence-based outputs is requested
+pub fn trio_analysis(g: &Graph, settings: &TrioSettings) -> Result<(), Box<dyn Error>> {
     //for n in g.all_nodes() {
     //    println!("Node: {} length: {} cov: {}", n.name, n.length, n.coverage);
     //}
@@ -404,13 +1612,52 @@ pub fn run_trio_analysis(settings: &TrioSettings) -> Result<(), Box<dyn Error>>
     let hap_names =
         parse_hap_names(&settings.hap_names).expect("Problem while parsing haplotype names");
 
+    let confidence_settings = if settings.path_confidence {
+        let [purity_weight, density_weight, monochromatic_weight, gap_weight] =
+            settings.confidence_weights;
+        Some(ConfidenceSettings {
+            purity_weight,
+            density_weight,
+            monochromatic_weight,
+            gap_weight,
+            full_density_sparsity: settings.marker_sparsity,
+        })
+    } else {
+        None
+    };
+
+    if let Some(output) = &settings.bubble_vcf {
+        info!("Writing bubble variants to {}", output.to_str().unwrap());
+        write_bubble_vcf(g, output)?;
+    }
+
+    if let Some(output) = &settings.feedback_links {
+        info!("Writing feedback link set to {}", output.to_str().unwrap());
+        write_feedback_links(g, output)?;
+    }
+
     info!(
         "Reading trio marker information from {}",
         &settings.markers.to_str().unwrap()
     );
-    let trio_infos = trio::read_trio(&settings.markers)?;
+    let file_spec = match &settings.marker_columns {
+        Some(columns) => trio::TrioFileSpec::parse(columns)
+            .ok_or_else(|| format!("Invalid marker columns specification '{columns}'"))?,
+        None => trio::TrioFileSpec::default(),
+    };
+    let (trio_infos, unknown_infos): (Vec<_>, Vec<_>) =
+        trio::read_trio(&settings.markers, &file_spec)?
+            .into_iter()
+            .partition(|ti| g.try_name2id(&ti.node_name).is_some());
+    if !unknown_infos.is_empty() {
+        warn!(
+            "Ignoring marker counts for {} node(s) absent from the graph (e.g. {})",
+            unknown_infos.len(),
+            unknown_infos[0].node_name
+        );
+    }
 
-    let solid_cov_est = weighted_mean_solid_cov(&g, settings.solid_len);
+    let solid_cov_est = weighted_mean_solid_cov(g, settings.solid_len);
     if settings.suspect_homozygous_cov_coeff > 0. || settings.solid_homozygous_cov_coeff > 0. {
         info!("Coverage estimate based on long nodes was {solid_cov_est}");
         if solid_cov_est == 0. {
@@ -427,12 +1674,32 @@ pub fn run_trio_analysis(settings: &TrioSettings) -> Result<(), Box<dyn Error>>
 
     let solid_homozygous_cov = settings.solid_homozygous_cov_coeff * solid_cov_est;
 
+    let overrides = match &settings.assignment_overrides {
+        Some(overrides_fn) => {
+            info!(
+                "Reading group overrides from {}",
+                overrides_fn.to_str().unwrap()
+            );
+            trio::read_assignment_overrides(g, overrides_fn)?
+        }
+        None => Vec::new(),
+    };
+    //overrides are re-applied after every stage that might have changed the groups
+    let apply_overrides = |mut assignments: trio::AssignmentStorage, stage: &str| {
+        if !overrides.is_empty() {
+            let changed = trio::apply_overrides(&mut assignments, &overrides);
+            info!("Overrides changed groups of {changed} nodes after {stage}");
+        }
+        assignments
+    };
+
     info!("Assigning initial parental groups to the nodes");
     let assignments = trio::assign_parental_groups(
-        &g,
+        g,
         &trio_infos,
         &GroupAssignmentSettings {
             assign_cnt: settings.marker_cnt,
+            assign_density: settings.marker_density,
             assign_sparsity: settings.marker_sparsity,
             assign_ratio: settings.marker_ratio,
             solid_ratio: settings.solid_ratio.unwrap_or(settings.marker_ratio),
@@ -444,23 +1711,56 @@ pub fn run_trio_analysis(settings: &TrioSettings) -> Result<(), Box<dyn Error>>
         settings.solid_len,
         solid_homozygous_cov,
     );
+    let assignments = apply_overrides(assignments, "initial assignment");
 
     let raw_cnts = trio_infos
         .into_iter()
         .map(|ti| (g.name2id(&ti.node_name), ti))
         .collect::<HashMap<usize, trio::TrioInfo>>();
 
+    if settings.plan {
+        info!("Planning the path search (based on the initial assignment)");
+        TrioPlan::compute(
+            g,
+            &assignments,
+            &HaploSearchSettings {
+                init_node_len: settings.init_node_len.unwrap_or(settings.solid_len),
+                ..HaploSearchSettings::default()
+            },
+        )
+        .log(&hap_names);
+        return Ok(());
+    }
+
     if let Some(output) = &settings.init_assign {
         info!(
             "Writing initial node annotation to {}",
             output.to_str().unwrap()
         );
-        output_coloring(&g, &assignments, output, &hap_names)?;
+        output_coloring(g, &assignments, output, &hap_names)?;
     }
 
     info!("Marking homozygous nodes");
-    let assigner = trio::HomozygousAssigner::new(
-        &g,
+    let pre_homozygous = settings
+        .homozygous_diff
+        .as_ref()
+        .map(|_| assignments.clone());
+    let doubled_cov = settings.doubled_cov_coeff.map(|coeff| {
+        let mut hap_cov_est = trio::definite_mean_cov(g, &assignments);
+        info!("Coverage estimate based on nodes assigned to parental groups was {hap_cov_est}");
+        if hap_cov_est == 0. {
+            //fall back to the primary mode of the coverage distribution
+            hap_cov_est = graph_algos::estimate_haploid_peak(g).unwrap_or(0.);
+            if hap_cov_est > 0. {
+                info!("Using coverage peak estimate {hap_cov_est} instead");
+            } else {
+                warn!("Coverage of nodes assigned to parental groups is not available, coverage-based homozygous node detection disabled");
+            }
+        }
+        coeff * hap_cov_est
+    });
+    let mut assigner = trio::HomozygousAssigner::new(
+        g,
         assignments,
         settings.trusted_len,
         suspect_homozygous_cov,
@@ -468,12 +1768,24 @@ pub fn run_trio_analysis(settings: &TrioSettings) -> Result<(), Box<dyn Error>>
         solid_homozygous_cov,
         settings.max_homozygous_len,
     );
+    if let Some(min_cov) = doubled_cov.filter(|&c| c > 0.) {
+        assigner.set_min_doubled_cov(min_cov);
+    }
 
-    let assignments = assigner.run();
+    let assignments = apply_overrides(assigner.run(), "homozygous node detection");
+
+    if let (Some(output), Some(before)) = (&settings.homozygous_diff, &pre_homozygous) {
+        info!(
+            "Writing group changes during homozygous node detection to {}",
+            output.to_str().unwrap()
+        );
+        output_assignment_diff(g, before, &assignments, output, &hap_names)?;
+    }
 
     let mut search_settings = HaploSearchSettings {
         solid_len: settings.solid_len,
         trusted_len: settings.trusted_len,
+        init_node_len: settings.init_node_len.unwrap_or(settings.solid_len),
         fill_bubbles: settings.try_fill_bubbles,
         fillable_bubble_len: settings.fillable_bubble_len,
         fillable_bubble_diff: settings.fillable_bubble_diff,
@@ -486,6 +1798,12 @@ pub fn run_trio_analysis(settings: &TrioSettings) -> Result<(), Box<dyn Error>>
         good_side_cov_gap: settings.good_side_cov_gap,
         min_gap_size: settings.min_gap_size as i64,
         default_gap_size: settings.default_gap_size as i64,
+        estimate_ambig_gaps: settings.estimate_ambig_gaps,
+        ambig_gap_search_limit: settings.ambig_gap_search_limit,
+        max_path_length: settings.max_path_length.unwrap_or(usize::MAX),
+        progress_interval: settings.progress_interval,
+        random_ties: settings.random_ties,
+        seed: settings.seed,
         ..HaploSearchSettings::default()
     };
 
@@ -510,11 +1828,11 @@ pub fn run_trio_analysis(settings: &TrioSettings) -> Result<(), Box<dyn Error>>
         }
     }
 
-    let assignments = augment_by_path_search(&g, assignments, search_settings);
+    let assignments = augment_by_path_search(g, assignments, search_settings);
 
     let assignments = if settings.assign_tangles {
         assign_short_node_tangles(
-            &g,
+            g,
             assignments,
             settings.solid_len,
             TangleAssignmentSettings {
@@ -527,58 +1845,309 @@ pub fn run_trio_analysis(settings: &TrioSettings) -> Result<(), Box<dyn Error>>
         assignments
     };
 
+    let assignments = match settings.propagation_rounds {
+        Some(rounds) => {
+            info!("Propagating parental groups to unassigned nodes");
+            trio::propagate_assignments(g, assignments, rounds, settings.trusted_len)
+        }
+        None => assignments,
+    };
+    let assignments = apply_overrides(assignments, "assignment refinement");
+
     if let Some(output) = &settings.refined_assign {
         info!(
             "Writing refined node annotation to {}",
             output.to_str().unwrap()
         );
-        output_coloring(&g, &assignments, output, &hap_names)?;
+        output_coloring(g, &assignments, output, &hap_names)?;
+    }
+    let mut path_searcher = HaploSearcher::new(g, &assignments, search_settings, Some(&raw_cnts));
+
+    if let Some(name) = &settings.trace_node {
+        let node_id = g
+            .try_name2id(name)
+            .ok_or_else(|| format!("Trace node {name} is not in the graph"))?;
+        path_searcher.set_trace_node(node_id);
     }
-    let mut path_searcher = HaploSearcher::new(&g, &assignments, search_settings, Some(&raw_cnts));
 
-    let haplo_paths = path_searcher.find_all();
+    let mut haplo_paths = path_searcher.find_all();
+
+    if settings.trace_node.is_some() {
+        info!(
+            "Writing path search trace to {}",
+            settings.trace_output.to_str().unwrap()
+        );
+        let mut output = create_output(&settings.trace_output)?;
+        writeln!(output, "vertex\tgroup\tcandidates\tdecision\textension")?;
+        for line in path_searcher.take_trace() {
+            writeln!(output, "{line}")?;
+        }
+    }
     let node_usage = path_searcher.take_used();
 
-    let assignments = augment_assignments(&g, assignments, &node_usage, false);
+    if settings.redundant_paths != RedundantPathMode::Keep {
+        let redundant = trio_walk::redundant_paths(&haplo_paths);
+        info!("Found {} redundant haplo-paths", redundant.len());
+        for &i in &redundant {
+            let (path, node_id, group) = &haplo_paths[i];
+            warn!(
+                "Nodes of {:?} path seeded from {} ({}) are contained in another path",
+                group,
+                g.name(*node_id),
+                path.print(g)
+            );
+        }
+        if settings.redundant_paths == RedundantPathMode::Drop {
+            let redundant: HashSet<usize> = redundant.into_iter().collect();
+            haplo_paths = haplo_paths
+                .into_iter()
+                .enumerate()
+                .filter(|(i, _)| !redundant.contains(i))
+                .map(|(_, p)| p)
+                .collect();
+        }
+    }
+
+    let assignments = augment_assignments(g, assignments, &node_usage, false);
 
-    if let Some(output) = &settings.final_assign {
+    //written together with the trivial paths for unused nodes (see below)
+    let mut final_coloring = match &settings.final_assign {
+        Some(output) => {
+            info!(
+                "Writing final node annotation to {}",
+                output.to_str().unwrap()
+            );
+            Some(ColoringWriter::create(output, &assignments, &hap_names)?)
+        }
+        None => None,
+    };
+
+    if let Some(output) = &settings.json {
         info!(
-            "Writing final node annotation to {}",
+            "Writing final node annotation (JSON) to {}",
             output.to_str().unwrap()
         );
-        output_coloring(&g, &assignments, output, &hap_names)?;
+        write_node_annotation_json(g, &assignments, &raw_cnts, create_output(output)?)?;
     }
 
-    if let Some(output) = &settings.paths {
-        info!("Outputting haplo-paths to {}", output.to_str().unwrap());
-        write_paths(
-            &g,
-            haplo_paths,
-            &assignments,
-            &node_usage,
-            output,
-            settings.gaf_format,
-            &hap_names,
-        )?;
+    if let Some(output) = &settings.missed_het_pairs {
+        info!(
+            "Writing candidate missed heterozygous pairs to {}",
+            output.to_str().unwrap()
+        );
+        output_missed_het_pairs(g, &assignments, settings.trusted_len, output, &hap_names)?;
     }
 
+    let summary = TrioSummary::compute(
+        g,
+        &assignments,
+        &haplo_paths,
+        &node_usage,
+        settings.genome_size,
+    );
+    let mut records = path_records_visiting(
+        g,
+        haplo_paths,
+        &assignments,
+        &node_usage,
+        &hap_names,
+        &mut |node_id, n| match &mut final_coloring {
+            Some(writer) => writer.write_node(node_id, n),
+            None => Ok(()),
+        },
+    )?;
+    if let Some(writer) = final_coloring {
+        writer.finish()?;
+    }
+    if let Some(edits_fn) = &settings.path_edits {
+        info!("Applying path edits from {}", edits_fn.to_str().unwrap());
+        let edits = curation::parse_path_edits(&fs::read_to_string(edits_fn)?)?;
+        records = curation::apply_path_edits(g, records, &edits)?;
+    }
+
+    if settings.orient_to_reference {
+        info!("Orienting paths with respect to the reference");
+        records = records
+            .into_iter()
+            .map(|r| PathRecord {
+                path: reference_oriented(g, r.path),
+                ..r
+            })
+            .collect();
+    }
+
+    //every path output relies on the links recorded in the paths
+    check_path_links(g, &records)?;
+
+    //path listing (and its per-haplotype split) as TSV or as walks
+    let create_path_sink =
+        |output_fn: &std::path::Path| -> Result<Box<dyn PathSink>, std::io::Error> {
+            let output = create_output(output_fn)?;
+            Ok(match &settings.walk_sample {
+                Some(sample) => Box::new(WalkSink::new(output, sample)?),
+                None => Box::new(TsvSink::new(
+                    output,
+                    settings.path_format(),
+                    &hap_names,
+                    settings.path_tag.as_deref(),
+                    confidence_settings.as_ref().map(|cs| (&raw_cnts, cs)),
+                )?),
+            })
+        };
+
+    //all outputs are produced in a single pass over the paths
+    let mut path_sink = match &settings.paths {
+        Some(output) => {
+            info!("Outputting haplo-paths to {}", output.to_str().unwrap());
+            Some(create_path_sink(output)?)
+        }
+        None => None,
+    };
+    //trivial paths of unused nodes (following the haplo-paths) are only filtered
+    //in the path listing, other outputs still cover all nodes
+    if settings.skip_unused || settings.min_unused_len > 0 {
+        if let Some(inner) = path_sink.take() {
+            path_sink = Some(Box::new(UnusedFilterSink::new(
+                inner,
+                settings.unused_len_threshold(),
+            )));
+        }
+    }
+    let mut group_split_sink = match &settings.group_paths {
+        Some(prefix) => {
+            info!("Outputting per-haplotype haplo-paths to {prefix}.{{hap1,hap2,shared,unassigned}}.paths");
+            Some(GroupSplitSink::create(
+                prefix,
+                |output_fn| create_path_sink(output_fn.as_ref()),
+                &assignments,
+                &hap_names,
+            )?)
+        }
+        None => None,
+    };
+    let mut fasta_sink = match &settings.hap_fasta {
+        Some(prefix) => {
+            info!("Outputting haplotype sequences to {prefix}.hap[12].fasta");
+            Some(HapFastaSink::create(
+                prefix,
+                settings.shared_fasta,
+                &assignments,
+                &hap_names,
+            )?)
+        }
+        None => None,
+    };
+    let mut path_fasta_sink = match &settings.fasta {
+        Some(output) => {
+            info!("Outputting path sequences to {}", output.to_str().unwrap());
+            Some(FastaSink::new(create_output(output)?))
+        }
+        None => None,
+    };
+    let mut agp_sink = match &settings.agp {
+        Some(output) => {
+            info!("Outputting scaffolds AGP to {}", output.to_str().unwrap());
+            Some(AgpSink::new(create_output(output)?)?)
+        }
+        None => None,
+    };
+    let mut bed_sink = match &settings.bed {
+        Some(output) => {
+            info!(
+                "Outputting node placements BED to {}",
+                output.to_str().unwrap()
+            );
+            Some(BedSink::new(create_output(output)?))
+        }
+        None => None,
+    };
+    let mut sinks: Vec<&mut dyn PathSink> = Vec::new();
+    if let Some(sink) = path_sink.as_mut() {
+        sinks.push(sink.as_mut());
+    }
+    if let Some(sink) = group_split_sink.as_mut() {
+        sinks.push(sink);
+    }
+    if let Some(sink) = agp_sink.as_mut() {
+        sinks.push(sink);
+    }
+    if let Some(sink) = bed_sink.as_mut() {
+        sinks.push(sink);
+    }
+    if let Some(sink) = path_fasta_sink.as_mut() {
+        sinks.push(sink);
+    }
+    if let Some(sink) = fasta_sink.as_mut() {
+        sinks.push(sink);
+    }
+    write_to_sinks(g, &records, &mut sinks)?;
+
+    if let Some(output) = &settings.annotated_gfa {
+        info!("Writing annotated graph to {}", output.to_str().unwrap());
+        write_annotated_gfa(g.clone(), &assignments, output, &hap_names)?;
+    }
+
+    summary.log(&hap_names);
     info!("All done");
     Ok(())
 }
 
+pub struct PrimaryAltSettings {
+    //node colors output
+    pub colors: Option<String>,
+    pub paths: Option<String>,
+    pub gaf_paths: bool,
+    pub agp: Option<String>,
+    //per-bubble primary/alt alleles output
+    pub bubbles: Option<String>,
+    pub unique_block_len: usize,
+    //unused nodes are only listed in paths output if their length is >= min_unused_len
+    //(none if not provided), AGP always covers all nodes
+    pub min_unused_len: Option<usize>,
+    //TSV overriding node lengths from the graph
+    pub node_lengths: Option<PathBuf>,
+}
+
+impl Default for PrimaryAltSettings {
+    fn default() -> Self {
+        Self {
+            colors: None,
+            paths: None,
+            gaf_paths: false,
+            agp: None,
+            bubbles: None,
+            unique_block_len: 500_000,
+            min_unused_len: Some(0),
+            node_lengths: None,
+        }
+    }
+}
+
 pub fn run_primary_alt_analysis(
     graph_fn: &PathBuf,
-    colors_fn: &Option<String>,
-    paths_fn: &Option<String>,
-    gaf_paths: bool,
+    settings: &PrimaryAltSettings,
 ) -> Result<(), Box<dyn Error>> {
-    let g = read_graph(graph_fn)?;
-    let unique_block_len = 500_000;
-    let linear_blocks = pseudo_hap::pseudo_hap_decompose(&g, unique_block_len);
+    let g = read_graph(
+        graph_fn,
+        ReadOptions::default(),
+        false,
+        settings.node_lengths.as_ref(),
+    )?;
+    primary_alt_analysis(&g, settings)
+}
 
-    if let Some(output) = colors_fn {
+//primary/alt analysis of the already loaded graph
+pub fn primary_alt_analysis(
+    g: &Graph,
+    settings: &PrimaryAltSettings,
+) -> Result<(), Box<dyn Error>> {
+    let gaf_paths = settings.gaf_paths;
+    let min_unused_len = settings.min_unused_len;
+    let linear_blocks = pseudo_hap::pseudo_hap_decompose(g, settings.unique_block_len);
+
+    if let Some(output) = &settings.colors {
         info!("Writing node colors to {}", output);
-        let mut output = File::create(output)?;
+        let mut output = create_output(output)?;
 
         let mut primary_nodes = HashSet::new();
         let mut alt_nodes = HashSet::new();
@@ -613,41 +2182,120 @@ pub fn run_primary_alt_analysis(
     }
 
     let used: HashSet<usize> = linear_blocks.iter().flat_map(|b| b.all_nodes()).collect();
+    info!("Primary blocks: {}", linear_blocks.len());
+    info!(
+        "Total primary length: {}",
+        linear_blocks
+            .iter()
+            .map(|b| b.instance_path().total_length(g))
+            .sum::<usize>()
+    );
+    info!(
+        "Alt nodes: {}",
+        linear_blocks
+            .iter()
+            .map(|b| b.known_alt_nodes().len())
+            .sum::<usize>()
+    );
+    info!("Unused nodes: {}", g.node_cnt() - used.len());
+
+    if let Some(output) = &settings.agp {
+        info!("Outputting scaffolds AGP to {}", output);
+        let mut records: Vec<PathRecord> = Vec::new();
+        for (block_id, block) in linear_blocks.iter().enumerate() {
+            records.push(PathRecord::new(
+                format!("primary_{block_id}"),
+                block.instance_path().clone(),
+                None,
+            ));
+            for (alt_id, alt_path) in block.alt_paths(g).into_iter().enumerate() {
+                records.push(PathRecord::new(
+                    format!("alt_{block_id}_{alt_id}"),
+                    alt_path,
+                    None,
+                ));
+            }
+        }
+        for (node_id, n) in g.all_nodes().enumerate() {
+            if !used.contains(&node_id) {
+                records.push(PathRecord {
+                    unused: true,
+                    ..PathRecord::new(
+                        format!("unused_{}", n.name),
+                        Path::new(Vertex::forward(node_id)),
+                        None,
+                    )
+                });
+            }
+        }
+        check_path_links(g, &records)?;
+        let mut sink = AgpSink::new(create_output(output)?)?;
+        write_to_sinks(g, &records, &mut [&mut sink])?;
+    }
 
-    if let Some(output) = paths_fn {
+    if let Some(output) = &settings.bubbles {
+        info!("Outputting per-bubble primary/alt alleles to {}", output);
+        let mut output = create_output(output)?;
+        writeln!(output, "block\tstart\tend\tprimary\talt")?;
+        for (block_id, block) in linear_blocks.iter().enumerate() {
+            //limiting the number of enumerated alleles within complex bubbles
+            for (primary, alts) in block.bubble_alleles(g, 100) {
+                let alt_str = if alts.is_empty() {
+                    String::from("NA")
+                } else {
+                    alts.iter().map(|p| p.print_format(g, gaf_paths)).join(";")
+                };
+                writeln!(
+                    output,
+                    "primary_{}\t{}\t{}\t{}\t{}",
+                    block_id,
+                    g.v_str(primary.start()),
+                    g.v_str(primary.end()),
+                    primary.print_format(g, gaf_paths),
+                    alt_str
+                )?;
+            }
+        }
+    }
+
+    if let Some(output) = &settings.paths {
         info!("Outputting paths in {}", output);
-        let mut output = File::create(output)?;
+        let mut output = create_output(output)?;
 
-        writeln!(output, "name\tlen\tpath\tassignment")?;
+        writeln!(output, "name\tlen\tpath\tassignment\tcircular")?;
 
         for (block_id, block) in linear_blocks.into_iter().enumerate() {
             writeln!(
                 output,
-                "primary_{}\t{}\t{}\tPRIMARY",
+                "primary_{}\t{}\t{}\tPRIMARY\t{}",
                 block_id,
-                block.instance_path().total_length(&g),
-                block.instance_path().print_format(&g, gaf_paths)
+                block.instance_path().total_length(g),
+                block.instance_path().print_format(g, gaf_paths),
+                block.instance_path().is_circular(g)
             )?;
-            for (alt_id, &known_alt) in block.known_alt_nodes().iter().enumerate() {
+            for (alt_id, alt_path) in block.alt_paths(g).into_iter().enumerate() {
                 writeln!(
                     output,
-                    "alt_{}_{}\t{}\t{}\tALT",
+                    "alt_{}_{}\t{}\t{}\tALT\t{}",
                     block_id,
                     alt_id,
-                    g.node(known_alt).length,
-                    Path::new(Vertex::forward(known_alt)).print_format(&g, gaf_paths)
+                    alt_path.total_length(g),
+                    alt_path.print_format(g, gaf_paths),
+                    alt_path.is_circular(g)
                 )?;
             }
         }
 
         for (node_id, n) in g.all_nodes().enumerate() {
-            if !used.contains(&node_id) {
+            if !used.contains(&node_id) && min_unused_len.is_some_and(|l| n.length >= l) {
+                let unused_path = Path::new(Vertex::forward(node_id));
                 writeln!(
                     output,
-                    "unused_{}\t{}\t{}\tNA",
+                    "unused_{}\t{}\t{}\tNA\t{}",
                     n.name,
                     n.length,
-                    Path::new(Vertex::forward(node_id)).print_format(&g, gaf_paths)
+                    unused_path.print_format(g, gaf_paths),
+                    unused_path.is_circular(g)
                 )?;
             }
         }
@@ -7,6 +7,11 @@ use env_logger::{Builder, Env, Target};
 #[derive(Parser, Debug)]
 #[command(name = "rukki", author = "Sergey Nurk", about = "extraction of paths from assembly graphs", long_about=None)]
 struct Args {
+    /// Number of threads (0 uses all available cores).
+    /// Outputs do not depend on the number of threads
+    #[clap(long, global = true, default_value_t = 0)]
+    threads: usize,
+
     #[clap(subcommand)]
     subcmd: Commands,
 }
@@ -14,19 +19,34 @@ struct Args {
 #[derive(Subcommand, Debug)]
 enum Commands {
     /// Trio-marker based analysis
-    Trio(rukki::TrioSettings),
+    Trio(Box<rukki::TrioSettings>),
+    /// Basic graph statistics
+    Stats(rukki::StatsSettings),
 }
 
 fn main() {
+    let args = Args::parse();
+
     //env_logger::init();
     let mut builder = Builder::from_env(Env::default().default_filter_or("info"));
-    builder.target(Target::Stdout);
+    //keeping stdout clean if any of the outputs goes there
+    let stdout_output = match &args.subcmd {
+        Commands::Stats(settings) => settings.writes_to_stdout(),
+        Commands::Trio(_) => std::env::args().any(|a| a == "-"),
+    };
+    if stdout_output {
+        builder.target(Target::Stderr);
+    } else {
+        builder.target(Target::Stdout);
+    }
     builder.init();
     //info!("Starting up");
 
     //info!("Cmd arguments: {:?}", env::args());
 
-    let args = Args::parse();
+    if let Err(e) = rukki::init_thread_pool(args.threads) {
+        warn!("Failed to initialize thread pool: {:?}", e);
+    }
 
     match &args.subcmd {
         Commands::Trio(settings) => {
@@ -38,5 +58,13 @@ fn main() {
                 Err(e) => info!("Some error happened {:?}", e),
             }
         }
+        Commands::Stats(settings) => {
+            info!("Computing graph statistics");
+
+            match rukki::run_stats(settings) {
+                Ok(()) => info!("Success"),
+                Err(e) => info!("Some error happened {:?}", e),
+            }
+        }
     }
 }
@@ -0,0 +1,477 @@
+use crate::graph::*;
+use crate::trio::{AssignmentStorage, TrioGroup, TrioInfo};
+use crate::{group_str, path_confidence, path_tag_summary, ConfidenceSettings, PathRecord};
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+//Consumer of the reported paths, which are passed one at a time
+//(sequences are reconstructed and written path by path)
+pub trait PathSink {
+    fn write_path(&mut self, g: &Graph, record: &PathRecord) -> Result<(), std::io::Error>;
+
+    //called once after all paths were passed
+    fn finish(&mut self) -> Result<(), std::io::Error> {
+        Ok(())
+    }
+}
+
+//single pass over the records, driving all the sinks
+pub fn write_to_sinks(
+    g: &Graph,
+    records: &[PathRecord],
+    sinks: &mut [&mut dyn PathSink],
+) -> Result<(), std::io::Error> {
+    for record in records {
+        for sink in sinks.iter_mut() {
+            sink.write_path(g, record)?;
+        }
+    }
+    for sink in sinks.iter_mut() {
+        sink.finish()?;
+    }
+    Ok(())
+}
+
+//tab-separated name, path, assignment, circularity flag and (optionally) tag summary and confidence
+//paths are printed according to the format (see Path::print_format_ext)
+pub struct TsvSink<'a, W: Write> {
+    output: W,
+    format: PathFormat,
+    hap_names: &'a (&'a str, &'a str),
+    tag_key: Option<&'a str>,
+    confidence: Option<(&'a HashMap<usize, TrioInfo>, &'a ConfidenceSettings)>,
+}
+
+impl<'a, W: Write> TsvSink<'a, W> {
+    //writes the header
+    pub fn new(
+        mut output: W,
+        format: PathFormat,
+        hap_names: &'a (&'a str, &'a str),
+        tag_key: Option<&'a str>,
+        confidence: Option<(&'a HashMap<usize, TrioInfo>, &'a ConfidenceSettings)>,
+    ) -> Result<Self, std::io::Error> {
+        write!(output, "name\tpath\tassignment\tcircular")?;
+        if let Some(key) = tag_key {
+            write!(output, "\t{key}_values\t{key}_purity")?;
+        }
+        if confidence.is_some() {
+            write!(output, "\tconfidence")?;
+        }
+        writeln!(output)?;
+        Ok(Self {
+            output,
+            format,
+            hap_names,
+            tag_key,
+            confidence,
+        })
+    }
+}
+
+impl<W: Write> PathSink for TsvSink<'_, W> {
+    fn write_path(&mut self, g: &Graph, record: &PathRecord) -> Result<(), std::io::Error> {
+        let path = &record.path;
+        write!(
+            self.output,
+            "{}\t{}\t{}\t{}",
+            record.name,
+            path.print_format_ext(g, self.format),
+            group_str(record.group, self.hap_names).to_uppercase(),
+            path.is_circular(g)
+        )?;
+        if let Some(key) = self.tag_key {
+            match path_tag_summary(g, path, key) {
+                Some((values, purity)) => {
+                    write!(self.output, "\t{}\t{:.3}", values.join(","), purity)?
+                }
+                None => write!(self.output, "\tNA\tNA")?,
+            }
+        }
+        if let Some((raw_cnts, confidence_settings)) = self.confidence {
+            match record
+                .group
+                .and_then(|x| path_confidence(g, path, x, raw_cnts, confidence_settings))
+            {
+                Some(c) => write!(self.output, "\t{c:.3}")?,
+                None => write!(self.output, "\tNA")?,
+            }
+        }
+        writeln!(self.output)
+    }
+
+    fn finish(&mut self) -> Result<(), std::io::Error> {
+        self.output.flush()
+    }
+}
+
+//GFA W-lines (see Path::to_walk_line) with path names used as sequence ids
+//haplotype index is 1 for maternal, 2 for paternal and 0 otherwise
+pub struct WalkSink<W: Write> {
+    output: W,
+    sample: String,
+}
+
+impl<W: Write> WalkSink<W> {
+    //writes the header
+    pub fn new(mut output: W, sample: &str) -> Result<Self, std::io::Error> {
+        writeln!(output, "H\tVN:Z:1.1")?;
+        Ok(Self {
+            output,
+            sample: String::from(sample),
+        })
+    }
+}
+
+impl<W: Write> PathSink for WalkSink<W> {
+    fn write_path(&mut self, g: &Graph, record: &PathRecord) -> Result<(), std::io::Error> {
+        let hap = match record.group {
+            Some(TrioGroup::MATERNAL) => 1,
+            Some(TrioGroup::PATERNAL) => 2,
+            _ => 0,
+        };
+        writeln!(
+            self.output,
+            "{}",
+            record.path.to_walk_line(g, &self.sample, hap, &record.name)
+        )
+    }
+
+    fn finish(&mut self) -> Result<(), std::io::Error> {
+        self.output.flush()
+    }
+}
+
+//AGP v2.1, every path is reported as a separate scaffold (object) named by the path name
+//nodes become 'W' components (overlaps with previous nodes are trimmed),
+//gaps (jumps) become 'N' lines of the estimated size (non-positive gaps are treated as overlaps)
+pub struct AgpSink<W: Write> {
+    output: W,
+}
+
+impl<W: Write> AgpSink<W> {
+    //writes the header
+    pub fn new(mut output: W) -> Result<Self, std::io::Error> {
+        writeln!(output, "##agp-version 2.1")?;
+        Ok(Self { output })
+    }
+}
+
+impl<W: Write> PathSink for AgpSink<W> {
+    fn write_path(&mut self, g: &Graph, record: &PathRecord) -> Result<(), std::io::Error> {
+        let (name, path) = (&record.name, &record.path);
+        let mut pos = 0;
+        let mut part = 0;
+        for (i, &v) in path.vertices().iter().enumerate() {
+            let mut trim = 0;
+            if i > 0 {
+                let l = path.general_link_at(i - 1);
+                match l {
+                    GeneralizedLink::GAP(gap_info) if gap_info.gap_size > 0 => {
+                        part += 1;
+                        writeln!(
+                            self.output,
+                            "{}\t{}\t{}\t{}\tN\t{}\tscaffold\tyes\tunspecified",
+                            name,
+                            pos + 1,
+                            pos + gap_info.gap_size as usize,
+                            part,
+                            gap_info.gap_size
+                        )?;
+                        pos += gap_info.gap_size as usize;
+                    }
+                    _ => trim = l.overlap() as usize,
+                }
+            }
+            let node_len = g.vertex_length(v);
+            let trim = std::cmp::min(trim, node_len);
+            if trim == node_len {
+                continue;
+            }
+            //trimming prefix of the oriented node
+            let (comp_beg, comp_end, orientation) = match v.direction {
+                Direction::FORWARD => (trim + 1, node_len, '+'),
+                Direction::REVERSE => (1, node_len - trim, '-'),
+            };
+            part += 1;
+            writeln!(
+                self.output,
+                "{}\t{}\t{}\t{}\tW\t{}\t{}\t{}\t{}",
+                name,
+                pos + 1,
+                pos + node_len - trim,
+                part,
+                g.name(v.node_id),
+                comp_beg,
+                comp_end,
+                orientation
+            )?;
+            pos += node_len - trim;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), std::io::Error> {
+        self.output.flush()
+    }
+}
+
+fn write_fasta_record<W: Write>(
+    output: &mut W,
+    g: &Graph,
+    name: &str,
+    path: &Path,
+) -> Result<(), std::io::Error> {
+    let seq = path.sequence(g).ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("Sequence of some node in path {name} is not available"),
+        )
+    })?;
+    writeln!(output, ">{name}")?;
+    writeln!(output, "{seq}")
+}
+
+//BED6 with placement of every node within the path sequence (0-based, half-open),
+//spans of consecutive nodes overlap by the link overlap size,
+//gaps (jumps) shift the next node by the estimated gap size
+pub struct BedSink<W: Write> {
+    output: W,
+}
+
+impl<W: Write> BedSink<W> {
+    pub fn new(output: W) -> Self {
+        Self { output }
+    }
+}
+
+impl<W: Write> PathSink for BedSink<W> {
+    fn write_path(&mut self, g: &Graph, record: &PathRecord) -> Result<(), std::io::Error> {
+        let path = &record.path;
+        for (&v, start) in path.vertices().iter().zip(path.node_offsets(g)) {
+            let orientation = match v.direction {
+                Direction::FORWARD => '+',
+                Direction::REVERSE => '-',
+            };
+            writeln!(
+                self.output,
+                "{}\t{}\t{}\t{}\t0\t{}",
+                record.name,
+                start,
+                start + g.vertex_length(v) as i64,
+                g.name(v.node_id),
+                orientation
+            )?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), std::io::Error> {
+        self.output.flush()
+    }
+}
+
+//one FASTA record per path (under the path name)
+pub struct FastaSink<W: Write> {
+    output: W,
+}
+
+impl<W: Write> FastaSink<W> {
+    pub fn new(output: W) -> Self {
+        Self { output }
+    }
+}
+
+impl<W: Write> PathSink for FastaSink<W> {
+    fn write_path(&mut self, g: &Graph, record: &PathRecord) -> Result<(), std::io::Error> {
+        write_fasta_record(&mut self.output, g, &record.name, &record.path)
+    }
+
+    fn finish(&mut self) -> Result<(), std::io::Error> {
+        self.output.flush()
+    }
+}
+
+//Writes <prefix>.hap1.fasta (maternal) and <prefix>.hap2.fasta (paternal).
+//Homozygous nodes not covered by haplo-paths go into both files (under the same name),
+//or into <prefix>.shared.fasta if separate_shared is set.
+//Unassigned nodes are not reported.
+pub struct HapFastaSink<'a> {
+    hap1: BufWriter<File>,
+    hap2: BufWriter<File>,
+    shared: Option<BufWriter<File>>,
+    reported_shared: HashSet<usize>,
+    assignments: &'a AssignmentStorage,
+    hap_names: &'a (&'a str, &'a str),
+}
+
+impl<'a> HapFastaSink<'a> {
+    pub fn create(
+        prefix: &str,
+        separate_shared: bool,
+        assignments: &'a AssignmentStorage,
+        hap_names: &'a (&'a str, &'a str),
+    ) -> Result<Self, std::io::Error> {
+        let shared = if separate_shared {
+            Some(BufWriter::new(File::create(format!(
+                "{prefix}.shared.fasta"
+            ))?))
+        } else {
+            None
+        };
+        Ok(Self {
+            hap1: BufWriter::new(File::create(format!("{prefix}.hap1.fasta"))?),
+            hap2: BufWriter::new(File::create(format!("{prefix}.hap2.fasta"))?),
+            shared,
+            reported_shared: HashSet::new(),
+            assignments,
+            hap_names,
+        })
+    }
+}
+
+impl PathSink for HapFastaSink<'_> {
+    fn write_path(&mut self, g: &Graph, record: &PathRecord) -> Result<(), std::io::Error> {
+        let output = match record.group {
+            Some(TrioGroup::MATERNAL) => &mut self.hap1,
+            Some(TrioGroup::PATERNAL) => &mut self.hap2,
+            _ => return Ok(()),
+        };
+        let path = &record.path;
+        let node_id = path.start().node_id;
+        if record.unused && self.assignments.group(node_id) == Some(TrioGroup::HOMOZYGOUS) {
+            let name = format!(
+                "{}_unused_{}",
+                group_str(Some(TrioGroup::HOMOZYGOUS), self.hap_names),
+                g.name(node_id)
+            );
+            match self.shared.as_mut() {
+                Some(shared) => {
+                    if self.reported_shared.insert(node_id) {
+                        write_fasta_record(shared, g, &name, path)?;
+                    }
+                }
+                None => write_fasta_record(output, g, &name, path)?,
+            }
+            Ok(())
+        } else {
+            write_fasta_record(output, g, &record.name, path)
+        }
+    }
+
+    fn finish(&mut self) -> Result<(), std::io::Error> {
+        self.hap1.flush()?;
+        self.hap2.flush()?;
+        if let Some(shared) = self.shared.as_mut() {
+            shared.flush()?;
+        }
+        Ok(())
+    }
+}
+
+//Splits the paths between per-haplotype sinks (same conventions as HapFastaSink):
+//maternal and paternal paths go to hap1 and hap2 sinks respectively,
+//homozygous nodes not covered by haplo-paths are reported once into the shared sink,
+//unassigned paths go into the unassigned sink.
+pub struct GroupSplitSink<'a> {
+    hap1: Box<dyn PathSink + 'a>,
+    hap2: Box<dyn PathSink + 'a>,
+    shared: Box<dyn PathSink + 'a>,
+    unassigned: Box<dyn PathSink + 'a>,
+    reported_shared: HashSet<usize>,
+    assignments: &'a AssignmentStorage,
+    hap_names: &'a (&'a str, &'a str),
+}
+
+impl<'a> GroupSplitSink<'a> {
+    //sinks are created for <prefix>.hap1.paths, <prefix>.hap2.paths,
+    //<prefix>.shared.paths and <prefix>.unassigned.paths
+    pub fn create<F>(
+        prefix: &str,
+        mut create_sink: F,
+        assignments: &'a AssignmentStorage,
+        hap_names: &'a (&'a str, &'a str),
+    ) -> Result<Self, std::io::Error>
+    where
+        F: FnMut(String) -> Result<Box<dyn PathSink + 'a>, std::io::Error>,
+    {
+        Ok(Self {
+            hap1: create_sink(format!("{prefix}.hap1.paths"))?,
+            hap2: create_sink(format!("{prefix}.hap2.paths"))?,
+            shared: create_sink(format!("{prefix}.shared.paths"))?,
+            unassigned: create_sink(format!("{prefix}.unassigned.paths"))?,
+            reported_shared: HashSet::new(),
+            assignments,
+            hap_names,
+        })
+    }
+}
+
+impl PathSink for GroupSplitSink<'_> {
+    fn write_path(&mut self, g: &Graph, record: &PathRecord) -> Result<(), std::io::Error> {
+        let output = match record.group {
+            Some(TrioGroup::MATERNAL) => &mut self.hap1,
+            Some(TrioGroup::PATERNAL) => &mut self.hap2,
+            _ => return self.unassigned.write_path(g, record),
+        };
+        let node_id = record.path.start().node_id;
+        if record.unused && self.assignments.group(node_id) == Some(TrioGroup::HOMOZYGOUS) {
+            if self.reported_shared.insert(node_id) {
+                let shared_record = PathRecord {
+                    name: format!(
+                        "{}_unused_{}",
+                        group_str(Some(TrioGroup::HOMOZYGOUS), self.hap_names),
+                        g.name(node_id)
+                    ),
+                    group: Some(TrioGroup::HOMOZYGOUS),
+                    ..record.clone()
+                };
+                self.shared.write_path(g, &shared_record)?;
+            }
+            Ok(())
+        } else {
+            output.write_path(g, record)
+        }
+    }
+
+    fn finish(&mut self) -> Result<(), std::io::Error> {
+        self.hap1.finish()?;
+        self.hap2.finish()?;
+        self.shared.finish()?;
+        self.unassigned.finish()
+    }
+}
+
+//Passes the paths to the wrapped sink, except for the trivial paths of unused nodes
+//(see PathRecord::unused) which are dropped if shorter than the threshold (all if None)
+pub struct UnusedFilterSink<'a> {
+    inner: Box<dyn PathSink + 'a>,
+    min_unused_len: Option<usize>,
+}
+
+impl<'a> UnusedFilterSink<'a> {
+    pub fn new(inner: Box<dyn PathSink + 'a>, min_unused_len: Option<usize>) -> Self {
+        Self {
+            inner,
+            min_unused_len,
+        }
+    }
+}
+
+impl PathSink for UnusedFilterSink<'_> {
+    fn write_path(&mut self, g: &Graph, record: &PathRecord) -> Result<(), std::io::Error> {
+        if record.unused
+            && self
+                .min_unused_len
+                .is_none_or(|l| g.node(record.path.start().node_id).length < l)
+        {
+            return Ok(());
+        }
+        self.inner.write_path(g, record)
+    }
+
+    fn finish(&mut self) -> Result<(), std::io::Error> {
+        self.inner.finish()
+    }
+}
@@ -21,6 +21,100 @@ impl LinearBlock {
         &self.known_alt_nodes
     }
 
+    //alt nodes joined into maximal non-branching walks (via links between alt nodes),
+    //oriented consistently with the instance path when adjacent to it
+    pub fn alt_paths(&self, g: &Graph) -> Vec<Path> {
+        let alt_links = |v: Vertex| {
+            g.outgoing_edges(v)
+                .iter()
+                .copied()
+                .filter(|l| self.known_alt_nodes.contains(&l.end.node_id))
+                .collect::<Vec<Link>>()
+        };
+        let alt_in_degree = |v: Vertex| alt_links(v.rc()).len();
+        let primary: HashSet<Vertex> = self.instance_path.vertices().iter().copied().collect();
+        let follows_primary = |p: &Path| {
+            g.incoming_edges(p.start())
+                .any(|l| primary.contains(&l.start))
+                || g.outgoing_edges(p.end())
+                    .iter()
+                    .any(|l| primary.contains(&l.end))
+        };
+
+        let mut alt_nodes: Vec<usize> = self.known_alt_nodes.iter().copied().collect();
+        alt_nodes.sort();
+        let mut used = HashSet::new();
+        let mut paths = Vec::new();
+        for &node_id in &alt_nodes {
+            if used.contains(&node_id) {
+                continue;
+            }
+            //going back to the start of the walk
+            let mut v = Vertex::forward(node_id);
+            let mut visited = HashSet::from([node_id]);
+            while alt_in_degree(v) == 1 {
+                let u = alt_links(v.rc())[0].end.rc();
+                if alt_links(u).len() != 1 || !visited.insert(u.node_id) {
+                    break;
+                }
+                v = u;
+            }
+            let mut p = Path::new(v);
+            loop {
+                let links = alt_links(p.end());
+                if links.len() != 1
+                    || alt_in_degree(links[0].end) != 1
+                    || p.in_path(links[0].end.node_id)
+                {
+                    break;
+                }
+                p.append(links[0]);
+            }
+            used.extend(p.vertices().iter().map(|v| v.node_id));
+            if !follows_primary(&p) {
+                let rc = p.clone().reverse_complement();
+                if follows_primary(&rc) {
+                    p = rc;
+                }
+            }
+            paths.push(p);
+        }
+        paths
+    }
+
+    //superbubbles along the instance path (in path order), each reported as
+    //primary allele (sub-path of the instance path) together with alternative ones
+    //(at most max_paths paths through every bubble are considered)
+    pub fn bubble_alleles(&self, g: &Graph, max_paths: usize) -> Vec<(Path, Vec<Path>)> {
+        let p = &self.instance_path;
+        let params = superbubble::SbSearchParams::unrestricted();
+        let mut answer = Vec::new();
+        let mut i = 0;
+        while i + 1 < p.len() {
+            let end_pos =
+                superbubble::find_superbubble(g, p.vertices()[i], &params).and_then(|bubble| {
+                    let j = (i + 1..p.len()).find(|&j| p.vertices()[j] == bubble.end_vertex())?;
+                    Some((bubble, j))
+                });
+            let Some((bubble, j)) = end_pos else {
+                i += 1;
+                continue;
+            };
+            let mut primary = Path::new(p.vertices()[i]);
+            for k in i..j {
+                primary.append(p.link_at(k));
+            }
+            let alts = bubble
+                .all_paths(g, max_paths)
+                .into_iter()
+                .filter(|alt| *alt != primary)
+                .collect();
+            answer.push((primary, alts));
+            i = j;
+        }
+        answer
+    }
+
     pub fn all_nodes(&self) -> impl Iterator<Item = usize> + '_ {
         self.instance_path
             .vertices()
@@ -99,8 +193,8 @@ impl LinearBlock {
     }
 
     //fn is_bridge(&self, g: &Graph) -> bool {
-    //    g.incoming_edge_cnt(self.instance_path.start()) == 1
-    //        && g.outgoing_edge_cnt(self.instance_path.end()) == 1
+    //    g.in_degree(self.instance_path.start()) == 1
+    //        && g.out_degree(self.instance_path.end()) == 1
     //}
 
     fn reverse_complement(self) -> LinearBlock {
@@ -115,13 +209,13 @@ impl LinearBlock {
 //todo maybe support blocks here? (use block search and is_block method)
 #[allow(clippy::many_single_char_names)]
 fn bridged_by_vertex(g: &Graph, v: Vertex) -> Option<Path> {
-    if g.incoming_edge_cnt(v) == 1 && g.outgoing_edge_cnt(v) == 1 {
-        let u = g.incoming_edges(v)[0].start;
+    if g.in_degree(v) == 1 && g.out_degree(v) == 1 {
+        let u = g.incoming_edges(v).next().unwrap().start;
         let w = g.outgoing_edges(v)[0].end;
         if u.node_id == v.node_id || w.node_id == v.node_id || w.node_id == u.node_id {
             return None;
         }
-        let mut p = Path::from_link(g.incoming_edges(v)[0]);
+        let mut p = Path::from_link(g.incoming_edges(v).next().unwrap());
         p.append(g.outgoing_edges(v)[0]);
         Some(p)
     } else {
@@ -130,7 +224,7 @@ fn bridged_by_vertex(g: &Graph, v: Vertex) -> Option<Path> {
 }
 
 fn other_outgoing(g: &Graph, v: Vertex, l: Link) -> Option<Link> {
-    if g.outgoing_edge_cnt(v) == 2 {
+    if g.out_degree(v) == 2 {
         let alt = g
             .outgoing_edges(v)
             .iter()
@@ -144,13 +238,8 @@ fn other_outgoing(g: &Graph, v: Vertex, l: Link) -> Option<Link> {
 }
 
 fn other_incoming(g: &Graph, v: Vertex, l: Link) -> Option<Link> {
-    if g.incoming_edge_cnt(v) == 2 {
-        let alt = g
-            .incoming_edges(v)
-            .iter()
-            .copied()
-            .find(|&x| x != l)
-            .unwrap();
+    if g.in_degree(v) == 2 {
+        let alt = g.incoming_edges(v).find(|&x| x != l).unwrap();
         assert!(alt.start != l.start);
         return Some(alt);
     }
@@ -183,7 +272,7 @@ fn unique_block_ahead(g: &Graph, v: Vertex, unique_block_len: usize) -> Option<L
 }
 
 fn unambiguous_outgoing(g: &Graph, v: Vertex) -> Option<Link> {
-    match g.outgoing_edge_cnt(v) {
+    match g.out_degree(v) {
         1 => Some(g.outgoing_edges(v)[0]),
         _ => None,
     }
@@ -219,11 +308,12 @@ fn extension_in_deadend(g: &Graph, v: Vertex, unique_block_len: usize) -> Option
 //- v - w -      - v - o x
 //l -- 'horizontal' link
 fn extension_out_deadend(g: &Graph, v: Vertex, unique_block_len: usize) -> Option<LinearBlock> {
-    if g.outgoing_edge_cnt(v) == 2 {
+    if g.out_degree(v) == 2 {
         //TODO generalize?
         let mut deadend_links: Vec<Link> = g
             .outgoing_edges(v)
-            .into_iter()
+            .iter()
+            .copied()
             .filter(|&l| is_deadend(g, l.end))
             .collect();
         deadend_links.sort_by_key(|&l| g.vertex_length(l.end));
@@ -300,7 +390,7 @@ fn admissible_alt_class(
 
 //TODO Generalize maybe support simple blocks and/or extra dead-ends (need to then return subgraph info)
 fn is_deadend(g: &Graph, v: Vertex) -> bool {
-    g.outgoing_edge_cnt(v) == 0 || g.incoming_edge_cnt(v) == 0
+    g.out_degree(v) == 0 || g.in_degree(v) == 0
 }
 
 fn visited_if_reachable(
@@ -1,17 +1,20 @@
 use crate::graph::*;
 use crate::graph_algos::dfs;
 use crate::graph_algos::superbubble;
+use itertools::Itertools;
 use log::debug;
 use log::info;
+use log::warn;
+use serde::Serialize;
 use std::cmp::{max, min};
 use std::collections::{HashMap, HashSet};
-use std::fs::File;
+use std::io::BufRead;
 use std::io::Result as IOResult;
-use std::io::{BufRead, BufReader};
 use std::path::PathBuf;
+use std::str::FromStr;
 
 //TODO add UNASSIGNED to display useful info for all nodes
-#[derive(Copy, Clone, Debug, PartialEq, PartialOrd, Eq, Ord)]
+#[derive(Copy, Clone, Debug, PartialEq, PartialOrd, Eq, Ord, Serialize)]
 pub enum TrioGroup {
     MATERNAL,
     PATERNAL,
@@ -20,6 +23,14 @@ pub enum TrioGroup {
 }
 
 impl TrioGroup {
+    //symmetric; ISSUE is incompatible with everything (including itself),
+    //MATERNAL and PATERNAL are incompatible with each other,
+    //HOMOZYGOUS is compatible with everything except ISSUE:
+    //            MATERNAL  PATERNAL  HOMOZYGOUS  ISSUE
+    //MATERNAL    -         +         -           +
+    //PATERNAL    +         -         -           +
+    //HOMOZYGOUS  -         -         -           +
+    //ISSUE       +         +         +           +
     pub fn incompatible(g1: TrioGroup, g2: TrioGroup) -> bool {
         g1 == TrioGroup::ISSUE
             || g2 == TrioGroup::ISSUE
@@ -35,6 +46,10 @@ impl TrioGroup {
         matches!(*self, TrioGroup::MATERNAL | TrioGroup::PATERNAL)
     }
 
+    //group of a node shared between (or used by) two groups
+    //symmetric; same groups blend into themselves, any different pair into HOMOZYGOUS
+    //(e.g. MATERNAL + HOMOZYGOUS = HOMOZYGOUS, MATERNAL + PATERNAL = HOMOZYGOUS)
+    //panics on ISSUE
     pub fn blend(g1: TrioGroup, g2: TrioGroup) -> TrioGroup {
         assert!(g1 != TrioGroup::ISSUE && g2 != TrioGroup::ISSUE);
         if g1 == g2 {
@@ -44,6 +59,7 @@ impl TrioGroup {
         }
     }
 
+    //None acts as identity
     pub fn optional_blend(og1: Option<TrioGroup>, og2: Option<TrioGroup>) -> Option<TrioGroup> {
         match og1 {
             None => og2,
@@ -55,10 +71,28 @@ impl TrioGroup {
     }
 }
 
-#[derive(Clone, Debug)]
+//expects upper-case group name as in the outputs (e.g. MATERNAL)
+impl FromStr for TrioGroup {
+    type Err = String;
+
+    fn from_str(group_str: &str) -> Result<Self, Self::Err> {
+        match group_str {
+            "MATERNAL" => Ok(TrioGroup::MATERNAL),
+            "PATERNAL" => Ok(TrioGroup::PATERNAL),
+            "HOMOZYGOUS" => Ok(TrioGroup::HOMOZYGOUS),
+            "ISSUE" => Ok(TrioGroup::ISSUE),
+            _ => Err(format!("Invalid group string {group_str}")),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
 pub struct Assignment {
     pub group: TrioGroup,
-    pub info: String,
+    //id of the info string interned within the storage (see AssignmentStorage::info)
+    info_id: u32,
+    //see binomial_confidence, NaN if assignment isn't based on marker counts
+    pub confidence: f32,
 }
 
 #[derive(Clone, Debug)]
@@ -78,30 +112,171 @@ impl TrioInfo {
     }
 }
 
-pub fn read_trio(path: &PathBuf) -> IOResult<Vec<TrioInfo>> {
+//1 - P(X >= max(mat, pat)) for X ~ Binomial(mat + pat, 0.5),
+//i.e. confidence that the observed marker split isn't produced by a balanced (non-haplotype-specific) node
+//0 if there are no markers
+pub fn binomial_confidence(mat: usize, pat: usize) -> f64 {
+    let n = mat + pat;
+    if n == 0 {
+        return 0.;
+    }
+    let x = max(mat, pat);
+    let ln_half_n = (n as f64) * 0.5_f64.ln();
+    //ln(C(n, x))
+    let mut ln_term: f64 = (1..=x)
+        .map(|i| ((n - x + i) as f64).ln() - (i as f64).ln())
+        .sum();
+    //terms decrease starting from x >= n/2
+    let mut tail = 0.;
+    for k in x..=n {
+        let term = (ln_term + ln_half_n).exp();
+        tail += term;
+        if term < tail * 1e-12 {
+            break;
+        }
+        ln_term += ((n - k) as f64).ln() - ((k + 1) as f64).ln();
+    }
+    (1. - tail).clamp(0., 1.)
+}
+
+//column of the marker counts file, given by (0-based) index or by header name
+#[derive(Clone, Debug, PartialEq)]
+pub enum ColumnRef {
+    Index(usize),
+    Name(String),
+}
+
+//layout of the marker counts file
+#[derive(Clone, Debug, PartialEq)]
+pub struct TrioFileSpec {
+    pub node: ColumnRef,
+    pub mat: ColumnRef,
+    pub pat: ColumnRef,
+}
+
+impl Default for TrioFileSpec {
+    fn default() -> Self {
+        Self {
+            node: ColumnRef::Index(0),
+            mat: ColumnRef::Index(1),
+            pat: ColumnRef::Index(2),
+        }
+    }
+}
+
+impl TrioFileSpec {
+    //comma separated node, maternal and paternal columns, each is either a 1-based column number or a header name
+    pub fn parse(s: &str) -> Option<TrioFileSpec> {
+        let refs: Vec<ColumnRef> = s
+            .split(',')
+            .map(|c| match c.trim().parse::<usize>() {
+                Ok(0) => None,
+                Ok(i) => Some(ColumnRef::Index(i - 1)),
+                Err(_) if !c.trim().is_empty() => Some(ColumnRef::Name(String::from(c.trim()))),
+                Err(_) => None,
+            })
+            .collect::<Option<_>>()?;
+        match &refs[..] {
+            [node, mat, pat] => Some(TrioFileSpec {
+                node: node.clone(),
+                mat: mat.clone(),
+                pat: pat.clone(),
+            }),
+            _ => None,
+        }
+    }
+
+    fn uses_names(&self) -> bool {
+        [&self.node, &self.mat, &self.pat]
+            .iter()
+            .any(|c| matches!(c, ColumnRef::Name(_)))
+    }
+}
+
+//First line is treated as header if it starts with 'node' or 'contig' (case-insensitive),
+//or if any of the columns is referenced by name (header is required then)
+pub fn read_trio(path: &PathBuf, spec: &TrioFileSpec) -> IOResult<Vec<TrioInfo>> {
+    let invalid = |line_num: usize, msg: String| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("{}, line {}: {}", path.display(), line_num, msg),
+        )
+    };
     let mut infos = Vec::new();
-    let file = File::open(path)?;
-    for line in BufReader::new(file).lines() {
+    let mut idx = None;
+    //gzipped files are decompressed on the fly
+    for (i, line) in crate::open_input(path)?.lines().enumerate() {
         let l = line?;
         let split: Vec<&str> = l.trim().split('\t').collect();
-        if &split[0].to_lowercase() != "node" && &split[0].to_lowercase() != "contig" {
-            let node_name = String::from(split[0]);
-            let mat: usize = split[1].parse().expect("Invalid maternal count");
-            let pat: usize = split[2].parse().expect("Invalid paternal count");
-            infos.push(TrioInfo {
-                node_name,
-                mat,
-                pat,
-            })
+        if i == 0 {
+            let first = split[0].to_lowercase();
+            if spec.uses_names() || first == "node" || first == "contig" {
+                let resolve = |c: &ColumnRef| match c {
+                    ColumnRef::Index(i) => Some(*i),
+                    ColumnRef::Name(name) => split.iter().position(|h| h == name),
+                };
+                idx = Some(
+                    [&spec.node, &spec.mat, &spec.pat]
+                        .iter()
+                        .map(|c| {
+                            resolve(c).ok_or_else(|| {
+                                invalid(1, format!("column {c:?} not found in the header"))
+                            })
+                        })
+                        .collect::<IOResult<Vec<usize>>>()?,
+                );
+                continue;
+            }
+        }
+        if l.trim().is_empty() {
+            continue;
         }
+        let idx = idx.get_or_insert_with(|| {
+            [&spec.node, &spec.mat, &spec.pat]
+                .iter()
+                .map(|c| match c {
+                    ColumnRef::Index(i) => *i,
+                    ColumnRef::Name(_) => unreachable!(),
+                })
+                .collect()
+        });
+        let field = |j: usize| {
+            split
+                .get(idx[j])
+                .ok_or_else(|| invalid(i + 1, format!("missing column {}", idx[j] + 1)))
+        };
+        let cnt = |j: usize| -> IOResult<usize> {
+            let f = field(j)?;
+            f.parse()
+                .map_err(|_| invalid(i + 1, format!("invalid marker count '{f}'")))
+        };
+        infos.push(TrioInfo {
+            node_name: String::from(*field(0)?),
+            mat: cnt(1)?,
+            pat: cnt(2)?,
+        });
     }
     Ok(infos)
 }
 
+//sparse representation for general use,
+//dense (indexed by node id) one for bookkeeping over large graphs
+#[derive(Clone)]
+enum Storage {
+    Sparse(HashMap<usize, Assignment>),
+    Dense(Vec<Option<Assignment>>),
+}
+
+const EMPTY_INFO_ID: u32 = 0;
+
 //TODO add template parameter
+//info strings are interned, since the same few values are shared by most of the nodes
 #[derive(Clone)]
 pub struct AssignmentStorage {
-    storage: HashMap<usize, Assignment>,
+    storage: Storage,
+    infos: Vec<String>,
+    info_ids: HashMap<String, u32>,
+    last_info_id: Option<u32>,
 }
 
 impl Default for AssignmentStorage {
@@ -113,17 +288,67 @@ impl Default for AssignmentStorage {
 //TODO remove by_name methods
 impl AssignmentStorage {
     pub fn new() -> AssignmentStorage {
+        Self::with_storage(Storage::Sparse(HashMap::new()))
+    }
+
+    //preallocated storage for node ids 0..node_cnt
+    //avoids hashing overhead when most of the nodes get assigned
+    pub fn new_dense(node_cnt: usize) -> AssignmentStorage {
+        Self::with_storage(Storage::Dense(vec![None; node_cnt]))
+    }
+
+    fn with_storage(storage: Storage) -> AssignmentStorage {
         AssignmentStorage {
-            storage: HashMap::new(),
+            storage,
+            infos: vec![String::new()],
+            info_ids: HashMap::new(),
+            last_info_id: None,
         }
     }
 
+    #[inline]
+    fn intern(&mut self, info: &str) -> u32 {
+        //empty info (e.g. on group updates) is the most common one
+        if info.is_empty() {
+            return EMPTY_INFO_ID;
+        }
+        //consecutive assignments typically share the info
+        if let Some(id) = self.last_info_id {
+            if self.infos[id as usize] == info {
+                return id;
+            }
+        }
+        let id = self.intern_new(info);
+        self.last_info_id = Some(id);
+        id
+    }
+
+    fn intern_new(&mut self, info: &str) -> u32 {
+        if let Some(&id) = self.info_ids.get(info) {
+            return id;
+        }
+        let id = u32::try_from(self.infos.len()).expect("Too many distinct assignment infos");
+        self.infos.push(String::from(info));
+        self.info_ids.insert(String::from(info), id);
+        id
+    }
+
+    //in increasing order of node ids
     pub fn assigned(&self) -> impl Iterator<Item = usize> + '_ {
-        self.storage.keys().copied()
+        let it: Box<dyn Iterator<Item = usize>> = match &self.storage {
+            Storage::Sparse(m) => Box::new(m.keys().copied().sorted()),
+            Storage::Dense(v) => Box::new(
+                v.iter()
+                    .enumerate()
+                    .filter(|(_, a)| a.is_some())
+                    .map(|(node_id, _)| node_id),
+            ),
+        };
+        it
     }
 
     pub fn is_definite(&self, node_id: usize) -> bool {
-        if let Some(assign) = self.storage.get(&node_id) {
+        if let Some(assign) = self.get(node_id) {
             if TrioGroup::is_definite(&assign.group) {
                 return true;
             }
@@ -131,26 +356,28 @@ impl AssignmentStorage {
         false
     }
 
-    pub fn assign<S: Into<String>>(
+    pub fn assign<S: AsRef<str>>(
         &mut self,
         node_id: usize,
         group: TrioGroup,
         info: S,
     ) -> Option<Assignment> {
-        self.storage.insert(
-            node_id,
-            Assignment {
-                group,
-                info: info.into(),
-            },
-        )
+        let assign = Assignment {
+            group,
+            info_id: self.intern(info.as_ref()),
+            confidence: f32::NAN,
+        };
+        match &mut self.storage {
+            Storage::Sparse(m) => m.insert(node_id, assign),
+            Storage::Dense(v) => v[node_id].replace(assign),
+        }
     }
 
     pub fn update_group(&mut self, node_id: usize, group: TrioGroup) {
         match self.group(node_id) {
             //FIXME how to simultaneously check key and get mutable reference to stored value?
             Some(exist_group) => {
-                self.storage.get_mut(&node_id).unwrap().group = TrioGroup::blend(exist_group, group)
+                self.get_mut(node_id).unwrap().group = TrioGroup::blend(exist_group, group)
             }
             None => {
                 self.assign(node_id, group, "");
@@ -165,25 +392,66 @@ impl AssignmentStorage {
     }
 
     pub fn get(&self, node_id: usize) -> Option<&Assignment> {
-        self.storage.get(&node_id)
+        match &self.storage {
+            Storage::Sparse(m) => m.get(&node_id),
+            Storage::Dense(v) => v.get(node_id)?.as_ref(),
+        }
     }
 
     pub fn get_mut(&mut self, node_id: usize) -> Option<&mut Assignment> {
-        self.storage.get_mut(&node_id)
+        match &mut self.storage {
+            Storage::Sparse(m) => m.get_mut(&node_id),
+            Storage::Dense(v) => v.get_mut(node_id)?.as_mut(),
+        }
     }
 
     pub fn contains(&self, node_id: usize) -> bool {
-        self.storage.contains_key(&node_id)
+        self.get(node_id).is_some()
+    }
+
+    //info provided when the node was assigned
+    pub fn info(&self, node_id: usize) -> Option<&str> {
+        self.get(node_id)
+            .map(|assign| self.infos[assign.info_id as usize].as_str())
+    }
+
+    //no-op for unassigned nodes
+    pub fn set_info(&mut self, node_id: usize, info: &str) {
+        if self.contains(node_id) {
+            let info_id = self.intern(info);
+            self.get_mut(node_id).unwrap().info_id = info_id;
+        }
     }
 
     pub fn group(&self, node_id: usize) -> Option<TrioGroup> {
-        self.storage.get(&node_id).map(|assign| assign.group)
+        self.get(node_id).map(|assign| assign.group)
+    }
+
+    //groups keyed by node names (for cross-referencing with external files)
+    pub fn groups_by_name<'g>(&self, g: &'g Graph) -> HashMap<&'g str, TrioGroup> {
+        self.assigned()
+            .map(|node_id| (g.name(node_id), self.group(node_id).unwrap()))
+            .collect()
+    }
+
+    //nodes with group different from the one in the other (e.g. earlier) storage, sorted by id
+    pub fn changed_nodes(&self, other: &AssignmentStorage) -> Vec<usize> {
+        let mut changed: Vec<usize> = self
+            .assigned()
+            .chain(other.assigned())
+            .filter(|&node_id| self.group(node_id) != other.group(node_id))
+            .collect();
+        changed.sort();
+        changed.dedup();
+        changed
     }
 }
 
 pub struct GroupAssignmentSettings {
     /// Minimal number of parent-specific markers required for assigning parental group to a node
     pub assign_cnt: usize,
+    /// If set, minimal density of parent-specific markers (per kb) is required instead of assign_cnt
+    pub assign_density: Option<f64>,
     /// Require at least (node_length / <value>) markers within the node for parental group assignment
     pub assign_sparsity: usize,
     /// Sets minimal marker excess for assigning a parental group to <value>:1
@@ -204,6 +472,7 @@ impl Default for GroupAssignmentSettings {
     fn default() -> Self {
         Self {
             assign_cnt: 10,
+            assign_density: None,
             assign_sparsity: 10_000,
             assign_ratio: 5.,
             solid_ratio: 5.,
@@ -231,15 +500,21 @@ pub fn assign_parental_groups(
             settings.issue_cnt, settings.issue_sparsity, settings.issue_ratio);
     assert!(settings.issue_ratio <= settings.assign_ratio);
 
+    let markers_per_kb = |tot: usize, node_len: usize| 1000. * (tot as f64) / (node_len as f64);
+
     let assign_node_f = |x: usize, y: usize, node_len: usize, node_cov: f64| {
         assert!(x >= y);
         let tot = x + y;
-        tot >= settings.assign_cnt
+        let enough_markers = match settings.assign_density {
+            Some(density) => markers_per_kb(tot, node_len) >= density - 1e-6,
+            None => tot >= settings.assign_cnt,
+        };
+        enough_markers
             && node_len <= tot * settings.assign_sparsity
             && ((x as f64) > settings.assign_ratio * (y as f64) - 1e-6
                 || (node_len > solid_len
                     && (x as f64) > settings.solid_ratio * (y as f64) - 1e-6
-                    && node_cov < solid_cov + 1e-6))
+                    && (node_cov.is_nan() || node_cov < solid_cov + 1e-6)))
     };
 
     let issue_node_f = |x: usize, y: usize, node_len: usize| {
@@ -261,6 +536,15 @@ pub fn assign_parental_groups(
             node_len,
             trio_info.counts_str()
         );
+        //density is reported in the info when it is used for assignment
+        let info = match settings.assign_density {
+            Some(_) => format!(
+                "{}:{:.2}/kb",
+                trio_info.counts_str(),
+                markers_per_kb(trio_info.mat + trio_info.pat, node_len)
+            ),
+            None => trio_info.counts_str(),
+        };
 
         if issue_node_f(
             max(trio_info.mat, trio_info.pat),
@@ -268,7 +552,7 @@ pub fn assign_parental_groups(
             node_len,
         ) {
             debug!("Assigning ISSUE label");
-            assignments.assign(node_id, TrioGroup::ISSUE, trio_info.counts_str());
+            assignments.assign(node_id, TrioGroup::ISSUE, info);
         } else if assign_node_f(
             max(trio_info.mat, trio_info.pat),
             min(trio_info.mat, trio_info.pat),
@@ -277,28 +561,21 @@ pub fn assign_parental_groups(
         ) {
             if trio_info.mat >= trio_info.pat {
                 debug!("Looks MATERNAL");
-                assignments.assign(node_id, TrioGroup::MATERNAL, trio_info.counts_str());
+                assignments.assign(node_id, TrioGroup::MATERNAL, info);
             } else {
                 debug!("Looks PATERNAL");
-                assignments.assign(node_id, TrioGroup::PATERNAL, trio_info.counts_str());
+                assignments.assign(node_id, TrioGroup::PATERNAL, info);
             }
         } else {
             debug!("Failed to assign label based on marker counts");
         }
+        if let Some(assign) = assignments.get_mut(node_id) {
+            assign.confidence = binomial_confidence(trio_info.mat, trio_info.pat) as f32;
+        }
     }
     assignments
 }
 
-fn parse_group(group_str: &str) -> TrioGroup {
-    match group_str {
-        "MATERNAL" => TrioGroup::MATERNAL,
-        "PATERNAL" => TrioGroup::PATERNAL,
-        "HOMOZYGOUS" => TrioGroup::HOMOZYGOUS,
-        "ISSUE" => TrioGroup::ISSUE,
-        _ => panic!("Invalid group string {group_str}"),
-    }
-}
-
 pub fn parse_node_assignments(
     g: &Graph,
     assignments_fn: &str,
@@ -308,15 +585,85 @@ pub fn parse_node_assignments(
         let split: Vec<&str> = line.trim().split('\t').collect();
         if &split[0].to_lowercase() != "node" && &split[0].to_lowercase() != "contig" {
             let node_name = split[0];
-            let group = parse_group(split[1]);
-            assignments.update_group(g.name2id(node_name), group);
+            let group: TrioGroup = split[1]
+                .parse()
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+            match g.try_name2id(node_name) {
+                Some(node_id) => assignments.update_group(node_id, group),
+                None => warn!("Ignoring assignment of node {node_name} absent from the graph"),
+            }
         }
     }
     Ok(assignments)
 }
 
+//info of the assignments forced by apply_overrides
+pub const OVERRIDE_INFO: &str = "Override";
+
+//tab-separated node name and group (MATERNAL, PATERNAL, HOMOZYGOUS or ISSUE, case-insensitive) per line
+//empty lines, lines starting with '#' and header line (starting with 'node' or 'contig') are skipped
+//nodes absent from the graph are ignored with a warning
+pub fn read_assignment_overrides(g: &Graph, path: &PathBuf) -> IOResult<Vec<(usize, TrioGroup)>> {
+    let mut overrides = Vec::new();
+    for (i, line) in crate::open_input(path)?.lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let split: Vec<&str> = line.split('\t').collect();
+        let first = split[0].to_lowercase();
+        if i == 0 && (first == "node" || first == "contig") {
+            continue;
+        }
+        let group: TrioGroup = split
+            .get(1)
+            .and_then(|s| s.trim().to_uppercase().parse().ok())
+            .ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!(
+                        "{}, line {}: invalid group override '{line}'",
+                        path.display(),
+                        i + 1
+                    ),
+                )
+            })?;
+        match g.try_name2id(split[0]) {
+            Some(node_id) => overrides.push((node_id, group)),
+            None => warn!(
+                "Ignoring override for node {} absent from the graph",
+                split[0]
+            ),
+        }
+    }
+    Ok(overrides)
+}
+
+//forces the groups of the nodes irrespective of their previous assignment (later overrides win)
+//returns the number of nodes, which group changed
+pub fn apply_overrides(
+    assignments: &mut AssignmentStorage,
+    overrides: &[(usize, TrioGroup)],
+) -> usize {
+    let mut changed = 0;
+    for &(node_id, group) in overrides {
+        if assignments.group(node_id) != Some(group) {
+            changed += 1;
+        }
+        assignments.assign(node_id, group, OVERRIDE_INFO);
+    }
+    changed
+}
+
 const MAX_COMPONENT_SIZE: usize = 100;
 
+//length-weighted mean coverage of nodes assigned to definite (maternal/paternal) groups
+//0 if none of such nodes has coverage
+pub fn definite_mean_cov(g: &Graph, assignments: &AssignmentStorage) -> f64 {
+    g.weighted_mean_cov(|node_id| assignments.is_definite(node_id))
+}
+
 pub struct HomozygousAssigner<'a> {
     g: &'a Graph,
     assignments: AssignmentStorage,
@@ -325,6 +672,8 @@ pub struct HomozygousAssigner<'a> {
     solid_len: usize,
     min_solid_cov: f64,
     max_assign_len: usize,
+    //nodes with higher coverage are marked irrespective of their neighborhood
+    min_doubled_cov: Option<f64>,
     complex_component_size: usize,
     considered: HashSet<usize>,
 }
@@ -347,18 +696,29 @@ impl<'a> HomozygousAssigner<'a> {
             solid_len,
             min_solid_cov,
             max_assign_len,
+            min_doubled_cov: None,
             complex_component_size: MAX_COMPONENT_SIZE,
             considered: HashSet::new(),
         }
     }
 
+    //also mark nodes with (roughly doubled) coverage above the value as homozygous,
+    //length restrictions on (re-)assignment still apply
+    pub fn set_min_doubled_cov(&mut self, min_doubled_cov: f64) {
+        self.min_doubled_cov = Some(min_doubled_cov);
+    }
+
     fn can_assign(&self, node_id: usize) -> bool {
         let n = self.g.node(node_id);
         if n.length > self.max_assign_len {
             return false;
         }
         match self.assignments.group(node_id) {
-            None => n.length < self.solid_len || n.coverage > self.min_solid_cov - 1e-5,
+            None => {
+                n.length < self.solid_len
+                    || !n.has_coverage()
+                    || n.coverage > self.min_solid_cov - 1e-5
+            }
             //TODO think if we should be able to also reclassify ISSUE nodes
             Some(TrioGroup::ISSUE) => false,
             //TODO can probably be removed / asserted if only single round allowed
@@ -366,7 +726,7 @@ impl<'a> HomozygousAssigner<'a> {
             _ => {
                 n.length < self.trusted_len
                     && self.min_suspect_cov.is_some()
-                    && n.coverage > self.min_suspect_cov.unwrap() - 1e-5
+                    && (!n.has_coverage() || n.coverage > self.min_suspect_cov.unwrap() - 1e-5)
             }
         }
     }
@@ -412,9 +772,47 @@ impl<'a> HomozygousAssigner<'a> {
             }
         }
         info!("Marked {}", marked);
+        if self.min_doubled_cov.is_some() {
+            let cov_marked = self.mark_high_coverage();
+            info!("Marked {} based on coverage", cov_marked);
+        }
         self.assignments
     }
 
+    fn mark_high_coverage(&mut self) -> usize {
+        let min_cov = self.min_doubled_cov.unwrap();
+        let mut marked = 0;
+        for node_id in 0..self.g.node_cnt() {
+            let n = self.g.node(node_id);
+            if self.considered.contains(&node_id)
+                || !n.has_coverage()
+                || n.coverage < min_cov - 1e-5
+                || n.length > self.max_assign_len
+            {
+                continue;
+            }
+            let reassignable = match self.assignments.group(node_id) {
+                None => true,
+                Some(g) if g.is_definite() => n.length < self.trusted_len,
+                _ => false,
+            };
+            if reassignable {
+                debug!(
+                    "Marking node {} with coverage {} as homozygous",
+                    n.name, n.coverage
+                );
+                self.considered.insert(node_id);
+                self.assignments.assign(
+                    node_id,
+                    TrioGroup::HOMOZYGOUS,
+                    "HomozygousAssigner:coverage",
+                );
+                marked += 1;
+            }
+        }
+        marked
+    }
+
     fn mark_vertex_and_chains(&mut self, v: Vertex) -> usize {
         debug!("Marking vertex {}", self.g.v_str(v));
         //hit node with existing assignment
@@ -491,8 +889,7 @@ impl<'a> HomozygousAssigner<'a> {
                 || self
                     .g
                     .incoming_edges(x)
-                    .iter()
-                    .all(|&l| visited_vertices.contains(&l.start))
+                    .all(|l| visited_vertices.contains(&l.start))
         })
     }
 }
@@ -513,6 +910,44 @@ impl Default for TangleAssignmentSettings {
     }
 }
 
+//Every unassigned node gets the (MATERNAL or PATERNAL) group held by the majority
+// of assigned nodes of length >= long_len within 'rounds' links from it (ties are left unassigned).
+//Votes are collected based on the original assignments, assigned nodes are never changed.
+pub fn propagate_assignments(
+    g: &Graph,
+    mut assignments: AssignmentStorage,
+    rounds: usize,
+    long_len: usize,
+) -> AssignmentStorage {
+    let mut propagated = Vec::new();
+    for node_id in 0..g.node_cnt() {
+        if assignments.contains(node_id) {
+            continue;
+        }
+        let (mut mat, mut pat) = (0, 0);
+        for n_id in g.neighborhood(node_id, rounds) {
+            if g.node_length(n_id) < long_len {
+                continue;
+            }
+            match assignments.group(n_id) {
+                Some(TrioGroup::MATERNAL) => mat += 1,
+                Some(TrioGroup::PATERNAL) => pat += 1,
+                _ => {}
+            }
+        }
+        if mat > pat {
+            propagated.push((node_id, TrioGroup::MATERNAL));
+        } else if pat > mat {
+            propagated.push((node_id, TrioGroup::PATERNAL));
+        }
+    }
+    debug!("Propagated groups to {} nodes", propagated.len());
+    for (node_id, group) in propagated {
+        assignments.assign(node_id, group, "propagated");
+    }
+    assignments
+}
+
 pub fn assign_short_node_tangles(
     g: &Graph,
     mut assignments: AssignmentStorage,
@@ -576,6 +1011,111 @@ pub fn assign_short_node_tangles(
     assignments
 }
 
+pub struct HetPairSettings {
+    /// Minimal length of the considered nodes
+    pub min_len: usize,
+    /// Maximal ratio of the longer node length to the shorter one
+    pub max_len_ratio: f64,
+    /// Maximal ratio of the higher node coverage to the lower one (ignored if coverage is missing)
+    pub max_cov_ratio: f64,
+}
+
+impl Default for HetPairSettings {
+    fn default() -> Self {
+        Self {
+            min_len: 200_000,
+            max_len_ratio: 1.25,
+            max_cov_ratio: 1.5,
+        }
+    }
+}
+
+fn neighbor_nodes(g: &Graph, node_id: usize) -> HashSet<usize> {
+    let v = Vertex::forward(node_id);
+    g.outgoing_edges(v)
+        .iter()
+        .map(|l| l.end.node_id)
+        .chain(g.incoming_edges(v).map(|l| l.start.node_id))
+        .filter(|&n| n != node_id)
+        .collect()
+}
+
+//whether v and w have common predecessor and whether they have common successor
+//(both -- regular bubble alleles)
+fn shared_sides(g: &Graph, v: Vertex, w: Vertex) -> (bool, bool) {
+    let preds = |x: Vertex| g.incoming_edges(x).map(|l| l.start).collect::<HashSet<_>>();
+    let succs = |x: Vertex| {
+        g.outgoing_edges(x)
+            .iter()
+            .map(|l| l.end)
+            .collect::<HashSet<_>>()
+    };
+    (
+        !preds(v).is_disjoint(&preds(w)),
+        !succs(v).is_disjoint(&succs(w)),
+    )
+}
+
+//Candidate 'missed' heterozygous pairs -- long nodes of opposite (maternal/paternal) groups
+//with similar lengths and (haploid) coverages, which share a common predecessor or successor,
+//but (in any orientation) are not alleles of a simple bubble (e.g. due to fragmented flank).
+//Returned pairs are ordered by node ids.
+pub fn missed_het_pairs(
+    g: &Graph,
+    assignments: &AssignmentStorage,
+    settings: &HetPairSettings,
+) -> Vec<(usize, usize)> {
+    let considered = |node_id: usize| {
+        g.node_length(node_id) >= settings.min_len && assignments.is_definite(node_id)
+    };
+    let similar = |x: f64, y: f64, max_ratio: f64| {
+        let (lo, hi) = if x < y { (x, y) } else { (y, x) };
+        hi <= lo * max_ratio + 1e-6
+    };
+
+    let mut pairs = Vec::new();
+    for node_id in (0..g.node_cnt()).filter(|&n| considered(n)) {
+        let group = assignments.group(node_id).unwrap();
+        let mut candidates = neighbor_nodes(g, node_id)
+            .into_iter()
+            .flat_map(|n| neighbor_nodes(g, n))
+            .filter(|&other| {
+                other > node_id
+                    && considered(other)
+                    && TrioGroup::incompatible(group, assignments.group(other).unwrap())
+            })
+            .collect::<Vec<_>>();
+        candidates.sort();
+        candidates.dedup();
+        for other in candidates {
+            let (n1, n2) = (g.node(node_id), g.node(other));
+            if !similar(n1.length as f64, n2.length as f64, settings.max_len_ratio) {
+                continue;
+            }
+            if n1.coverage > 0.
+                && n2.coverage > 0.
+                && !similar(n1.coverage, n2.coverage, settings.max_cov_ratio)
+            {
+                continue;
+            }
+            let v = Vertex::forward(node_id);
+            let sides =
+                [Vertex::forward(other), Vertex::reverse(other)].map(|w| shared_sides(g, v, w));
+            if !sides.iter().any(|&(pred, succ)| pred || succ)
+                || sides.iter().any(|&(pred, succ)| pred && succ)
+            {
+                continue;
+            }
+            debug!(
+                "Candidate missed heterozygous pair {} and {}",
+                n1.name, n2.name
+            );
+            pairs.push((node_id, other));
+        }
+    }
+    pairs
+}
+
 #[cfg(test)]
 mod tests {
     use crate::graph::*;
@@ -3,17 +3,23 @@ use crate::graph_algos::only_or_none;
 use crate::graph_algos::*;
 use crate::trio::*;
 use itertools::Itertools;
-use log::{debug, warn};
+use log::{debug, info, warn};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::borrow::Cow;
+use std::cell::{Cell, RefCell};
 use std::collections::{HashMap, HashSet};
 
 //FIXME move to dfs.rs
 //TODO optimize
+//buffer is cleared and used as the visited set of both searches (to reuse its allocation)
 pub fn reachable_between(
     g: &Graph,
     v: Vertex,
     w: Vertex,
     node_len_thr: usize,
     node_f: Option<&dyn Fn(Vertex) -> bool>,
+    buffer: &mut dfs::VertexSet,
 ) -> HashSet<Vertex> {
     //let (sinks, mut short_ahead) = dfs::sinks_ahead(g, v, node_len_thr, node_f);
     use dfs::*;
@@ -21,40 +27,93 @@ pub fn reachable_between(
 
     let mut fwd_dfs = DFS::new(g, TraversalDirection::FORWARD, node_f);
     fwd_dfs.set_max_node_len(node_len_thr);
+    buffer.clear();
+    fwd_dfs.set_blocked(std::mem::take(buffer));
     fwd_dfs.extend_blocked(std::iter::once(w));
     //inner_dfs(g, v, node_len_thr, &mut visited, &mut border);
     fwd_dfs.run_from(v);
-    if fwd_dfs.boundary().contains(&w) {
-        let fwd_visited = fwd_dfs.visited();
+    let fwd_visited = fwd_dfs.boundary().contains(&w).then(|| fwd_dfs.visited());
+    *buffer = fwd_dfs.take_blocked();
+    if let Some(fwd_visited) = fwd_visited {
         reachable_between.insert(v);
         reachable_between.insert(w);
 
         let mut bwd_dfs = DFS::new(g, TraversalDirection::REVERSE, node_f);
         bwd_dfs.set_max_node_len(node_len_thr);
+        buffer.clear();
+        bwd_dfs.set_blocked(std::mem::take(buffer));
         bwd_dfs.extend_blocked(std::iter::once(v));
         bwd_dfs.run_from(w);
         let bwd_visited = bwd_dfs.visited();
         assert!(bwd_dfs.boundary().contains(&v));
+        *buffer = bwd_dfs.take_blocked();
 
         reachable_between.extend(fwd_visited.intersection(&bwd_visited).copied());
     }
     reachable_between
 }
 
-//FIXME use iterators
-fn considered_extensions(
-    g: &Graph,
+//links are only copied if filtering is required
+fn considered_extensions<'a>(
+    g: &'a Graph,
     v: Vertex,
     consider_vertex_f: Option<&dyn Fn(Vertex) -> bool>,
-) -> Vec<Link> {
+) -> Cow<'a, [Link]> {
     match consider_vertex_f {
-        None => g.outgoing_edges(v),
-        Some(avail) => g
-            .outgoing_edges(v)
-            .iter()
-            .copied()
-            .filter(|l| avail(l.end))
-            .collect(),
+        None => Cow::Borrowed(g.outgoing_edges(v)),
+        Some(avail) => Cow::Owned(
+            g.outgoing_edges(v)
+                .iter()
+                .copied()
+                .filter(|l| avail(l.end))
+                .collect(),
+        ),
+    }
+}
+
+//reasons for rejecting jumps (extensions towards the next solid/assigned node)
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum JumpRejection {
+    //some of the long nodes ahead lack (non-ISSUE) assignment
+    NotAllDefinite,
+    //several long nodes ahead are compatible with the target group
+    MultipleCandidates,
+    //paths from both ends overlapped and couldn't be trimmed to put a gap
+    CouldntTrim,
+    //extension included node unavailable for the target group (e.g. already used)
+    NodeUnavailable,
+}
+
+impl JumpRejection {
+    pub const ALL: [JumpRejection; 4] = [
+        Self::NotAllDefinite,
+        Self::MultipleCandidates,
+        Self::CouldntTrim,
+        Self::NodeUnavailable,
+    ];
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Self::NotAllDefinite => "not all long nodes definite",
+            Self::MultipleCandidates => "multiple matching extensions",
+            Self::CouldntTrim => "couldn't trim",
+            Self::NodeUnavailable => "node unavailable",
+        }
+    }
+}
+
+//tallies of jump rejections (search methods take &self, hence interior mutability)
+#[derive(Default, Debug)]
+pub struct JumpRejectionCounts([Cell<usize>; 4]);
+
+impl JumpRejectionCounts {
+    fn add(&self, reason: JumpRejection) {
+        let cnt = &self.0[reason as usize];
+        cnt.set(cnt.get() + 1);
+    }
+
+    pub fn count(&self, reason: JumpRejection) -> usize {
+        self.0[reason as usize].get()
     }
 }
 
@@ -62,6 +121,9 @@ pub struct ExtensionHelper<'a> {
     g: &'a Graph,
     assignments: &'a AssignmentStorage,
     allow_unassigned: bool,
+    rejections: JumpRejectionCounts,
+    //visited set of the local searches, cleared and reused between the calls
+    dfs_buffer: RefCell<dfs::VertexSet>,
 }
 
 impl<'a> ExtensionHelper<'a> {
@@ -91,16 +153,17 @@ impl<'a> ExtensionHelper<'a> {
         // then check that all the vertices are assigned something
         // (other than ISSUE)
         if v_it.clone().all(|v| self.bearable_assignment(v.node_id)) {
+            let compatible = v_it
+                .filter(|v| self.compatible_assignment(v.node_id, group))
+                .collect_vec();
             //FIXME remove debug
-            debug!(
-                "{}",
-                v_it.clone()
-                    .filter(|v| self.compatible_assignment(v.node_id, group))
-                    .map(|x| self.g.v_str(x))
-                    .join(", ")
-            );
-            only_or_none(v_it.filter(|v| self.compatible_assignment(v.node_id, group)))
+            debug!("{}", compatible.iter().map(|&x| self.g.v_str(x)).join(", "));
+            if compatible.len() > 1 {
+                self.rejections.add(JumpRejection::MultipleCandidates);
+            }
+            only_or_none(compatible.into_iter())
         } else {
+            self.rejections.add(JumpRejection::NotAllDefinite);
             None
         }
     }
@@ -159,19 +222,28 @@ impl<'a> ExtensionHelper<'a> {
             Some(&check_unassigned),
         );
         dfs.set_max_node_len(solid_len);
+        let mut buffer = self.dfs_buffer.take();
+        buffer.clear();
+        dfs.set_blocked(buffer);
         dfs.run_from(v);
+        let boundary = dfs.boundary().clone();
+        *self.dfs_buffer.borrow_mut() = dfs.take_blocked();
 
         //could be if solid unassigned node is in the boundary
-        if dfs.boundary().iter().any(|&x| check_unassigned(x)) {
+        if boundary.iter().any(|&x| check_unassigned(x)) {
+            self.rejections.add(JumpRejection::NotAllDefinite);
             return None;
         }
 
-        only_or_none(
-            dfs.boundary()
-                .iter()
-                .filter(|x| self.compatible_assignment(x.node_id, group))
-                .copied(),
-        )
+        let compatible = boundary
+            .iter()
+            .filter(|x| self.compatible_assignment(x.node_id, group))
+            .copied()
+            .collect_vec();
+        if compatible.len() > 1 {
+            self.rejections.add(JumpRejection::MultipleCandidates);
+        }
+        only_or_none(compatible.into_iter())
     }
 
     fn find_compatible_sink(
@@ -237,6 +309,8 @@ pub struct HaploSearchSettings {
     //configuring node length thresholds
     pub solid_len: usize,
     pub trusted_len: usize,
+    //minimal length of nodes to launch the path search from (set independently of solid_len)
+    pub init_node_len: usize,
 
     //configuring behavior
     //Allow reuse of nodes within the same haplotype (otherwise prevented),
@@ -259,6 +333,27 @@ pub struct HaploSearchSettings {
     pub skippable_tangle_size: usize,
     pub min_gap_size: i64,
     pub default_gap_size: i64,
+    //estimate ambiguous gaps by the shortest walk between the path ends (otherwise default_gap_size)
+    pub estimate_ambig_gaps: bool,
+    //gaps longer than this are not searched for when estimating (default_gap_size is used instead)
+    pub ambig_gap_search_limit: usize,
+
+    //graphs with at least this many nodes use dense (node id indexed) storage for node usage
+    pub dense_used_node_cnt: usize,
+
+    //stop extending the path once its total length exceeds the value
+    pub max_path_length: usize,
+
+    //report progress every <value> processed seed nodes (0 disables reporting)
+    pub progress_interval: usize,
+
+    //break ties between equally good bubble-filling connectors at random
+    //(otherwise the smaller node id wins)
+    pub random_ties: bool,
+
+    //seed of the searcher random number generator,
+    //currently only consulted for tie-breaking if random_ties is set
+    pub seed: u64,
 }
 
 impl Default for HaploSearchSettings {
@@ -266,6 +361,7 @@ impl Default for HaploSearchSettings {
         Self {
             solid_len: 500_000,
             trusted_len: 200_000,
+            init_node_len: 500_000,
             allow_intersections: false,
             allow_unassigned: false,
             fill_bubbles: true,
@@ -278,6 +374,13 @@ impl Default for HaploSearchSettings {
             skippable_tangle_size: 1_000_000,
             min_gap_size: 1000,
             default_gap_size: 5000,
+            estimate_ambig_gaps: false,
+            ambig_gap_search_limit: 50_000,
+            dense_used_node_cnt: 1_000_000,
+            max_path_length: usize::MAX,
+            progress_interval: 1000,
+            random_ties: false,
+            seed: 0,
         }
     }
 }
@@ -298,13 +401,98 @@ pub struct HaploSearcher<'a> {
     assignments: &'a AssignmentStorage,
     extension_helper: ExtensionHelper<'a>,
     settings: HaploSearchSettings,
+    //usage is tracked per node id (irrespective of orientation):
+    //node covered by a path in any orientation is used in full,
+    //and a path never contains the same node twice (in either orientation)
     used: AssignmentStorage,
     small_tangle_index: HashMap<Vertex, scc::LocalizedTangle>,
     raw_cnts: Option<&'a HashMap<usize, TrioInfo>>,
+    //only paths seeded from this node are traced
+    trace_node: Option<usize>,
+    tracing: bool,
+    trace: RefCell<Vec<String>>,
+    //single source of randomness for the search (see HaploSearchSettings::seed)
+    rng: RefCell<ChaCha8Rng>,
 }
 
 pub type HaploPath = (Path, usize, TrioGroup);
 
+//see HaploSearcher::find_all_iter
+pub struct HaploPathIter<'s, 'a> {
+    searcher: &'s mut HaploSearcher<'a>,
+    nodes: std::vec::IntoIter<usize>,
+    //number of nodes taken from 'nodes' (considered as seeds)
+    processed: usize,
+    found: usize,
+    finished: bool,
+}
+
+impl Iterator for HaploPathIter<'_, '_> {
+    type Item = HaploPath;
+
+    fn next(&mut self) -> Option<HaploPath> {
+        for node_id in self.nodes.by_ref() {
+            self.processed += 1;
+            let haplo_path = self.searcher.try_seed(node_id);
+            if haplo_path.is_some() {
+                self.found += 1;
+            }
+            let interval = self.searcher.settings.progress_interval;
+            if interval > 0 && self.processed.is_multiple_of(interval) {
+                info!(
+                    "Processed {} seed nodes, found {} haplo-paths, {} nodes used",
+                    self.processed,
+                    self.found,
+                    self.searcher.used.assigned().count()
+                );
+            }
+            if haplo_path.is_some() {
+                return haplo_path;
+            }
+        }
+        if !self.finished {
+            self.finished = true;
+            self.searcher.log_jump_rejections();
+        }
+        None
+    }
+}
+
+//indices of the paths, node set of which is contained in the node set of another path of the same group
+//(out of paths with identical node sets only the first is kept)
+//paths of different groups legitimately share (homozygous) nodes and are never considered redundant
+pub fn redundant_paths(paths: &[HaploPath]) -> Vec<usize> {
+    let node_sets = paths
+        .iter()
+        .map(|(p, _, _)| {
+            p.vertices()
+                .iter()
+                .map(|v| v.node_id)
+                .collect::<HashSet<_>>()
+        })
+        .collect_vec();
+    let mut node_2_paths: HashMap<usize, Vec<usize>> = HashMap::new();
+    for (i, nodes) in node_sets.iter().enumerate() {
+        for &node_id in nodes {
+            node_2_paths.entry(node_id).or_default().push(i);
+        }
+    }
+
+    let mut redundant = Vec::new();
+    for (i, (p, _, group)) in paths.iter().enumerate() {
+        let contained = node_2_paths[&p.start().node_id].iter().any(|&j| {
+            j != i
+                && paths[j].2 == *group
+                && node_sets[i].is_subset(&node_sets[j])
+                && (node_sets[i].len() < node_sets[j].len() || j < i)
+        });
+        if contained {
+            redundant.push(i);
+        }
+    }
+    redundant
+}
+
 impl<'a> HaploSearcher<'a> {
     pub fn new(
         g: &'a Graph,
@@ -316,11 +504,17 @@ impl<'a> HaploSearcher<'a> {
             g,
             assignments,
             settings,
-            used: AssignmentStorage::new(),
+            used: if g.node_cnt() >= settings.dense_used_node_cnt {
+                AssignmentStorage::new_dense(g.node_cnt())
+            } else {
+                AssignmentStorage::new()
+            },
             extension_helper: ExtensionHelper {
                 g,
                 assignments,
                 allow_unassigned: settings.allow_unassigned,
+                rejections: JumpRejectionCounts::default(),
+                dfs_buffer: RefCell::new(dfs::VertexSet::for_graph(g)),
             },
             small_tangle_index: HashMap::from_iter(
                 scc::find_small_localized(
@@ -332,40 +526,137 @@ impl<'a> HaploSearcher<'a> {
                 .map(|s| (s.entrance.start, s)),
             ),
             raw_cnts,
+            trace_node: None,
+            tracing: false,
+            trace: RefCell::new(Vec::new()),
+            rng: RefCell::new(ChaCha8Rng::seed_from_u64(settings.seed)),
         }
     }
 
+    //record the decisions made while growing the path seeded from the node
+    pub fn set_trace_node(&mut self, node_id: usize) {
+        self.trace_node = Some(node_id);
+    }
+
+    //tab-separated trace lines (vertex, considered candidates, decision, chosen extension)
+    pub fn take_trace(&mut self) -> Vec<String> {
+        self.trace.take()
+    }
+
+    //none_decision describes the case of no extension
+    fn trace_step(
+        &self,
+        v: Vertex,
+        group: TrioGroup,
+        step: Option<&(&str, Path)>,
+        none_decision: &str,
+    ) {
+        if !self.tracing {
+            return;
+        }
+        let candidates = self
+            .g
+            .outgoing_edges(v)
+            .iter()
+            .map(|l| {
+                format!(
+                    "{}:{}:{:.1}",
+                    self.g.v_str(l.end),
+                    self.assignments
+                        .group(l.end.node_id)
+                        .map_or(String::from("NA"), |x| format!("{x:?}")),
+                    self.g.node(l.end.node_id).coverage
+                )
+            })
+            .join(",");
+        let (decision, ext) = match step {
+            Some((decision, ext)) => (*decision, ext.print(self.g)),
+            None => (none_decision, String::from("-")),
+        };
+        self.trace.borrow_mut().push(format!(
+            "{}\t{:?}\t{}\t{}\t{}",
+            self.g.v_str(v),
+            group,
+            if candidates.is_empty() {
+                "-"
+            } else {
+                &candidates
+            },
+            decision,
+            ext
+        ));
+    }
+
     pub fn used(&self) -> &AssignmentStorage {
         &self.used
     }
 
+    pub fn used_by_name(&self) -> HashMap<&'a str, TrioGroup> {
+        self.used.groups_by_name(self.g)
+    }
+
     pub fn take_used(self) -> AssignmentStorage {
         self.used
     }
 
-    //TODO maybe use single length threshold?
     pub fn find_all(&mut self) -> Vec<HaploPath> {
-        let mut answer = Vec::new();
+        self.find_all_iter().collect()
+    }
+
+    //lazy version of find_all, paths are searched for as they are requested
+    //(used nodes are updated after every path)
+    pub fn find_all_iter(&mut self) -> HaploPathIter<'_, 'a> {
+        //longer nodes first, ties broken by node id
         let mut nodes = self.g.all_nodes().enumerate().collect_vec();
-        nodes.sort_by_key(|(_, n)| n.length);
+        nodes.sort_by_key(|&(node_id, n)| (std::cmp::Reverse(n.length), node_id));
+        HaploPathIter {
+            nodes: nodes
+                .into_iter()
+                .map(|(node_id, _)| node_id)
+                .collect_vec()
+                .into_iter(),
+            searcher: self,
+            processed: 0,
+            found: 0,
+            finished: false,
+        }
+    }
 
-        for (node_id, _node) in nodes.into_iter().rev() {
-            //launch from long, definitely assigned nodes
-            if !self.used.contains(node_id)
-                && self.long_node(node_id)
-                && self.assignments.is_definite(node_id)
-            {
-                let group = self.assignments.get(node_id).unwrap().group;
-                let path = self.haplo_path(Vertex::forward(node_id), group);
-                self.used
-                    .update_all(path.vertices().iter().map(|v| v.node_id), group);
-                self.used.get_mut(path.start().node_id).unwrap().info =
-                    String::from("path_boundary");
-                self.used.get_mut(path.end().node_id).unwrap().info = String::from("path_boundary");
-                answer.push((path, node_id, group));
-            }
+    //launch from long, definitely assigned nodes
+    fn try_seed(&mut self, node_id: usize) -> Option<HaploPath> {
+        if self.used.contains(node_id)
+            || self.g.node(node_id).length < self.settings.init_node_len
+            || !self.assignments.is_definite(node_id)
+        {
+            return None;
         }
-        answer
+        let group = self.assignments.get(node_id).unwrap().group;
+        self.tracing = self.trace_node == Some(node_id);
+        let path = self.haplo_path(Vertex::forward(node_id), group);
+        self.tracing = false;
+        self.used
+            .update_all(path.vertices().iter().map(|v| v.node_id), group);
+        self.used.set_info(path.start().node_id, "path_boundary");
+        self.used.set_info(path.end().node_id, "path_boundary");
+        Some((path, node_id, group))
+    }
+
+    fn log_jump_rejections(&self) {
+        info!(
+            "Jump rejections: {}",
+            JumpRejection::ALL
+                .iter()
+                .map(|r| format!(
+                    "{} -- {}",
+                    r.description(),
+                    self.jump_rejections().count(*r)
+                ))
+                .join(", ")
+        );
+    }
+
+    pub fn jump_rejections(&self) -> &JumpRejectionCounts {
+        &self.extension_helper.rejections
     }
 
     fn haplo_path(&self, v: Vertex, group: TrioGroup) -> Path {
@@ -418,17 +709,21 @@ impl<'a> HaploSearcher<'a> {
         group: TrioGroup,
         allow_gaps: bool,
     ) -> Option<Path> {
+        let mut buffer = self.extension_helper.dfs_buffer.borrow_mut();
         let mut reachable_vertices = reachable_between(
             self.g,
             v,
             w,
             self.settings.solid_len,
             Some(&|x: Vertex| self.unassigned_or_compatible(x.node_id, group)),
+            &mut buffer,
         );
 
         if reachable_vertices.is_empty() {
-            reachable_vertices = reachable_between(self.g, v, w, self.settings.solid_len, None);
+            reachable_vertices =
+                reachable_between(self.g, v, w, self.settings.solid_len, None, &mut buffer);
         }
+        drop(buffer);
 
         let mut p1 = Path::new(v);
         debug!(
@@ -487,20 +782,44 @@ impl<'a> HaploSearcher<'a> {
             debug!("Paths forward and backward overlapped");
             debug!("Trimming path forward to {}", self.g.v_str(trim_to));
             assert!(p1.trim_to(&trim_to));
+            if p1.len() == 1 {
+                debug!("Backward path reached the start, can't put the gap");
+                self.extension_helper
+                    .rejections
+                    .add(JumpRejection::CouldntTrim);
+                return None;
+            }
             p1.trim(1);
             debug_assert!(!p1.vertices().iter().any(|x| p2.in_path(x.node_id)));
         }
 
+        let gap_size = if self.settings.estimate_ambig_gaps {
+            //estimating gap size by the shortest walk between the path ends
+            shortest_distance(
+                self.g,
+                p1.end(),
+                p2.start(),
+                self.g.vertex_length(p2.start()) + self.settings.ambig_gap_search_limit,
+            )
+            .map_or(self.settings.default_gap_size, |d| {
+                std::cmp::max(
+                    d as i64 - self.g.vertex_length(p2.start()) as i64,
+                    self.settings.min_gap_size,
+                )
+            })
+        } else {
+            self.settings.default_gap_size
+        };
         debug!(
-            "Putting ambiguous gap between {} and {}",
+            "Putting ambiguous gap of size {} between {} and {}",
+            gap_size,
             self.g.v_str(p1.end()),
             self.g.v_str(p2.start())
         );
         p1.append_general(GeneralizedLink::GAP(GapInfo {
             start: p1.end(),
             end: p2.start(),
-            //FIXME use something reasonable
-            gap_size: self.settings.default_gap_size,
+            gap_size,
             info: String::from("ambig_path"),
         }));
         assert!(p1.can_merge_in(&p2));
@@ -509,11 +828,24 @@ impl<'a> HaploSearcher<'a> {
     }
 
     fn grow_forward(&self, path: &mut Path, group: TrioGroup) {
+        //total path length is tracked across the extensions
+        let mut length = path.total_length(self.g);
         loop {
+            if self.length_exceeded(length) {
+                warn!(
+                    "Path length exceeded limit of {}, stopping extension at {}",
+                    self.settings.max_path_length,
+                    self.g.v_str(path.end())
+                );
+                break;
+            }
             if self.long_node(path.end().node_id) {
-                self.solid_aimed_grow(path, group);
+                self.solid_aimed_grow(path, &mut length, group);
+                if self.length_exceeded(length) {
+                    continue;
+                }
             }
-            if !self.unguided_grow_to_solid(path, group) {
+            if !self.unguided_grow_to_solid(path, &mut length, group) {
                 debug!("Stopping extension");
                 break;
             }
@@ -522,21 +854,33 @@ impl<'a> HaploSearcher<'a> {
 
     //Tries to maximally grow the path forward from a solid node, iteratively trying to guess next solid target
     //returns true if anything was done and false if couldn't extend
-    fn solid_aimed_grow(&self, path: &mut Path, group: TrioGroup) {
+    fn solid_aimed_grow(&self, path: &mut Path, length: &mut usize, group: TrioGroup) {
         debug!(
             "Initiating 'guided' extension from {}",
             self.g.v_str(path.end())
         );
-        while let Some(ext) = self.solid_aimed_step_ext(path.end(), group) {
+        while !self.length_exceeded(*length) {
+            let step = self
+                .solid_aimed_step_ext(path.end(), group)
+                .map(|ext| ("solid_aimed", ext));
+            self.trace_step(path.end(), group, step.as_ref(), "no_solid_target");
+            let Some((_, ext)) = step else {
+                break;
+            };
             debug!("Found extension {}", ext.print(self.g));
             if self.check_available_append(path, &ext, group) {
                 debug!("Merging in");
+                *length += self.extension_length(&ext);
                 path.merge_in(ext);
                 debug!(
                     "Will continue 'guided' extension from {}",
                     self.g.v_str(path.end())
                 );
             } else {
+                self.extension_helper
+                    .rejections
+                    .add(JumpRejection::NodeUnavailable);
+                self.trace_step(path.end(), group, Some(&("unavailable", ext)), "");
                 warn!(
                     "Couldn't merge in guided extension from {}",
                     self.g.v_str(path.end())
@@ -547,8 +891,14 @@ impl<'a> HaploSearcher<'a> {
     }
 
     //Tries to maximally grow the path forward until the next solid node (without gessing next solid in advance)
-    //returns true if reached solid node and false if ended in issue or couldn't extend anymore
-    fn unguided_grow_to_solid(&self, path: &mut Path, group: TrioGroup) -> bool {
+    //returns true if reached solid node (or the path length limit)
+    //and false if ended in issue or couldn't extend anymore
+    fn unguided_grow_to_solid(
+        &self,
+        path: &mut Path,
+        length: &mut usize,
+        group: TrioGroup,
+    ) -> bool {
         debug!(
             "Initiating unguided extension from {}",
             self.g.v_str(path.end())
@@ -556,13 +906,21 @@ impl<'a> HaploSearcher<'a> {
         //try to make one step ahead, s.a. gap/tangle/bubble and regular extension
         while let Some(ext) = self.unguided_next_or_gap(path.end(), group) {
             if self.check_available_append(path, &ext, group) {
+                *length += self.extension_length(&ext);
                 path.merge_in(ext);
                 let v = path.end();
                 if self.long_node(v.node_id) {
                     debug!("Reached solid node {}", self.g.v_str(v));
                     return true;
                 }
+                if self.length_exceeded(*length) {
+                    return true;
+                }
             } else {
+                self.extension_helper
+                    .rejections
+                    .add(JumpRejection::NodeUnavailable);
+                self.trace_step(path.end(), group, Some(&("unavailable", ext)), "");
                 debug!("Had issue growing beyond {}", self.g.v_str(path.end()));
                 return false;
             }
@@ -571,11 +929,20 @@ impl<'a> HaploSearcher<'a> {
     }
 
     fn unguided_next_or_gap(&self, v: Vertex, group: TrioGroup) -> Option<Path> {
-        self.local_next(v, group, None)
-            .or_else(|| self.assigned_aimed_ext(v, group))
-            .or_else(|| self.gap_patch(v, group, self.settings.trusted_len))
+        let step = self
+            .labeled_local_next(v, group, None)
+            .or_else(|| {
+                self.assigned_aimed_ext(v, group)
+                    .map(|p| ("assigned_aimed", p))
+            })
+            .or_else(|| {
+                self.gap_patch(v, group, self.settings.trusted_len)
+                    .map(|p| ("gap_jump", p))
+            })
             //FIXME this one might lead to interesting non-trivial issues
-            .or_else(|| self.gap_patch(v, group, 0))
+            .or_else(|| self.gap_patch(v, group, 0).map(|p| ("short_gap_jump", p)));
+        self.trace_step(v, group, step.as_ref(), "stop");
+        step.map(|(_, p)| p)
     }
 
     fn find_unbroken_alt_candidate(
@@ -587,7 +954,7 @@ impl<'a> HaploSearcher<'a> {
         assert!(self.g.vertex_length(v) >= short_node_threshold);
 
         //dead-end case
-        if self.g.outgoing_edge_cnt(v) == 0 {
+        if self.g.out_degree(v) == 0 {
             let component =
                 dfs::ShortNodeComponent::back_from_long(self.g, v, short_node_threshold);
 
@@ -602,7 +969,7 @@ impl<'a> HaploSearcher<'a> {
                     self.g.vertex_length(alt) as i64 - self.g.vertex_length(v) as i64,
                 )
             })
-        } else if self.g.outgoing_edge_cnt(v) == 1 {
+        } else if self.g.out_degree(v) == 1 {
             //haplotype merge-in case
             let alt = self.g.outgoing_edges(v)[0].end;
             Some((alt, self.g.vertex_length(alt) as i64))
@@ -656,7 +1023,7 @@ impl<'a> HaploSearcher<'a> {
                 .sources
                 .iter()
                 .filter(|s| self.assignments.group(s.node_id).unwrap() == group)
-                .filter(|&s| self.g.incoming_edge_cnt(*s) == 0),
+                .filter(|&s| self.g.in_degree(*s) == 0),
         ) {
             debug!("Dead-end case");
             //dead-end case
@@ -740,6 +1107,26 @@ impl<'a> HaploSearcher<'a> {
         path
     }
 
+    //best (maximal) of the non-empty set of candidates,
+    //ties are broken in favor of the smaller node id or at random if random_ties is set
+    fn choose_best(
+        &self,
+        candidates: Vec<Vertex>,
+        cmp: impl Fn(&Vertex, &Vertex) -> std::cmp::Ordering,
+    ) -> Vertex {
+        let best = *candidates.iter().max_by(|a, b| cmp(a, b)).unwrap();
+        let tied = candidates
+            .into_iter()
+            .filter(|c| cmp(c, &best).is_eq())
+            .sorted_by_key(|c| c.node_id)
+            .collect_vec();
+        if self.settings.random_ties && tied.len() > 1 {
+            tied[self.rng.borrow_mut().gen_range(0..tied.len())]
+        } else {
+            tied[0]
+        }
+    }
+
     fn raw_marker_excess(&self, v: &Vertex, group: TrioGroup) -> Option<i64> {
         let raw_cnts = self.raw_cnts?;
         let info = raw_cnts.get(&v.node_id)?;
@@ -783,11 +1170,21 @@ impl<'a> HaploSearcher<'a> {
             && self.bubble_filling_cov_check(v)
             && self.bubble_filling_cov_check(w)
         {
-            let cov = |x: &Vertex| self.g.node(x.node_id).coverage;
+            //nodes without coverage go last
+            //missing coverage is treated as the lowest one,
+            //ties are broken by choose_best
+            let cov = |x: &Vertex| {
+                let n = self.g.node(x.node_id);
+                if n.has_coverage() {
+                    n.coverage
+                } else {
+                    f64::NEG_INFINITY
+                }
+            };
 
             //Filling the bubble
             let mut direct_connectors = considered_extensions(self.g, v, consider_vertex_f)
-                .into_iter()
+                .iter()
                 .filter_map(|l1| self.g.connector(l1.end, w))
                 .map(|l2| l2.start)
                 .filter(|tc_v| self.unassigned_or_compatible(tc_v.node_id, group))
@@ -800,6 +1197,9 @@ impl<'a> HaploSearcher<'a> {
                         .filter(|&c| !self.used.contains(c.node_id))
                         .filter(|&c| {
                             self.settings.good_side_cov_gap == 0.
+                                || [c, &v, &w]
+                                    .iter()
+                                    .any(|x| !self.g.node(x.node_id).has_coverage())
                                 || self.settings.good_side_cov_gap * cov(c)
                                     > (cov(&v) + cov(&w)) / 2. - 1e-5
                         })
@@ -812,22 +1212,14 @@ impl<'a> HaploSearcher<'a> {
 
                     //direct_connectors.sort_by_key(|c| cov(c));
                     //direct_connectors.into_iter().max_by_key(|&c| (cov(c), self.raw_marker_excess(c, group)));
-                    direct_connectors
-                        .into_iter()
-                        .max_by(|a, b| {
-                            (self.raw_marker_excess(a, group).unwrap_or_default(), cov(a))
-                                .partial_cmp(&(
-                                    self.raw_marker_excess(b, group).unwrap_or_default(),
-                                    cov(b),
-                                ))
-                                .unwrap()
-                        })
-                        .unwrap()
+                    self.choose_best(direct_connectors, |a, b| {
+                        self.raw_marker_excess(a, group)
+                            .unwrap_or_default()
+                            .cmp(&self.raw_marker_excess(b, group).unwrap_or_default())
+                            .then(cov(a).total_cmp(&cov(b)))
+                    })
                 } else {
-                    direct_connectors
-                        .into_iter()
-                        .max_by(|a, b| cov(a).partial_cmp(&cov(b)).unwrap())
-                        .unwrap()
+                    self.choose_best(direct_connectors, |a, b| cov(a).total_cmp(&cov(b)))
                 };
 
                 let p = self.connecting_path(v, c, w);
@@ -928,6 +1320,17 @@ impl<'a> HaploSearcher<'a> {
         true
     }
 
+    //checks if the path length has reached the limit
+    fn length_exceeded(&self, path_length: usize) -> bool {
+        path_length > self.settings.max_path_length
+    }
+
+    //increase of the path length after merging in the extension (starting at its end)
+    fn extension_length(&self, ext: &Path) -> usize {
+        ext.total_length(self.g)
+            .saturating_sub(self.g.vertex_length(ext.start()))
+    }
+
     //returns false if hit some issue (self-intersection, node reuse, etc)
     //TODO optimize
     fn check_available_append(&self, path: &Path, ext: &Path, group: TrioGroup) -> bool {
@@ -941,7 +1344,8 @@ impl<'a> HaploSearcher<'a> {
     fn bubble_filling_cov_check(&self, v: Vertex) -> bool {
         assert!(self.settings.fill_bubbles && self.settings.max_unique_cov >= 0.);
         (self.settings.max_unique_cov > 0.
-            && (self.g.node(v.node_id).coverage - 1e-5) < self.settings.max_unique_cov)
+            && (!self.g.node(v.node_id).has_coverage()
+                || (self.g.node(v.node_id).coverage - 1e-5) < self.settings.max_unique_cov))
             || self.long_node(v.node_id)
             || self.assignments.group(v.node_id) == Some(TrioGroup::HOMOZYGOUS)
     }
@@ -952,13 +1356,28 @@ impl<'a> HaploSearcher<'a> {
         group: TrioGroup,
         constraint_vertex_f: Option<&dyn Fn(Vertex) -> bool>,
     ) -> Option<Path> {
+        self.labeled_local_next(v, group, constraint_vertex_f)
+            .map(|(_, p)| p)
+    }
+
+    //extension together with the kind of the step
+    fn labeled_local_next(
+        &self,
+        v: Vertex,
+        group: TrioGroup,
+        constraint_vertex_f: Option<&dyn Fn(Vertex) -> bool>,
+    ) -> Option<(&'static str, Path)> {
         self.find_small_tangle_jump_ahead(v, group)
+            .map(|p| ("tangle_jump", p))
             .or_else(|| {
                 self.extension_helper
                     .group_extension(v, group, constraint_vertex_f)
-                    .map(Path::from_link)
+                    .map(|l| ("group_extension", Path::from_link(l)))
+            })
+            .or_else(|| {
+                self.find_bubble_fill_ahead(v, group, constraint_vertex_f)
+                    .map(|p| ("bubble_fill", p))
             })
-            .or_else(|| self.find_bubble_fill_ahead(v, group, constraint_vertex_f))
     }
 
     fn grow_local_maybe_gap(
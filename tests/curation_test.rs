@@ -0,0 +1,56 @@
+use rukki::curation::*;
+use rukki::trio::TrioGroup;
+use rukki::*;
+
+#[test]
+fn join_and_break() {
+    let s = "
+S a * LN:i:100
+S b * LN:i:100
+S c * LN:i:100
+S d * LN:i:100
+L a + b + 10M
+";
+    let g = Graph::read(&s.replace(' ', "\t"));
+    let v = |name| Vertex::forward(g.name2id(name));
+    let records = vec![
+        PathRecord::new("p1", Path::new(v("a")), Some(TrioGroup::MATERNAL)),
+        PathRecord::new("p2", Path::new(v("b")), None),
+        PathRecord::new("p3", Path::new(v("c")), Some(TrioGroup::MATERNAL)),
+        PathRecord::new("p4", Path::new(v("d")), Some(TrioGroup::PATERNAL)),
+    ];
+    let edits = parse_path_edits(
+        "# comment
+join p1 p2
+join p1 p3 5000
+break p1 b
+",
+    )
+    .unwrap();
+    assert_eq!(edits.len(), 3);
+    let edited = apply_path_edits(&g, records.clone(), &edits).unwrap();
+    let summary = edited
+        .iter()
+        .map(|r| (r.name.as_str(), r.path.print(&g), r.group))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        summary,
+        vec![
+            ("p1_1", String::from("a+,b+"), Some(TrioGroup::MATERNAL)),
+            ("p1_2", String::from("c+"), Some(TrioGroup::MATERNAL)),
+            ("p4", String::from("d+"), Some(TrioGroup::PATERNAL)),
+        ]
+    );
+    assert_eq!(edited[0].path.links()[0].overlap(), 10);
+
+    //no link
+    let edits = parse_path_edits("join p1 p3").unwrap();
+    assert!(apply_path_edits(&g, records.clone(), &edits).is_err());
+    //incompatible groups
+    let edits = parse_path_edits("join p3 p4 100").unwrap();
+    assert!(apply_path_edits(&g, records.clone(), &edits).is_err());
+    //break at the last node
+    let edits = parse_path_edits("break p1 a").unwrap();
+    assert!(apply_path_edits(&g, records, &edits).is_err());
+    assert!(parse_path_edits("merge p1 p2").is_err());
+}
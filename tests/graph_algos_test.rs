@@ -0,0 +1,373 @@
+use rukki::graph_algos;
+use rukki::*;
+
+fn branchy_graph() -> Graph {
+    let s = "
+S a * LN:i:100
+S b * LN:i:300
+S c * LN:i:200
+S d * LN:i:100
+S e * LN:i:100
+S f * LN:i:100
+L a + b + 10M
+L a + c + 10M
+L b + c + 10M
+L b + d + 10M
+L c + d + 10M
+L d + e + 10M
+";
+    Graph::read(&s.replace(' ', "\t"))
+}
+
+#[test]
+fn longest_simple_path() {
+    let g = branchy_graph();
+    let v = |name| Vertex::forward(g.name2id(name));
+    let p = graph_algos::longest_simple_path(&g, v("a"), v("e"), usize::MAX, usize::MAX).unwrap();
+    assert_eq!(p.print(&g), "a+,b+,c+,d+,e+");
+    assert_eq!(p.total_length(&g), 760);
+
+    //node cap forces to skip one of the middle nodes, longer one is kept
+    let p = graph_algos::longest_simple_path(&g, v("a"), v("e"), 4, usize::MAX).unwrap();
+    assert_eq!(p.print(&g), "a+,b+,d+,e+");
+
+    assert!(graph_algos::longest_simple_path(&g, v("a"), v("e"), 3, usize::MAX).is_none());
+    assert!(graph_algos::longest_simple_path(&g, v("a"), v("f"), usize::MAX, usize::MAX).is_none());
+    assert!(graph_algos::longest_simple_path(&g, v("e"), v("a"), usize::MAX, usize::MAX).is_none());
+    assert_eq!(
+        graph_algos::longest_simple_path(&g, v("a"), v("a"), 1, 0)
+            .unwrap()
+            .len(),
+        1
+    );
+
+    //exhaustive search takes 9 path extensions, giving up if the budget is smaller
+    assert_eq!(
+        graph_algos::longest_simple_path(&g, v("a"), v("e"), usize::MAX, 9)
+            .unwrap()
+            .print(&g),
+        "a+,b+,c+,d+,e+"
+    );
+    assert!(graph_algos::longest_simple_path(&g, v("a"), v("e"), usize::MAX, 8).is_none());
+}
+
+#[test]
+fn weakly_connected() {
+    let g = branchy_graph();
+    assert_eq!(
+        graph_algos::weakly_connected(&g),
+        vec![vec![0, 1, 2, 3, 4], vec![5]]
+    );
+
+    //orientation of the links doesn't matter
+    let s = "
+S a * LN:i:100
+S b * LN:i:100
+S c * LN:i:100
+S d * LN:i:100
+S e * LN:i:100
+L d - a + 10M
+L c + a - 10M
+L b + e + 10M
+";
+    let g = Graph::read(&s.replace(' ', "\t"));
+    assert_eq!(
+        graph_algos::weakly_connected(&g),
+        vec![vec![0, 2, 3], vec![1, 4]]
+    );
+    assert!(graph_algos::weakly_connected(&Graph::new()).is_empty());
+}
+
+#[test]
+fn graph_stats() {
+    let s = "
+S a * LN:i:100 RC:i:1000
+S b * LN:i:300 RC:i:6000
+S c * LN:i:200
+S d * LN:i:400 RC:i:12000
+S e * LN:i:500 RC:i:15000
+L a + b + 10M
+L b + a + 10M
+L c + d + 10M
+";
+    let g = Graph::read(&s.replace(' ', "\t"));
+    let stats = graph_algos::GraphStats::compute(&g);
+    assert_eq!(stats.node_cnt, 5);
+    assert_eq!(stats.link_cnt, 3);
+    assert_eq!(stats.total_length, 1500);
+    assert_eq!(stats.n50, 400);
+    assert!((stats.coverage_mean.unwrap() - 34000. / 1300.).abs() < 1e-9);
+    assert!((stats.coverage_median.unwrap() - 30.).abs() < 1e-9);
+    assert_eq!(stats.wcc_cnt, 3);
+    assert_eq!(stats.scc_cnt, 1);
+    assert_eq!(stats.scc_node_cnt, 2);
+    assert!(stats.to_string().contains("N50\t400"));
+
+    let stats = graph_algos::GraphStats::compute(&Graph::read("S\ta\t*\tLN:i:10"));
+    assert_eq!(stats.coverage_mean, None);
+    assert_eq!(stats.n50, 10);
+    assert!(stats.to_string().contains("Mean coverage\tNA"));
+}
+
+#[test]
+fn shortest_distance() {
+    let g = branchy_graph();
+    let v = |name| Vertex::forward(g.name2id(name));
+    assert_eq!(
+        graph_algos::shortest_distance(&g, v("a"), v("a"), 0),
+        Some(0)
+    );
+    assert_eq!(
+        graph_algos::shortest_distance(&g, v("a"), v("c"), usize::MAX),
+        Some(190)
+    );
+    //a+,c+,d+,e+ is shorter than a+,b+,d+,e+
+    assert_eq!(
+        graph_algos::shortest_distance(&g, v("a"), v("e"), usize::MAX),
+        Some(370)
+    );
+    assert_eq!(
+        graph_algos::shortest_distance(&g, v("a"), v("e"), 370),
+        Some(370)
+    );
+    assert_eq!(
+        graph_algos::shortest_distance(&g, v("a"), v("e"), 369),
+        None
+    );
+    assert_eq!(
+        graph_algos::shortest_distance(&g, v("e"), v("a"), usize::MAX),
+        None
+    );
+    assert_eq!(
+        graph_algos::shortest_distance(&g, v("a"), v("f"), usize::MAX),
+        None
+    );
+    assert_eq!(
+        graph_algos::shortest_distance(&g, v("e").rc(), v("a").rc(), usize::MAX),
+        Some(370)
+    );
+}
+
+#[test]
+fn simple_paths() {
+    let g = branchy_graph();
+    let v = |name| Vertex::forward(g.name2id(name));
+    let print = |paths: Vec<Path>| paths.iter().map(|p| p.print(&g)).collect::<Vec<_>>();
+    assert_eq!(
+        print(graph_algos::simple_paths(
+            &g,
+            v("a"),
+            v("e"),
+            usize::MAX,
+            usize::MAX
+        )),
+        vec!["a+,c+,d+,e+", "a+,b+,d+,e+", "a+,b+,c+,d+,e+"]
+    );
+    //length budget
+    assert_eq!(
+        print(graph_algos::simple_paths(
+            &g,
+            v("a"),
+            v("e"),
+            570,
+            usize::MAX
+        )),
+        vec!["a+,c+,d+,e+", "a+,b+,d+,e+"]
+    );
+    //search stops once the count limit is reached (paths found first in depth-first order)
+    assert_eq!(
+        print(graph_algos::simple_paths(&g, v("a"), v("e"), usize::MAX, 1)),
+        vec!["a+,b+,c+,d+,e+"]
+    );
+    assert_eq!(
+        print(graph_algos::simple_paths(&g, v("a"), v("e"), usize::MAX, 2)),
+        vec!["a+,b+,d+,e+", "a+,b+,c+,d+,e+"]
+    );
+    assert!(graph_algos::simple_paths(&g, v("a"), v("e"), usize::MAX, 0).is_empty());
+    assert!(graph_algos::simple_paths(&g, v("a"), v("e"), 469, usize::MAX).is_empty());
+    assert!(graph_algos::simple_paths(&g, v("e"), v("a"), usize::MAX, usize::MAX).is_empty());
+    assert_eq!(
+        print(graph_algos::simple_paths(&g, v("a"), v("a"), 100, 10)),
+        vec!["a+"]
+    );
+}
+
+#[test]
+fn longest_dag_path() {
+    let g = branchy_graph();
+    let v = |name| Vertex::forward(g.name2id(name));
+    let p = graph_algos::longest_dag_path(&g, v("a")).unwrap();
+    assert_eq!(p.print(&g), "a+,b+,c+,d+,e+");
+    assert_eq!(p.total_length(&g), 760);
+    assert_eq!(
+        graph_algos::longest_dag_path(&g, v("c")).unwrap().print(&g),
+        "c+,d+,e+"
+    );
+    assert_eq!(
+        graph_algos::longest_dag_path(&g, v("d").rc())
+            .unwrap()
+            .print(&g),
+        "d-,c-,b-,a-"
+    );
+    assert_eq!(
+        graph_algos::longest_dag_path(&g, v("f")).unwrap().print(&g),
+        "f+"
+    );
+
+    let s = "
+S a * LN:i:100
+S b * LN:i:100
+S c * LN:i:100
+S d * LN:i:100
+L a + b + 10M
+L b + c + 10M
+L c + b + 10M
+L d + a + 10M
+";
+    let g = Graph::read(&s.replace(' ', "\t"));
+    let v = |name| Vertex::forward(g.name2id(name));
+    assert!(graph_algos::longest_dag_path(&g, v("a")).is_none());
+    assert!(graph_algos::longest_dag_path(&g, v("d")).is_none());
+    assert_eq!(
+        graph_algos::longest_dag_path(&g, v("a").rc())
+            .unwrap()
+            .print(&g),
+        "a-,d-"
+    );
+}
+
+#[test]
+fn deep_chain_dfs() {
+    //recursive traversal would overflow the stack on such a chain
+    let n = 200_000;
+    let mut s = String::new();
+    for i in 0..n {
+        s += &format!("S\tn{i}\t*\tLN:i:100\n");
+    }
+    for i in 1..n {
+        s += &format!("L\tn{}\t+\tn{}\t+\t10M\n", i - 1, i);
+    }
+    let g = Graph::read(&s);
+    let mut dfs = graph_algos::dfs::DFS::new_forward(&g);
+    dfs.run_from(Vertex::forward(0));
+    let exit_order = dfs.exit_order();
+    assert_eq!(exit_order.len(), n);
+    assert_eq!(exit_order[0], Vertex::forward(n - 1));
+    assert_eq!(exit_order[n - 1], Vertex::forward(0));
+
+    //long node stops the traversal and is reported in the boundary
+    let mut dfs = graph_algos::dfs::DFS::new_forward(&g);
+    dfs.set_max_node_len(100);
+    dfs.run_from(Vertex::forward(0));
+    assert_eq!(dfs.exit_order().len(), 1);
+    assert!(dfs.boundary().contains(&Vertex::forward(1)));
+
+    assert!(graph_algos::scc::strongly_connected(&g).is_empty());
+}
+
+#[test]
+fn dfs_reuse() {
+    let s = "
+S a * LN:i:100
+S b * LN:i:100
+S c * LN:i:100
+L a + b + 10M
+L b + c + 10M
+L c - a - 10M
+";
+    let g = Graph::read(&s.replace(' ', "\t"));
+    let a = Vertex::forward(g.name2id("a"));
+    let c = Vertex::forward(g.name2id("c"));
+
+    let mut visited = graph_algos::dfs::VertexSet::for_graph(&g);
+    assert!(visited.insert(a));
+    assert!(!visited.insert(a));
+    assert!(!visited.contains(&a.rc()));
+    assert_eq!(visited.len(), 1);
+    visited.clear();
+    assert!(visited.is_empty() && !visited.contains(&a));
+
+    //visited set is handed over between the searches
+    let mut dfs = graph_algos::dfs::DFS::new_forward(&g);
+    dfs.set_blocked(visited);
+    dfs.run_from(a);
+    let first = dfs.exit_order().clone();
+    assert_eq!(first.len(), 3);
+    let mut visited = dfs.take_blocked();
+    assert_eq!(visited.len(), 3);
+    visited.clear();
+
+    let mut dfs = graph_algos::dfs::DFS::new_forward(&g);
+    dfs.set_blocked(visited);
+    dfs.run_from(a);
+    assert_eq!(dfs.exit_order(), &first);
+    let mut visited = dfs.take_blocked();
+    visited.clear();
+
+    let mut dfs = graph_algos::dfs::DFS::new_forward(&g);
+    dfs.set_blocked(visited);
+    dfs.extend_blocked(std::iter::once(c));
+    dfs.run_from(a);
+    assert_eq!(dfs.exit_order().len(), 2);
+    assert!(dfs.boundary().contains(&c));
+}
+
+#[test]
+fn coverage_histogram() {
+    let s = "
+S a * LN:i:100 RC:i:1000
+S b * LN:i:300 RC:i:3300
+S c * LN:i:200
+S d * LN:i:400 RC:i:12000
+S e * LN:i:500 RC:i:5500
+";
+    let g = Graph::read(&s.replace(' ', "\t"));
+    let hist = graph_algos::coverage_histogram(&g, 10.);
+    assert_eq!(hist, vec![(10., 900), (30., 400)]);
+    let peak = graph_algos::estimate_haploid_peak(&g).unwrap();
+    assert!(peak > 9.5 && peak < 12.5, "{peak}");
+    //tiny bin width doesn't lead to huge allocations
+    assert_eq!(graph_algos::coverage_histogram(&g, 1e-12).len(), 3);
+
+    //extreme and non-finite coverage
+    let g = Graph::read(
+        &"
+S a * LN:i:100 ll:f:1e300
+S b * LN:i:300 ll:f:inf
+S c * LN:i:200 ll:f:NaN
+S d * LN:i:400 ll:f:10
+"
+        .replace(' ', "\t"),
+    );
+    let hist = graph_algos::coverage_histogram(&g, 1.);
+    assert_eq!(hist.len(), 2);
+    assert_eq!(hist[0], (10., 400));
+    assert_eq!(hist[1].1, 100);
+
+    assert!(graph_algos::coverage_histogram(&Graph::read("S\ta\t*\tLN:i:10"), 1.).is_empty());
+    assert_eq!(
+        graph_algos::estimate_haploid_peak(&Graph::read("S\ta\t*\tLN:i:10")),
+        None
+    );
+}
+
+#[test]
+fn ng50() {
+    let lengths = [100, 400, 300, 200];
+    assert_eq!(graph_algos::stats::n50(lengths.into_iter()), 300);
+    //NG50 equals N50 when genome size equals the total length
+    assert_eq!(
+        graph_algos::stats::ng50(lengths.into_iter(), 1000),
+        Some(300)
+    );
+    assert_eq!(
+        graph_algos::stats::ng50(lengths.into_iter(), 1800),
+        Some(200)
+    );
+    assert_eq!(
+        graph_algos::stats::ng50(lengths.into_iter(), 500),
+        Some(400)
+    );
+    assert_eq!(graph_algos::stats::ng50(lengths.into_iter(), 2001), None);
+    assert_eq!(graph_algos::stats::ng50(std::iter::empty(), 10), None);
+}
@@ -1,4 +1,6 @@
+use itertools::Itertools;
 use rukki::*;
+use std::collections::HashSet;
 
 #[test]
 fn one_node() {
@@ -30,17 +32,34 @@ L a + a + 10M
     assert_eq!(Direction::FORWARD, l.end.direction);
     let v = Vertex::forward(0);
     assert_eq!(g.outgoing_edges(v), vec![l]);
-    assert_eq!(g.incoming_edges(v), vec![l]);
+    assert_eq!(g.incoming_edges(v).collect_vec(), vec![l]);
 }
 
 #[test]
-#[should_panic]
 fn nontrivial_cigar() {
     let s = "
 S a * LN:i:100
+S b * LN:i:100
+S c * LN:i:100
 L a + a + 1D10M1I
+L a + b - 50M2I48M
+L b - c + *
+L c + a + 7Q
 ";
-    Graph::read(&s.replace(' ', "\t"));
+    let g = Graph::read(&s.replace(' ', "\t"));
+    let v = |name: &str| Vertex::forward(g.name2id(name));
+    let l = g.connector(v("a"), v("a")).unwrap();
+    assert_eq!(l.overlap, 11);
+    assert_eq!(g.link_cigar(l).as_deref(), Some("1D10M1I"));
+    let l = g.connector(v("a"), v("b").rc()).unwrap();
+    assert_eq!(l.overlap, 98);
+    assert_eq!(g.link_gfa_line(l), "L\ta\t+\tb\t-\t50M2I48M");
+    assert_eq!(g.link_cigar(l.rc()).as_deref(), Some("48M2D50M"));
+    //unknown and invalid overlaps default to 0
+    let l = g.connector(v("b").rc(), v("c")).unwrap();
+    assert_eq!(l.overlap, 0);
+    assert_eq!(g.link_gfa_line(l), "L\tb\t-\tc\t+\t0M");
+    assert_eq!(g.connector(v("c"), v("a")).unwrap().overlap, 0);
 }
 
 #[test]
@@ -60,7 +79,7 @@ L a - a - 10M
     assert_eq!(Direction::FORWARD, l.end.direction);
     let v = Vertex::forward(0);
     assert_eq!(g.outgoing_edges(v), vec![l]);
-    assert_eq!(g.incoming_edges(v), vec![l]);
+    assert_eq!(g.incoming_edges(v).collect_vec(), vec![l]);
 }
 
 #[test]
@@ -78,7 +97,7 @@ L a + a - 10M
     assert_eq!(Direction::REVERSE, l.end.direction);
     let v = Vertex::forward(0);
     assert_eq!(g.outgoing_edges(v), vec![l]);
-    assert_eq!(g.incoming_edges(v.rc()), vec![l]);
+    assert_eq!(g.incoming_edges(v.rc()).collect_vec(), vec![l]);
 }
 
 #[test]
@@ -95,7 +114,7 @@ L a - a + 10M
     assert_eq!(Direction::REVERSE, l.start.direction);
     assert_eq!(Direction::FORWARD, l.end.direction);
     let v = Vertex::forward(0);
-    assert_eq!(g.incoming_edges(v), vec![l]);
+    assert_eq!(g.incoming_edges(v).collect_vec(), vec![l]);
     assert_eq!(g.outgoing_edges(v.rc()), vec![l]);
 }
 
@@ -129,13 +148,13 @@ L a + b + 10M
         overlap: 10,
     };
     assert_eq!(g.outgoing_edges(v), vec![l]);
-    assert_eq!(g.incoming_edges(v), vec![]);
+    assert_eq!(g.incoming_edges(v).collect_vec(), vec![]);
     assert_eq!(g.outgoing_edges(v.rc()), vec![]);
-    assert_eq!(g.incoming_edges(v.rc()), vec![l.rc()]);
+    assert_eq!(g.incoming_edges(v.rc()).collect_vec(), vec![l.rc()]);
     assert_eq!(g.outgoing_edges(w), vec![]);
-    assert_eq!(g.incoming_edges(w), vec![l]);
+    assert_eq!(g.incoming_edges(w).collect_vec(), vec![l]);
     assert_eq!(g.outgoing_edges(w.rc()), vec![l.rc()]);
-    assert_eq!(g.incoming_edges(w.rc()), vec![]);
+    assert_eq!(g.incoming_edges(w.rc()).collect_vec(), vec![]);
 }
 
 #[test]
@@ -162,3 +181,995 @@ L b - a - 50M
     assert_eq!(1, g.link_cnt());
     assert_eq!(99, g.all_links().next().unwrap().overlap);
 }
+
+#[test]
+fn effective_coverage() {
+    let s = "
+S a * LN:i:100 RC:i:2000
+S b * LN:i:100 RC:i:3000
+S c * LN:i:100 RC:i:2000
+L a + b + 40M
+L b + c + 30M
+L a + c + 10M
+";
+    let g = Graph::read(&s.replace(' ', "\t"));
+    let b = g.name2id("b");
+    assert_eq!(30., g.node(b).coverage);
+    assert_eq!(30, g.unique_length(b));
+    assert_eq!(100., g.effective_coverage(b));
+    //only the longest of the overlaps on each side is excluded
+    assert_eq!(60, g.unique_length(g.name2id("a")));
+    assert_eq!(70, g.unique_length(g.name2id("c")));
+}
+
+#[test]
+fn effective_coverage_fully_overlapped() {
+    let s = "
+S a * LN:i:100 RC:i:2000
+S b * LN:i:60 RC:i:600
+S c * LN:i:100 RC:i:2000
+L a + b + 30M
+L b + c + 30M
+";
+    let g = Graph::read(&s.replace(' ', "\t"));
+    let b = g.name2id("b");
+    assert_eq!(0, g.unique_length(b));
+    assert_eq!(10., g.effective_coverage(b));
+}
+
+#[test]
+fn path_sequence() {
+    let s = "
+S a ACGTAC
+S b TTGGT
+S c *  LN:i:5
+L a + b - 2M
+";
+    let g = Graph::custom_read(
+        &s.replace(' ', "\t"),
+        &ReadOptions {
+            keep_sequences: true,
+            ..Default::default()
+        },
+    );
+    let a = Vertex::forward(g.name2id("a"));
+    let b = Vertex::forward(g.name2id("b"));
+    assert_eq!(g.vertex_sequence(b.rc()).unwrap(), "ACCAA");
+    let mut p = Path::from_link(g.connector(a, b.rc()).unwrap());
+    assert_eq!(p.sequence(&g).unwrap(), "ACGTACCAA");
+    assert_eq!(
+        p.clone().reverse_complement().sequence(&g).unwrap(),
+        "TTGGTACGT"
+    );
+    p.append_general(GeneralizedLink::GAP(GapInfo {
+        start: b.rc(),
+        end: Vertex::forward(g.name2id("c")),
+        gap_size: 3,
+        info: String::new(),
+    }));
+    assert_eq!(p.sequence(&g), None);
+    assert!(Graph::read(&s.replace(' ', "\t"))
+        .vertex_sequence(a)
+        .is_none());
+}
+
+#[test]
+fn inconsistent_path_link() {
+    let s = "
+S a * LN:i:100
+S b * LN:i:200
+S c * LN:i:200
+L a + b + 10M
+L b + c + 20M
+";
+    let g = Graph::read(&s.replace(' ', "\t"));
+    let a = Vertex::forward(g.name2id("a"));
+    let b = Vertex::forward(g.name2id("b"));
+    let c = Vertex::forward(g.name2id("c"));
+    let mut p = Path::from_link(g.connector(a, b).unwrap());
+    p.append(g.connector(b, c).unwrap());
+    assert_eq!(p.first_inconsistent_link(&g), None);
+    assert_eq!(
+        p.clone().reverse_complement().first_inconsistent_link(&g),
+        None
+    );
+
+    let mut corrupted = Path::from_link(g.connector(a, b).unwrap());
+    corrupted.append(Link {
+        start: b,
+        end: c,
+        overlap: 15,
+    });
+    assert_eq!(corrupted.first_inconsistent_link(&g), Some(1));
+
+    let missing = Path::from_link(Link {
+        start: a,
+        end: c,
+        overlap: 10,
+    });
+    assert_eq!(missing.first_inconsistent_link(&g), Some(0));
+
+    let records = vec![PathRecord::new("corrupted", corrupted, None)];
+    assert!(check_path_links(&g, &records).is_err());
+}
+
+#[test]
+fn lossy_utf8_gfa() {
+    let mut bytes = b"S\ta\t*\tLN:i:100\n".to_vec();
+    bytes.extend(b"S\tb\t*\tLN:i:200\tCO:Z:caf");
+    bytes.push(0xE9);
+    bytes.extend(b"\nL\ta\t+\tb\t+\t10M\n");
+    let gfa_fn = std::env::temp_dir().join(format!("rukki_lossy_{}.gfa", std::process::id()));
+    std::fs::write(&gfa_fn, &bytes).unwrap();
+
+    assert!(rukki::read_gfa_text(&gfa_fn, false).is_err());
+    let g = Graph::read(&rukki::read_gfa_text(&gfa_fn, true).unwrap());
+    std::fs::remove_file(&gfa_fn).unwrap();
+    assert_eq!(g.node_cnt(), 2);
+    assert_eq!(g.node_length(g.name2id("b")), 200);
+    assert_eq!(g.link_cnt(), 1);
+}
+
+#[test]
+fn gzipped_gfa() {
+    use flate2::write::GzEncoder;
+    use std::io::Write;
+
+    let s = "S\ta\t*\tLN:i:100\nS\tb\t*\tLN:i:200\nL\ta\t+\tb\t-\t10M\n";
+    let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(s.as_bytes()).unwrap();
+    let bytes = encoder.finish().unwrap();
+
+    let tmp = std::env::temp_dir();
+    let pid = std::process::id();
+    //second file is misnamed and has to be recognized by magic bytes
+    for gfa_fn in [
+        tmp.join(format!("rukki_gz_{pid}.gfa.gz")),
+        tmp.join(format!("rukki_gz_{pid}.gfa")),
+    ] {
+        std::fs::write(&gfa_fn, &bytes).unwrap();
+        let text = rukki::read_gfa_text(&gfa_fn, false).unwrap();
+        std::fs::remove_file(&gfa_fn).unwrap();
+        assert_eq!(text, s);
+        let g = Graph::read(&text);
+        assert_eq!(g.node_cnt(), 2);
+        assert_eq!(g.node_length(g.name2id("b")), 200);
+        assert_eq!(g.link_cnt(), 1);
+    }
+}
+
+#[test]
+fn read_from_reader() {
+    let s = "
+H VN:Z:1.0
+S a * LN:i:100 RC:i:1000
+L a + b - 3M
+S b ACGTACGTAC
+L b - c + 5M
+S c * LN:i:50
+";
+    let s = s.replace(' ', "\t");
+    let g = Graph::read_from(std::io::BufReader::new(s.as_bytes())).unwrap();
+    assert_eq!(g.node_cnt(), 3);
+    assert_eq!(g.link_cnt(), 2);
+    assert_eq!(g.node_length(g.name2id("b")), 10);
+    assert!((g.node(g.name2id("a")).coverage - 10.).abs() < 1e-9);
+    assert_eq!(g.as_gfa(), Graph::read(&s).as_gfa());
+
+    //invalid UTF-8 is reported as error rather than panic
+    let bytes = b"S\ta\t*\tLN:i:100\tCO:Z:caf\xE9\n";
+    assert!(Graph::read_from(&bytes[..]).is_err());
+}
+
+#[test]
+fn typed_tags() {
+    let s = "
+S a * LN:i:100 KC:i:5000 dp:f:12.5 tp:A:x CO:Z:some comment XX:B:i,1,2
+S b * LN:i:200 SO:i:bad
+L a + b - 10M ID:Z:link1 cv:f:3.5
+";
+    let s = s
+        .replace(' ', "\t")
+        .replace("some\tcomment", "some comment");
+    let g = Graph::read(&s);
+    let a = g.node(g.name2id("a"));
+    assert_eq!(a.tag("LN"), Some(&TagValue::Int(100)));
+    assert_eq!(a.tag("KC").and_then(TagValue::as_int), Some(5000));
+    assert_eq!(a.tag("dp").and_then(TagValue::as_float), Some(12.5));
+    assert_eq!(a.tag("tp"), Some(&TagValue::Char('x')));
+    assert_eq!(a.tag("CO").and_then(TagValue::as_str), Some("some comment"));
+    assert_eq!(
+        a.tag("XX"),
+        Some(&TagValue::Other('B', String::from("i,1,2")))
+    );
+    assert_eq!(a.tag("XX").unwrap().to_string(), "i,1,2");
+    assert_eq!(a.tag("RC"), None);
+    //malformed value is dropped
+    assert_eq!(g.node(g.name2id("b")).tag("SO"), None);
+
+    let l = g.all_links().next().unwrap();
+    assert_eq!(
+        g.link_tag(l, "ID").and_then(TagValue::as_str),
+        Some("link1")
+    );
+    assert_eq!(g.link_tag(l.rc(), "cv"), Some(&TagValue::Float(3.5)));
+    assert_eq!(g.link_tag(l, "XX"), None);
+}
+
+#[test]
+fn coverage_tags() {
+    let s = "
+S a * LN:i:100 RC:i:1000 dp:f:3.0
+S b * LN:i:100 dp:f:12.5 KC:i:5000
+S c * LN:i:200 KC:i:5000
+S d * LN:i:100
+";
+    let s = s.replace(' ', "\t");
+    let g = Graph::read(&s);
+    let cov = |name: &str| g.node(g.name2id(name)).coverage;
+    assert!((cov("a") - 10.).abs() < 1e-9);
+    assert!((cov("b") - 12.5).abs() < 1e-9);
+    assert!((cov("c") - 25.).abs() < 1e-9);
+    assert!(cov("d").is_nan());
+    assert!(!g.node(g.name2id("d")).has_coverage());
+    assert!(g.as_gfa().contains("S\td\t*\tLN:i:100\n"));
+}
+
+#[test]
+fn fallback_coverage_tags_parsed_lazily() {
+    //malformed lower priority tags are irrelevant if a preferred one is present
+    let s = "
+S a * LN:i:100 RC:i:1000 FC:i:x dp:f:y
+S b * LN:i:100 dp:f:12.5 KC:i:z
+";
+    let g = Graph::read_from(s.replace(' ', "\t").as_bytes()).unwrap();
+    assert!((g.node(g.name2id("a")).coverage - 10.).abs() < 1e-9);
+    assert!((g.node(g.name2id("b")).coverage - 12.5).abs() < 1e-9);
+
+    let s = "S a * LN:i:100 FC:i:x\n";
+    assert!(Graph::read_from(s.replace(' ', "\t").as_bytes()).is_err());
+}
+
+#[test]
+fn star_sequence_length() {
+    let s = "
+S a * LN:i:12345
+S b ACGT
+S c ACGTA LN:i:7
+S d  LN:i:3
+";
+    //empty sequence field for d
+    let s = s.replace(' ', "\t");
+    let g = Graph::custom_read(
+        &s,
+        &ReadOptions {
+            keep_sequences: true,
+            ..Default::default()
+        },
+    );
+    assert_eq!(g.node_length(g.name2id("a")), 12345);
+    assert_eq!(g.node(g.name2id("a")).sequence, None);
+    assert_eq!(g.node_length(g.name2id("b")), 4);
+    //sequence takes priority over inconsistent LN
+    assert_eq!(g.node_length(g.name2id("c")), 5);
+    assert_eq!(g.node_length(g.name2id("d")), 3);
+}
+
+#[test]
+#[should_panic(expected = "line 2: neither sequence nor LN tag provided for segment 'b'")]
+fn star_sequence_without_length() {
+    let s = "S\ta\tACGT\nS\tb\t*\tRC:i:100\n";
+    Graph::read(s);
+}
+
+#[test]
+fn parse_error_lines() {
+    let error_str = |s: &str| {
+        Graph::read_from(s.replace(' ', "\t").as_bytes())
+            .err()
+            .unwrap()
+            .to_string()
+    };
+    assert_eq!(
+        error_str("S a * LN:i:100\nL a + utig4-123 - 10M\n"),
+        "line 2: link references unknown segment 'utig4-123'"
+    );
+    assert_eq!(
+        error_str("S a * LN:i:100\nS b * LN:i:100\n\nL a + b x 10M\n"),
+        "line 4: invalid orientation 'x'"
+    );
+    assert_eq!(
+        error_str("S a * LN:i:100\nS b * LN:i:100\nL a + b -\n"),
+        "line 3: missing overlap field"
+    );
+    assert_eq!(
+        error_str("S a * LN:i:100\nS b * LN:i:x\n"),
+        "line 2: couldn't parse tag 'LN:i:x'"
+    );
+    assert_eq!(
+        error_str("S a * LN:i:100\nS a * LN:i:100\n"),
+        "line 2: duplicate segment 'a'"
+    );
+    let e = Graph::read_from("S\ta\t*\tLN:i:100\nL\ta\t+\ta\t+\t100M\n".as_bytes())
+        .err()
+        .unwrap();
+    assert_eq!(e.line, 2);
+    assert!(matches!(e.kind, ParseErrorKind::TooLongOverlap(100, _, _)));
+
+    //stored sequences have to be ASCII
+    let options = ReadOptions {
+        keep_sequences: true,
+        ..Default::default()
+    };
+    let e = Graph::custom_read_from("S\ta\tACGT\nS\tb\tACΓT\n".as_bytes(), &options)
+        .err()
+        .unwrap();
+    assert_eq!(
+        e.to_string(),
+        "line 2: non-ASCII characters in sequence of segment 'b'"
+    );
+}
+
+#[test]
+fn gfa_round_trip() {
+    let s = "
+S a ACGTACGTAC dp:f:12.5 CO:Z:first
+S b * LN:i:200 RC:i:1000
+S c * LN:i:50
+L a + b - 5M ID:Z:l1
+L b - c + 0M
+";
+    let s = s.replace(' ', "\t");
+    let mut g = Graph::custom_read(
+        &s,
+        &ReadOptions {
+            keep_sequences: true,
+            ..Default::default()
+        },
+    );
+    g.set_node_tag(
+        g.name2id("c"),
+        "hp",
+        TagValue::String(String::from("MATERNAL")),
+    );
+    let mut out = Vec::new();
+    g.write_gfa(&mut out).unwrap();
+    let gfa = String::from_utf8(out).unwrap();
+    assert!(gfa.contains("S\ta\tACGTACGTAC\tLN:i:10\tCO:Z:first\tdp:f:12.5\n"));
+    assert!(gfa.contains("S\tc\t*\tLN:i:50\thp:Z:MATERNAL\n"));
+
+    let g2 = Graph::custom_read(
+        &gfa,
+        &ReadOptions {
+            keep_sequences: true,
+            ..Default::default()
+        },
+    );
+    assert_eq!(g2.as_gfa(), g.as_gfa());
+    for n in g.all_nodes() {
+        let n2 = g2.node(g2.name2id(&n.name));
+        assert_eq!(n2.sequence, n.sequence);
+        assert_eq!(n2.tags.get("CO"), n.tags.get("CO"));
+        assert_eq!(n2.tags.get("hp"), n.tags.get("hp"));
+    }
+    let l = g2
+        .all_links()
+        .find(|l| g2.name(l.start.node_id) == "a" || g2.name(l.end.node_id) == "a");
+    assert_eq!(
+        g2.link_tag(l.unwrap(), "ID").and_then(TagValue::as_str),
+        Some("l1")
+    );
+}
+
+#[test]
+fn input_paths() {
+    let s = "
+S a * LN:i:100
+S b * LN:i:100
+S c * LN:i:100
+L a + b - 10M
+L b - c + 5M
+P p1 a+,b-,c+ 10M,5M
+P p2 c-,b+ *
+P p3 a-
+";
+    let s = s.replace(' ', "\t");
+    let g = Graph::read(&s);
+    let paths = g.input_paths();
+    assert_eq!(paths.len(), 3);
+    assert_eq!(paths[0].0, "p1");
+    assert_eq!(paths[0].1.print(&g), "a+,b-,c+");
+    assert_eq!(paths[0].1.total_length(&g), 285);
+    assert_eq!(paths[1].1.print(&g), "c-,b+");
+    assert_eq!(paths[1].1.link_at(0).overlap, 5);
+    assert_eq!(paths[2].1.print(&g), "a-");
+
+    let mut out = Vec::new();
+    g.write_gfa(&mut out).unwrap();
+    let gfa = String::from_utf8(out).unwrap();
+    assert!(gfa.contains("P\tp1\ta+,b-,c+\t10M,5M\n"));
+    assert!(gfa.contains("P\tp3\ta-\t*\n"));
+
+    let error_str = |p: &str| {
+        Graph::read_from(format!("{s}{p}\n").replace(' ', "\t").as_bytes())
+            .err()
+            .unwrap()
+            .to_string()
+    };
+    assert_eq!(
+        error_str("P p4 a+,d+ *"),
+        "line 10: invalid path 'p4': unknown segment 'd'"
+    );
+    assert_eq!(
+        error_str("P p4 a+,b- 10M,5M"),
+        "line 10: invalid path 'p4': 2 overlaps provided for 2 segments"
+    );
+
+    //paths revisiting a segment are skipped
+    let g = Graph::read(
+        &format!("{s}P p4 a+,b-,a+ *\nW s 1 p5 0 300 >a<b>a\nP p6 c+ *\n").replace(' ', "\t"),
+    );
+    let names = g
+        .input_paths()
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .collect_vec();
+    assert_eq!(names, ["p1", "p2", "p3", "p6"]);
+}
+
+#[test]
+fn try_name2id() {
+    let g = Graph::read("S\ta\t*\tLN:i:100\nS\tb\t*\tLN:i:100\n");
+    assert_eq!(g.try_name2id("b"), Some(1));
+    assert_eq!(g.try_name2id("c"), None);
+    assert_eq!(g.name2id("a"), 0);
+}
+
+#[test]
+fn subgraph_neighborhood() {
+    let s = "
+S a * LN:i:100
+S b * LN:i:200
+S c * LN:i:300
+S d * LN:i:400
+S e * LN:i:500
+L a + b - 10M ID:Z:ab
+L c + b + 10M
+L c + d + 10M
+L d + e + 10M
+L e + e - 10M
+P p1 a+,b- *
+P p2 b-,c- *
+";
+    let s = s.replace(' ', "\t");
+    let g = Graph::read(&s);
+    let nodes = |names: &[&str]| {
+        names
+            .iter()
+            .map(|n| g.name2id(n))
+            .collect::<HashSet<usize>>()
+    };
+    assert_eq!(g.neighborhood(g.name2id("a"), 0), nodes(&["a"]));
+    assert_eq!(g.neighborhood(g.name2id("a"), 2), nodes(&["a", "b", "c"]));
+    assert_eq!(g.neighborhood(g.name2id("d"), 1), nodes(&["c", "d", "e"]));
+    assert_eq!(g.neighborhood(g.name2id("e"), 100).len(), 5);
+
+    let sub = g.subgraph(&nodes(&["a", "b", "e"]));
+    assert_eq!(sub.node_cnt(), 3);
+    assert_eq!(sub.name(2), "e");
+    assert_eq!(sub.node_length(sub.name2id("b")), 200);
+    assert_eq!(sub.link_cnt(), 2);
+    let l = sub
+        .connector(
+            Vertex::forward(sub.name2id("a")),
+            Vertex::reverse(sub.name2id("b")),
+        )
+        .unwrap();
+    assert_eq!(sub.link_tag(l, "ID").and_then(TagValue::as_str), Some("ab"));
+    assert!(sub
+        .connector(
+            Vertex::forward(sub.name2id("e")),
+            Vertex::reverse(sub.name2id("e"))
+        )
+        .is_some());
+    assert_eq!(sub.input_paths().len(), 1);
+    assert_eq!(sub.input_paths()[0].1.print(&sub), "a+,b-");
+}
+
+#[test]
+fn node_link_removal() {
+    let s = "
+S a * LN:i:100
+S b * LN:i:200
+S c * LN:i:300
+S d * LN:i:400
+L a + b - 10M
+L b - c + 10M
+L c + c - 10M
+L c + d + 10M
+L a - d + 10M
+P p1 a+,b-,c+ *
+";
+    let mut g = Graph::read(&s.replace(' ', "\t"));
+    let v = |g: &Graph, name: &str| Vertex::forward(g.name2id(name));
+
+    let l = g.connector(v(&g, "a"), v(&g, "b").rc()).unwrap();
+    g.remove_link(&l.rc());
+    assert_eq!(g.link_cnt(), 4);
+    assert!(g.outgoing_edges(v(&g, "a")).is_empty());
+    assert!(g.outgoing_edges(v(&g, "b")).is_empty());
+
+    let c = g.name2id("c");
+    g.remove_node(c);
+    assert!(g.is_removed(c));
+    assert_eq!(g.try_name2id("c"), None);
+    assert_eq!(g.node_cnt(), 4);
+    assert_eq!(g.link_cnt(), 1);
+    assert!(g.input_paths().is_empty());
+    assert_eq!(g.incoming_edges(v(&g, "b")).len(), 0);
+    assert_eq!(g.incoming_edges(v(&g, "d")).len(), 1);
+
+    let new_ids = g.compact();
+    assert_eq!(new_ids, vec![Some(0), Some(1), None, Some(2)]);
+    assert_eq!(g.node_cnt(), 3);
+    assert_eq!(g.name2id("d"), 2);
+    assert_eq!(g.link_cnt(), 1);
+    assert!(g.connector(v(&g, "a").rc(), v(&g, "d")).is_some());
+}
+
+#[test]
+fn degrees_and_neighbors() {
+    let s = "
+S a * LN:i:100
+S b * LN:i:100
+S c * LN:i:100
+L a + b + 10M
+L a + c - 10M
+L c + a + 10M
+";
+    let g = Graph::read(&s.replace(' ', "\t"));
+    let v = |name: &str| Vertex::forward(g.name2id(name));
+    assert_eq!(g.out_degree(v("a")), 2);
+    assert_eq!(g.in_degree(v("a")), 1);
+    assert_eq!(g.in_degree(v("c").rc()), 2);
+    assert_eq!(g.in_degree(v("b").rc()), 0);
+    for w in [v("a"), v("b"), v("c")] {
+        for x in [w, w.rc()] {
+            assert_eq!(
+                g.successors(x).collect::<Vec<_>>(),
+                g.outgoing_edges(x)
+                    .iter()
+                    .map(|l| l.end)
+                    .collect::<Vec<_>>()
+            );
+            assert_eq!(
+                g.predecessors(x).collect::<Vec<_>>(),
+                g.incoming_edges(x).map(|l| l.start).collect::<Vec<_>>()
+            );
+            assert_eq!(g.out_degree(x), g.successors(x).count());
+            assert_eq!(g.in_degree(x), g.predecessors(x).count());
+        }
+    }
+    assert_eq!(g.predecessors(v("a")).collect::<Vec<_>>(), vec![v("c")]);
+    assert_eq!(
+        g.predecessors(v("c").rc()).sorted().collect::<Vec<_>>(),
+        vec![v("a"), v("a").rc()]
+    );
+}
+
+#[test]
+fn full_containment_links() {
+    let s = "
+S a * LN:i:100
+S b * LN:i:100
+S c * LN:i:50
+S d * LN:i:50
+L c + a + 10M
+L a + b - 100M
+L b - d + 10M
+";
+    let s = s.replace(' ', "\t");
+    let g = Graph::custom_read(
+        &s,
+        &ReadOptions {
+            normalize_overlaps: true,
+            ..Default::default()
+        },
+    );
+    assert_eq!(g.node_cnt(), 4);
+    assert_eq!(g.link_cnt(), 3);
+    let a = Vertex::forward(g.name2id("a"));
+    let b = Vertex::reverse(g.name2id("b"));
+    assert!(g.is_full_containment(Link {
+        start: a,
+        end: b,
+        overlap: 100
+    }));
+    //overlap was normalized
+    let l = g.connector(a, b).unwrap();
+    assert_eq!(l.overlap, 99);
+    assert!(!g.is_full_containment(l));
+
+    let g = Graph::custom_read(
+        &s,
+        &ReadOptions {
+            normalize_overlaps: true,
+            collapse_containments: true,
+            ..Default::default()
+        },
+    );
+    assert_eq!(g.node_cnt(), 3);
+    assert_eq!(g.link_cnt(), 2);
+    let v = |name, direction| Vertex {
+        node_id: g.name2id(name),
+        direction,
+    };
+    assert!(g
+        .connector(v("c", Direction::FORWARD), v("a", Direction::FORWARD))
+        .is_some());
+    assert!(g
+        .connector(v("a", Direction::FORWARD), v("d", Direction::FORWARD))
+        .is_some());
+    assert_eq!(g.all_nodes().map(|n| n.length).sum::<usize>(), 200);
+}
+
+#[test]
+fn parse_gaf_path() {
+    let s = "
+S a * LN:i:100
+S b * LN:i:100
+S c * LN:i:100
+S d * LN:i:100
+L a + b - 10M
+L b - c + 5M
+";
+    let g = Graph::read(&s.replace(' ', "\t"));
+    let p = Path::parse_gaf(&g, ">a<b>c").unwrap();
+    assert_eq!(p.print(&g), "a+,b-,c+");
+    assert_eq!(p.total_length(&g), 285);
+    assert_eq!(Path::parse_gaf(&g, &p.print_format(&g, true)).unwrap(), p);
+    let rc_p = p.clone().reverse_complement();
+    assert_eq!(
+        Path::parse_gaf(&g, &rc_p.print_format(&g, true)).unwrap(),
+        rc_p
+    );
+
+    let gapped = Path::parse_gaf(&g, ">a<b>c[N5000N:manual]<d").unwrap();
+    assert_eq!(gapped.print(&g), "a+,b-,c+,[N5000N:manual],d-");
+    assert_eq!(
+        Path::parse_gaf(&g, &gapped.print_format(&g, true)).unwrap(),
+        gapped
+    );
+
+    assert_eq!(Path::parse_gaf(&g, ""), Err(PathParseError::Empty));
+    assert_eq!(
+        Path::parse_gaf(&g, ">a>x"),
+        Err(PathParseError::UnknownNode(String::from("x")))
+    );
+    assert_eq!(
+        Path::parse_gaf(&g, ">a>b"),
+        Err(PathParseError::NoLink(
+            String::from("a+"),
+            String::from("b+")
+        ))
+    );
+    assert_eq!(
+        Path::parse_gaf(&g, ">a<b<a"),
+        Err(PathParseError::RepeatedNode(String::from("a")))
+    );
+    assert!(matches!(
+        Path::parse_gaf(&g, "a+,b-"),
+        Err(PathParseError::InvalidToken(_))
+    ));
+    assert!(matches!(
+        Path::parse_gaf(&g, ">a[N10N:x]"),
+        Err(PathParseError::InvalidToken(_))
+    ));
+}
+
+#[test]
+fn path_concat() {
+    let s = "
+S a * LN:i:100
+S b * LN:i:100
+S c * LN:i:100
+S d * LN:i:100
+L a + b - 10M
+L b - c + 5M
+L c + d + 5M
+L c + a + 5M
+";
+    let g = Graph::read(&s.replace(' ', "\t"));
+    let mut p = Path::parse_gaf(&g, ">a<b").unwrap();
+    p.try_concat(&Path::parse_gaf(&g, ">c>d").unwrap(), &g)
+        .unwrap();
+    assert_eq!(p.print(&g), "a+,b-,c+,d+");
+    assert_eq!(p.total_length(&g), 380);
+
+    let mut p = Path::parse_gaf(&g, ">a").unwrap();
+    assert_eq!(
+        p.try_concat(&Path::parse_gaf(&g, ">c").unwrap(), &g),
+        Err(ConcatError::NoLink(String::from("a+"), String::from("c+")))
+    );
+    assert_eq!(p.print(&g), "a+");
+
+    let mut p = Path::parse_gaf(&g, "<b>c").unwrap();
+    assert_eq!(
+        p.try_concat(&Path::parse_gaf(&g, ">a<b").unwrap(), &g),
+        Err(ConcatError::SharedNode(String::from("b")))
+    );
+    assert_eq!(p.print(&g), "b-,c+");
+}
+
+#[test]
+fn edge_lists_consistent() {
+    let s = "
+S a * LN:i:100
+S b * LN:i:100
+S c * LN:i:100
+L a + b + 10M
+L a + b - 10M
+L b - c + 10M
+L c - c + 10M
+L a - a + 10M
+";
+    let mut g = Graph::read(&s.replace(' ', "\t"));
+    let check = |g: &Graph| {
+        for v in g.all_vertices() {
+            assert_eq!(g.out_degree(v), g.outgoing_edges(v).len());
+            assert_eq!(
+                g.successors(v).collect::<Vec<_>>(),
+                g.outgoing_edges(v)
+                    .iter()
+                    .map(|l| l.end)
+                    .collect::<Vec<_>>()
+            );
+            let rc_incoming = g.incoming_edges(v.rc()).map(|l| l.rc()).collect::<Vec<_>>();
+            assert_eq!(g.outgoing_edges(v), rc_incoming.as_slice());
+        }
+    };
+    check(&g);
+    let a = Vertex::forward(g.name2id("a"));
+    let b = Vertex::forward(g.name2id("b"));
+    let l = g.connector(a, b.rc()).unwrap();
+    g.remove_link(&l);
+    check(&g);
+    assert_eq!(g.outgoing_edges(a).len(), 1);
+    g.remove_node(g.name2id("c"));
+    check(&g);
+    assert_eq!(g.outgoing_edges(b.rc()).len(), 1);
+}
+
+#[test]
+fn double_reverse_complement() {
+    let s = "
+S a * LN:i:100
+S b * LN:i:100
+S c * LN:i:100
+S d * LN:i:100
+L a + b - 10M
+L b - c + 5M
+";
+    let g = Graph::read(&s.replace(' ', "\t"));
+    for gaf in [">a", ">a<b>c", ">a<b>c[N5000N:manual]<d"] {
+        let p = Path::parse_gaf(&g, gaf).unwrap();
+        let rc_p = p.clone().reverse_complement();
+        assert_eq!(rc_p.len(), p.len());
+        assert_eq!(rc_p.start(), p.end().rc());
+        assert_eq!(rc_p.end(), p.start().rc());
+        assert_eq!(rc_p.reverse_complement(), p);
+    }
+    let p = Path::parse_gaf(&g, ">a<b>c").unwrap();
+    assert_eq!(p.clone().reverse_complement().print(&g), "c-,b+,a-");
+}
+
+#[test]
+fn path_validate() {
+    let s = "
+S a * LN:i:100
+S b * LN:i:100
+S c * LN:i:100
+L a + b - 10M
+";
+    let g = Graph::read(&s.replace(' ', "\t"));
+    let a = Vertex::forward(g.name2id("a"));
+    let b = Vertex::forward(g.name2id("b"));
+    let c = Vertex::forward(g.name2id("c"));
+
+    assert_eq!(Path::new(a).validate(&g), Ok(()));
+    assert_eq!(Path::parse_gaf(&g, ">a<b").unwrap().validate(&g), Ok(()));
+    let mut gapped = Path::parse_gaf(&g, ">a<b").unwrap();
+    gapped.append_general(GeneralizedLink::GAP(GapInfo {
+        start: b.rc(),
+        end: c,
+        gap_size: 100,
+        info: String::new(),
+    }));
+    assert_eq!(gapped.validate(&g), Ok(()));
+
+    let wrong_overlap = Path::from_link(Link {
+        start: a,
+        end: b.rc(),
+        overlap: 5,
+    });
+    assert_eq!(
+        wrong_overlap.validate(&g),
+        Err(PathError::MissingLink(
+            String::from("a+"),
+            String::from("b-"),
+            5
+        ))
+    );
+    let absent = Path::from_link(Link {
+        start: a,
+        end: c,
+        overlap: 0,
+    });
+    assert!(matches!(
+        absent.validate(&g),
+        Err(PathError::MissingLink(..))
+    ));
+    assert_eq!(
+        Path::new(Vertex::forward(10)).validate(&g),
+        Err(PathError::UnknownNode(10))
+    );
+}
+
+#[test]
+fn path_trim_to() {
+    let s = "
+S a * LN:i:100
+S b * LN:i:100
+S c * LN:i:100
+S d * LN:i:100
+L a + b + 10M
+L b + c + 10M
+";
+    let g = Graph::read(&s.replace(' ', "\t"));
+    let path = Path::parse_gaf(&g, ">a>b>c").unwrap();
+    let v = |name: &str| Vertex::forward(g.name2id(name));
+
+    let mut p = path.clone();
+    assert!(p.trim_to(&v("c")));
+    assert_eq!(p, path);
+
+    let mut p = path.clone();
+    assert!(p.trim_to(&v("b")));
+    assert_eq!(p.print(&g), "a+,b+");
+    assert_eq!(p.validate(&g), Ok(()));
+
+    let mut p = path.clone();
+    assert!(p.trim_to(&v("a")));
+    assert_eq!(p, Path::new(v("a")));
+    assert_eq!(p.len(), 1);
+
+    let mut p = path.clone();
+    assert!(!p.trim_to(&v("d")));
+    assert!(!p.trim_to(&v("b").rc()));
+    assert_eq!(p, path);
+}
+
+#[test]
+fn palindromic_nodes() {
+    let s = "
+S a * LN:i:100
+S b * LN:i:100
+S c * LN:i:100
+L a - a + 10M
+L a + b + 10M
+L b + b + 10M
+";
+    let g = Graph::read(&s.replace(' ', "\t"));
+    assert!(g.is_palindromic(g.name2id("a")));
+    //self-loop keeps orientation
+    assert!(!g.is_palindromic(g.name2id("b")));
+    assert!(!g.is_palindromic(g.name2id("c")));
+    //sequence wasn't stored
+    assert!(g.palindromic_nodes().is_empty());
+}
+
+#[test]
+fn node_length_override() {
+    let s = "
+S a * LN:i:100 RC:i:2000
+S b *
+S c ACGT
+L a + b + 10M
+";
+    assert!(Graph::read_from(std::io::Cursor::new(s.replace(' ', "\t"))).is_err());
+
+    let lengths_fn = std::env::temp_dir().join(format!("rukki_lengths_{}.tsv", std::process::id()));
+    std::fs::write(&lengths_fn, "#name\tlength\na\t200\nb\t50\nx\t10\n").unwrap();
+    let lengths = read_node_lengths(&lengths_fn).unwrap();
+    assert_eq!(lengths.len(), 3);
+
+    let g = Graph::custom_read_from(
+        std::io::Cursor::new(s.replace(' ', "\t")),
+        &ReadOptions {
+            node_lengths: Some(&lengths),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    assert_eq!(g.node_length(g.name2id("a")), 200);
+    assert_eq!(g.node_length(g.name2id("b")), 50);
+    assert_eq!(g.node_length(g.name2id("c")), 4);
+    //read counts are normalized by the provided length
+    assert_eq!(g.node(g.name2id("a")).coverage, 10.);
+
+    std::fs::write(&lengths_fn, "a\t200\nb\tNA\n").unwrap();
+    assert!(read_node_lengths(&lengths_fn).is_err());
+    std::fs::remove_file(&lengths_fn).unwrap();
+}
+
+#[test]
+fn exact_size_iterators() {
+    let s = "
+S a * LN:i:100
+S b * LN:i:200
+S c * LN:i:300
+L a + b - 10M
+L a + b - 10M
+L b - c + 10M
+L c + c - 10M
+L c - a - 10M
+";
+    let mut g = Graph::read(&s.replace(' ', "\t"));
+    let check = |g: &Graph| {
+        assert_eq!(g.all_nodes().len(), g.node_cnt());
+        let mut vertices = g.all_vertices();
+        assert_eq!(vertices.len(), 2 * g.node_cnt());
+        vertices.next();
+        assert_eq!(vertices.len(), 2 * g.node_cnt() - 1);
+        assert_eq!(vertices.count(), 2 * g.node_cnt() - 1);
+        let mut links = g.all_links();
+        assert_eq!(links.len(), g.link_cnt());
+        links.next();
+        assert_eq!(links.len(), g.link_cnt() - 1);
+        assert_eq!(links.count(), g.link_cnt() - 1);
+    };
+    check(&g);
+    assert_eq!(g.link_cnt(), 5);
+
+    //both copies of the multi-edge are removed
+    let a = Vertex::forward(g.name2id("a"));
+    let l = g.outgoing_edges(a)[0];
+    g.remove_link(&l.rc());
+    assert_eq!(g.link_cnt(), 3);
+    check(&g);
+
+    g.remove_node(g.name2id("c"));
+    assert_eq!(g.link_cnt(), 0);
+    assert_eq!(g.all_links().len(), 0);
+}
+
+#[test]
+fn path_raw_length() {
+    let s = "
+S a * LN:i:100
+S b * LN:i:200
+S c * LN:i:300
+L a + b - 10M
+";
+    let g = Graph::read(&s.replace(' ', "\t"));
+    let a = Vertex::forward(g.name2id("a"));
+    let b = Vertex::forward(g.name2id("b"));
+    let c = Vertex::forward(g.name2id("c"));
+    assert_eq!(Path::new(c).raw_length(&g), 300);
+    assert_eq!(Path::new(c).total_length(&g), 300);
+
+    let mut p = Path::from_link(g.connector(a, b.rc()).unwrap());
+    assert_eq!(p.total_length(&g), 290);
+    assert_eq!(p.raw_length(&g), 300);
+
+    p.append_general(GeneralizedLink::GAP(GapInfo {
+        start: b.rc(),
+        end: c,
+        gap_size: 50,
+        info: String::new(),
+    }));
+    assert_eq!(p.total_length(&g), 640);
+    assert_eq!(p.raw_length(&g), 600);
+    assert_eq!(p.node_offsets(&g).collect::<Vec<_>>(), vec![0, 90, 340]);
+    assert_eq!(Path::new(c).node_offsets(&g).collect::<Vec<_>>(), vec![0]);
+}
@@ -0,0 +1,1061 @@
+use rukki::trio::*;
+use rukki::*;
+use std::fs;
+
+#[test]
+fn hap_fasta_split() {
+    use rukki::path_sink::*;
+    let s = "
+S a AAAA
+S b CCCC
+S c GGGG
+S d GTTT
+L a + d - 1M
+";
+    let g = Graph::custom_read(
+        &s.replace(' ', "\t"),
+        &ReadOptions {
+            keep_sequences: true,
+            ..Default::default()
+        },
+    );
+    let mut assignments = AssignmentStorage::new();
+    assignments.assign(g.name2id("a"), TrioGroup::MATERNAL, "");
+    assignments.assign(g.name2id("b"), TrioGroup::PATERNAL, "");
+    assignments.assign(g.name2id("c"), TrioGroup::HOMOZYGOUS, "");
+    assignments.assign(g.name2id("d"), TrioGroup::MATERNAL, "");
+    let mut node_usage = AssignmentStorage::new();
+    node_usage.assign(g.name2id("a"), TrioGroup::MATERNAL, "");
+    node_usage.assign(g.name2id("d"), TrioGroup::MATERNAL, "");
+    let hap_names = ("mat", "pat");
+    let a = Vertex::forward(g.name2id("a"));
+    let d = Vertex::reverse(g.name2id("d"));
+    let haplo_paths = vec![(
+        Path::from_link(g.connector(a, d).unwrap()),
+        g.name2id("a"),
+        TrioGroup::MATERNAL,
+    )];
+    let records = path_records(&g, haplo_paths, &assignments, &node_usage, &hap_names);
+    assert_eq!(records.len(), 4);
+    //unused nodes are only filtered from the path listing
+    assert!(!records[0].unused && records[1..].iter().all(|r| r.unused));
+    for min_unused_len in [None, Some(5)] {
+        let mut buf = Vec::new();
+        let mut sink = UnusedFilterSink::new(
+            Box::new(
+                TsvSink::new(
+                    &mut buf,
+                    PathFormat {
+                        gaf: true,
+                        ..Default::default()
+                    },
+                    &hap_names,
+                    None,
+                    None,
+                )
+                .unwrap(),
+            ),
+            min_unused_len,
+        );
+        write_to_sinks(&g, &records, &mut [&mut sink]).unwrap();
+        drop(sink);
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            "name\tpath\tassignment\tcircular\nmat_from_a\t>a<d\tMAT\tfalse\n"
+        );
+    }
+
+    let prefix = std::env::temp_dir().join(format!("rukki_hap_fasta_{}", std::process::id()));
+    let prefix = prefix.to_str().unwrap();
+    let read = |suffix: &str| fs::read_to_string(format!("{prefix}.{suffix}.fasta")).unwrap();
+
+    write_hap_fasta(&g, &records, &assignments, prefix, false, &hap_names).unwrap();
+    assert_eq!(read("hap1"), ">mat_from_a\nAAAAAAC\n>hom_unused_c\nGGGG\n");
+    assert_eq!(read("hap2"), ">pat_unused_b\nCCCC\n>hom_unused_c\nGGGG\n");
+
+    write_hap_fasta(&g, &records, &assignments, prefix, true, &hap_names).unwrap();
+    assert_eq!(read("hap1"), ">mat_from_a\nAAAAAAC\n");
+    assert_eq!(read("hap2"), ">pat_unused_b\nCCCC\n");
+    assert_eq!(read("shared"), ">hom_unused_c\nGGGG\n");
+
+    for suffix in ["hap1", "hap2", "shared"] {
+        fs::remove_file(format!("{prefix}.{suffix}.fasta")).unwrap();
+    }
+}
+
+#[test]
+fn path_tag_summary() {
+    let s = "
+S a * LN:i:300 cl:Z:1
+S b * LN:i:100
+S c * LN:i:100 cl:Z:2
+L a + b + 10M
+L b + c + 10M
+";
+    let g = Graph::read(&s.replace(' ', "\t"));
+    let v = |name| Vertex::forward(g.name2id(name));
+    let mut p = Path::from_link(g.connector(v("a"), v("b")).unwrap());
+    p.append(g.connector(v("b"), v("c")).unwrap());
+    let (values, purity) = rukki::path_tag_summary(&g, &p, "cl").unwrap();
+    assert_eq!(values, vec!["1", "2"]);
+    assert_eq!(purity, 0.75);
+    assert!(rukki::path_tag_summary(&g, &Path::new(v("b")), "cl").is_none());
+    assert!(rukki::path_tag_summary(&g, &p, "xx").is_none());
+}
+
+#[test]
+fn path_confidence() {
+    let s = "
+S a * LN:i:10000
+S b * LN:i:10000
+S c * LN:i:100000
+S d * LN:i:100000
+S e * LN:i:100000
+S f * LN:i:10000
+S h * LN:i:10000
+L a + b + 0M
+L b + f + 0M
+L b + h + 0M
+";
+    let g = Graph::read(&s.replace(' ', "\t"));
+    let v = |name| Vertex::forward(g.name2id(name));
+    //long nodes c, d, e only have sparse mixed markers, f is paternal, h has no markers
+    let raw_cnts = ["a", "b", "c", "d", "e", "f"]
+        .iter()
+        .map(|&name| {
+            let (mat, pat) = match name {
+                "a" | "b" => (20, 0),
+                "f" => (0, 20),
+                _ => (1, 1),
+            };
+            (
+                g.name2id(name),
+                TrioInfo {
+                    node_name: String::from(name),
+                    mat,
+                    pat,
+                },
+            )
+        })
+        .collect();
+    let settings = ConfidenceSettings::default();
+    let confidence = |p: &Path, group| rukki::path_confidence(&g, p, group, &raw_cnts, &settings);
+
+    let clean = Path::from_link(g.connector(v("a"), v("b")).unwrap());
+    assert!(confidence(&clean, TrioGroup::MATERNAL).unwrap() > 0.99);
+
+    let mut jumpy = Path::new(v("c"));
+    for name in ["d", "e"] {
+        jumpy.append_general(GeneralizedLink::GAP(GapInfo {
+            start: jumpy.end(),
+            end: v(name),
+            gap_size: 5000,
+            info: String::from("jump"),
+        }));
+    }
+    assert!(confidence(&jumpy, TrioGroup::MATERNAL).unwrap() < 0.5);
+    //opposite haplotype (only monochromatic and gap-free terms contribute)
+    assert!((confidence(&clean, TrioGroup::PATERNAL).unwrap() - 0.5).abs() < 1e-6);
+    assert!(confidence(&clean, TrioGroup::HOMOZYGOUS).is_none());
+
+    //switch from maternal to paternal signal
+    //(choice at b is also not supported by maternal markers)
+    let switching = Path::parse_gaf(&g, ">a>b>f").unwrap();
+    let switching_conf = confidence(&switching, TrioGroup::MATERNAL).unwrap();
+    assert!((switching_conf - (40. / 60. + 1. + 0.5 + 0.5) / 4.).abs() < 1e-6);
+
+    //choice at b towards node without markers is an inferred jump
+    let inferred = Path::parse_gaf(&g, ">a>b>h").unwrap();
+    let inferred_conf = confidence(&inferred, TrioGroup::MATERNAL).unwrap();
+    assert!((inferred_conf - (1. + 1. + 1. + 0.5) / 4.).abs() < 1e-6);
+}
+
+#[test]
+fn confidence_weights_validation() {
+    use clap::Parser;
+    #[derive(Parser)]
+    struct Cli {
+        #[command(flatten)]
+        settings: TrioSettings,
+    }
+
+    let parse = |weights: &str| {
+        Cli::try_parse_from([
+            "rukki",
+            "-g",
+            "graph.gfa",
+            "-m",
+            "markers.tsv",
+            "--path-confidence",
+            "--confidence-weights",
+            weights,
+        ])
+    };
+    assert!(parse("1,1,1,1").is_ok());
+    assert!(parse("0,0,1,0").is_ok());
+    for weights in ["0,0,0,0", "1,-1,1,1", "1,1,1", "1,x,1,1", "1,1,1,inf"] {
+        assert!(
+            parse(weights).is_err(),
+            "weights {weights} should be rejected"
+        );
+    }
+}
+
+#[test]
+fn bubble_variants() {
+    let s = "
+S a AAAC
+S b G
+S c TT
+S d CCCA
+S e AAAC SN:Z:chr1 SO:i:100
+S f GG
+S h CCCA
+L a + b + 0M
+L a + c + 0M
+L b + d + 0M
+L c + d + 0M
+L e + f + 0M
+L f + h + 0M
+L e + h + 0M
+";
+    let g = Graph::custom_read(
+        &s.replace(' ', "\t"),
+        &ReadOptions {
+            keep_sequences: true,
+            ..Default::default()
+        },
+    );
+    let params = rukki::graph_algos::superbubble::SbSearchParams::unrestricted();
+    let variant = |name| {
+        let bubble = rukki::graph_algos::superbubble::find_superbubble(
+            &g,
+            Vertex::forward(g.name2id(name)),
+            &params,
+        )
+        .unwrap();
+        rukki::bubble_variant(&g, &bubble).unwrap()
+    };
+    assert_eq!(
+        variant("a"),
+        (
+            String::from("a+"),
+            4,
+            String::from("CG"),
+            String::from("CTT")
+        )
+    );
+    assert_eq!(
+        variant("e"),
+        (
+            String::from("chr1"),
+            104,
+            String::from("C"),
+            String::from("CGG")
+        )
+    );
+}
+
+#[test]
+fn multiple_sinks() {
+    use rukki::path_sink::*;
+    let s = "
+S a * LN:i:100
+S b * LN:i:100
+L a + b - 10M
+";
+    let g = Graph::read(&s.replace(' ', "\t"));
+    let hap_names = ("mat", "pat");
+    let records = vec![
+        PathRecord::new(
+            "mat_from_a",
+            Path::from_link(g.connector(Vertex::forward(0), Vertex::reverse(1)).unwrap()),
+            Some(TrioGroup::MATERNAL),
+        ),
+        PathRecord::new("na_unused_b", Path::new(Vertex::forward(1)), None),
+    ];
+    let (mut tsv_out, mut gaf_out) = (Vec::new(), Vec::new());
+    let mut tsv =
+        TsvSink::new(&mut tsv_out, PathFormat::default(), &hap_names, None, None).unwrap();
+    let mut gaf = TsvSink::new(
+        &mut gaf_out,
+        PathFormat {
+            gaf: true,
+            ..Default::default()
+        },
+        &hap_names,
+        None,
+        None,
+    )
+    .unwrap();
+    write_to_sinks(&g, &records, &mut [&mut tsv, &mut gaf]).unwrap();
+    assert_eq!(
+        String::from_utf8(tsv_out).unwrap(),
+        "name\tpath\tassignment\tcircular\nmat_from_a\ta+,b-\tMAT\tfalse\nna_unused_b\tb+\tNA\tfalse\n"
+    );
+    assert_eq!(
+        String::from_utf8(gaf_out).unwrap(),
+        "name\tpath\tassignment\tcircular\nmat_from_a\t>a<b\tMAT\tfalse\nna_unused_b\t>b\tNA\tfalse\n"
+    );
+}
+
+#[test]
+fn walk_lines() {
+    use rukki::path_sink::*;
+    let s = "
+S a * LN:i:100
+S b * LN:i:100
+S c * LN:i:50
+L a + b - 10M
+W sample1 1 chr1 0 190 >a<b
+W sample1 2 chr1 * * >b<a
+";
+    let g = Graph::read(&s.replace(' ', "\t"));
+    let paths = g.input_paths();
+    assert_eq!(paths.len(), 2);
+    assert_eq!(paths[0].0, "sample1#1#chr1");
+    assert_eq!(paths[0].1.print(&g), "a+,b-");
+    assert_eq!(paths[1].1.print(&g), "b+,a-");
+
+    let mut path = paths[0].1.clone();
+    path.append_general(GeneralizedLink::GAP(GapInfo {
+        start: path.end(),
+        end: Vertex::forward(2),
+        gap_size: 20,
+        info: String::from("test"),
+    }));
+    assert_eq!(
+        path.to_walk_line(&g, "s", 1, "p"),
+        "W\ts\t1\tp\t0\t190\t>a<b\nW\ts\t1\tp\t210\t260\t>c"
+    );
+
+    let records = vec![
+        PathRecord::new("mat_from_a", paths[0].1.clone(), Some(TrioGroup::MATERNAL)),
+        PathRecord::new("na_unused_c", Path::new(Vertex::forward(2)), None),
+    ];
+    let mut out = Vec::new();
+    let mut sink = WalkSink::new(&mut out, "HG002").unwrap();
+    write_to_sinks(&g, &records, &mut [&mut sink]).unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "H\tVN:Z:1.1\nW\tHG002\t1\tmat_from_a\t0\t190\t>a<b\nW\tHG002\t0\tna_unused_c\t0\t50\t>c\n"
+    );
+}
+
+#[test]
+fn reference_orientation() {
+    let s = "
+S a * LN:i:100 SN:Z:chr1 SO:i:0
+S b * LN:i:100
+S c * LN:i:100 SN:Z:chr1 SO:i:1000
+S d * LN:i:500 SN:Z:chr2 SO:i:0
+L c - b + 10M
+L b + a - 10M
+";
+    let g = Graph::read(&s.replace(' ', "\t"));
+    let v = |name| Vertex::forward(g.name2id(name));
+    let mut p = Path::from_link(g.connector(v("c").rc(), v("b")).unwrap());
+    p.append(g.connector(v("b"), v("a").rc()).unwrap());
+    assert_eq!(p.print(&g), "c-,b+,a-");
+    let p = rukki::reference_oriented(&g, p);
+    assert_eq!(p.print(&g), "a+,b-,c+");
+    assert_eq!(p.links()[0].overlap(), 10);
+    //already oriented
+    assert_eq!(rukki::reference_oriented(&g, p).print(&g), "a+,b-,c+");
+
+    //single node on the reference
+    assert_eq!(
+        rukki::reference_oriented(&g, Path::new(v("d").rc())).print(&g),
+        "d+"
+    );
+    //no reference information
+    assert_eq!(
+        rukki::reference_oriented(&g, Path::new(v("b").rc())).print(&g),
+        "b-"
+    );
+}
+
+#[test]
+fn circular_paths() {
+    use rukki::path_sink::*;
+    let s = "
+S a * LN:i:100
+S b * LN:i:100
+S c * LN:i:100
+L a + b - 10M
+L b - a + 10M
+L c + c + 5M
+";
+    let g = Graph::read(&s.replace(' ', "\t"));
+    let circ = Path::parse_gaf(&g, ">a<b").unwrap();
+    assert!(circ.is_circular(&g));
+    assert!(!Path::parse_gaf(&g, ">a").unwrap().is_circular(&g));
+    assert!(Path::parse_gaf(&g, "<c").unwrap().is_circular(&g));
+
+    let hap_names = ("mat", "pat");
+    let records = vec![PathRecord::new("mat_circ", circ, Some(TrioGroup::MATERNAL))];
+    let (mut gaf_out, mut repeat_out) = (Vec::new(), Vec::new());
+    let mut gaf = TsvSink::new(
+        &mut gaf_out,
+        PathFormat {
+            gaf: true,
+            ..Default::default()
+        },
+        &hap_names,
+        None,
+        None,
+    )
+    .unwrap();
+    let mut repeat = TsvSink::new(
+        &mut repeat_out,
+        PathFormat {
+            gaf: true,
+            repeat_circular_start: true,
+            ..Default::default()
+        },
+        &hap_names,
+        None,
+        None,
+    )
+    .unwrap();
+    write_to_sinks(&g, &records, &mut [&mut gaf, &mut repeat]).unwrap();
+    assert_eq!(
+        String::from_utf8(gaf_out).unwrap(),
+        "name\tpath\tassignment\tcircular\nmat_circ\t>a<b\tMAT\ttrue\n"
+    );
+    assert_eq!(
+        String::from_utf8(repeat_out).unwrap(),
+        "name\tpath\tassignment\tcircular\nmat_circ\t>a<b>a\tMAT\ttrue\n"
+    );
+}
+
+#[test]
+fn path_fasta() {
+    use rukki::path_sink::*;
+    let s = "
+S a ACGTAC
+S b TTGGT
+L a + b - 2M
+";
+    let g = Graph::custom_read(
+        &s.replace(' ', "\t"),
+        &ReadOptions {
+            keep_sequences: true,
+            ..Default::default()
+        },
+    );
+    let records = vec![
+        PathRecord::new(
+            "mat_from_a",
+            Path::parse_gaf(&g, ">a<b").unwrap(),
+            Some(TrioGroup::MATERNAL),
+        ),
+        PathRecord::new("na_unused_b", Path::parse_gaf(&g, ">b").unwrap(), None),
+    ];
+    let mut out = Vec::new();
+    let mut sink = FastaSink::new(&mut out);
+    write_to_sinks(&g, &records, &mut [&mut sink]).unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        ">mat_from_a\nACGTACCAA\n>na_unused_b\nTTGGT\n"
+    );
+
+    let g = Graph::read(&s.replace(' ', "\t"));
+    let mut sink = FastaSink::new(Vec::new());
+    assert!(write_to_sinks(&g, &records, &mut [&mut sink]).is_err());
+}
+
+#[test]
+fn scaffold_gaps() {
+    use rukki::path_sink::*;
+    let s = "
+S a * LN:i:100
+S b * LN:i:100
+S c * LN:i:100
+L a + b - 10M
+";
+    let g = Graph::read(&s.replace(' ', "\t"));
+    let p = Path::parse_gaf(&g, ">a<b[N3000N:tangle]>c").unwrap();
+    assert_eq!(
+        p.print_format_ext(
+            &g,
+            PathFormat {
+                scaffold_gaps: true,
+                ..Default::default()
+            }
+        ),
+        "a+,b-,[N3000],c+"
+    );
+    assert_eq!(
+        p.print_format_ext(
+            &g,
+            PathFormat {
+                gaf: true,
+                scaffold_gaps: true,
+                ..Default::default()
+            }
+        ),
+        ">a<b[N3000]>c"
+    );
+    assert_eq!(
+        p.print_format_ext(
+            &g,
+            PathFormat {
+                gaf: true,
+                ..Default::default()
+            }
+        ),
+        p.print_format(&g, true)
+    );
+
+    let hap_names = ("mat", "pat");
+    let records = vec![PathRecord::new("mat_scaf", p, Some(TrioGroup::MATERNAL))];
+    let mut out = Vec::new();
+    let mut sink = TsvSink::new(
+        &mut out,
+        PathFormat {
+            scaffold_gaps: true,
+            ..Default::default()
+        },
+        &hap_names,
+        None,
+        None,
+    )
+    .unwrap();
+    write_to_sinks(&g, &records, &mut [&mut sink]).unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "name\tpath\tassignment\tcircular\nmat_scaf\ta+,b-,[N3000],c+\tMAT\tfalse\n"
+    );
+}
+
+#[test]
+fn agp_output() {
+    use rukki::path_sink::*;
+    let s = "
+S a * LN:i:100
+S b * LN:i:50
+S c * LN:i:80
+L a + b - 10M
+";
+    let g = Graph::read(&s.replace(' ', "\t"));
+    let records = vec![
+        PathRecord::new(
+            "mat_scaf",
+            Path::parse_gaf(&g, ">a<b[N300N:tangle]<c").unwrap(),
+            Some(TrioGroup::MATERNAL),
+        ),
+        PathRecord::new("na_unused_c", Path::parse_gaf(&g, ">c").unwrap(), None),
+    ];
+    let mut out = Vec::new();
+    let mut sink = AgpSink::new(&mut out).unwrap();
+    write_to_sinks(&g, &records, &mut [&mut sink]).unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "##agp-version 2.1
+mat_scaf\t1\t100\t1\tW\ta\t1\t100\t+
+mat_scaf\t101\t140\t2\tW\tb\t1\t40\t-
+mat_scaf\t141\t440\t3\tN\t300\tscaffold\tyes\tunspecified
+mat_scaf\t441\t520\t4\tW\tc\t1\t80\t-
+na_unused_c\t1\t80\t1\tW\tc\t1\t80\t+
+"
+    );
+}
+
+#[test]
+fn bed_output() {
+    use rukki::path_sink::*;
+    let s = "
+S a * LN:i:100
+S b * LN:i:50
+S c * LN:i:80
+L a + b - 10M
+";
+    let g = Graph::read(&s.replace(' ', "\t"));
+    let path = Path::parse_gaf(&g, ">a<b[N300N:tangle]<c").unwrap();
+    let records = vec![
+        PathRecord::new("mat_scaf", path.clone(), Some(TrioGroup::MATERNAL)),
+        PathRecord::new("na_unused_c", Path::parse_gaf(&g, ">c").unwrap(), None),
+    ];
+    let mut out = Vec::new();
+    let mut sink = BedSink::new(&mut out);
+    write_to_sinks(&g, &records, &mut [&mut sink]).unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "mat_scaf\t0\t100\ta\t0\t+
+mat_scaf\t90\t140\tb\t0\t-
+mat_scaf\t440\t520\tc\t0\t-
+na_unused_c\t0\t80\tc\t0\t+
+"
+    );
+    assert_eq!(path.total_length(&g), 520);
+}
+
+#[test]
+fn json_node_annotation() {
+    let s = "
+S a * LN:i:100 ll:f:10
+S b * LN:i:200
+S c * LN:i:300
+L a + b + 20M
+";
+    let g = Graph::read(&s.replace(' ', "\t"));
+    let mut assignments = AssignmentStorage::new();
+    assignments.assign(g.name2id("a"), TrioGroup::MATERNAL, "marker");
+    assignments.assign(g.name2id("c"), TrioGroup::HOMOZYGOUS, "homozygous");
+    let raw_cnts = std::collections::HashMap::from([(
+        g.name2id("a"),
+        TrioInfo {
+            node_name: String::from("a"),
+            mat: 20,
+            pat: 1,
+        },
+    )]);
+    let mut out = Vec::new();
+    rukki::write_node_annotation_json(&g, &assignments, &raw_cnts, &mut out).unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&out).unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!([
+            {"name": "a", "length": 100, "unique_length": 80, "effective_coverage": 12.5,
+                "mat": 20, "pat": 1, "assignment": "MATERNAL", "color": "#FF8888"},
+            {"name": "c", "length": 300, "unique_length": 300, "effective_coverage": null,
+                "mat": null, "pat": null, "assignment": "HOMOZYGOUS", "color": "#7900D6"},
+        ])
+    );
+}
+
+#[test]
+fn stdout_or_file_output() {
+    let file_name = std::env::temp_dir().join(format!("rukki_output_{}", std::process::id()));
+    {
+        let mut output = create_output(&file_name).unwrap();
+        writeln!(output, "test").unwrap();
+    }
+    assert_eq!(fs::read_to_string(&file_name).unwrap(), "test\n");
+    fs::remove_file(&file_name).unwrap();
+
+    //"-" doesn't create a file
+    let mut output = create_output("-").unwrap();
+    output.flush().unwrap();
+    assert!(!std::path::Path::new("-").exists());
+}
+
+//marker counts consistent with the node assignments of test1 graph
+fn write_test1_markers(markers_fn: &std::path::Path) {
+    let markers = fs::read_to_string("tests/test_graphs/test1.ann.csv")
+        .unwrap()
+        .lines()
+        .skip(1)
+        .filter_map(|l| {
+            let split: Vec<&str> = l.split('\t').collect();
+            match split[1] {
+                "MATERNAL" => Some(format!("{}\t100\t0\n", split[0])),
+                "PATERNAL" => Some(format!("{}\t0\t100\n", split[0])),
+                _ => None,
+            }
+        })
+        .collect::<String>();
+    fs::write(markers_fn, markers).unwrap();
+}
+
+#[test]
+fn deterministic_trio_output() {
+    use clap::Parser;
+    #[derive(Parser)]
+    struct Cli {
+        #[command(flatten)]
+        settings: TrioSettings,
+    }
+
+    let tmp = std::env::temp_dir().join(format!("rukki_determinism_{}", std::process::id()));
+    fs::create_dir_all(&tmp).unwrap();
+    let markers_fn = tmp.join("markers.tsv");
+    write_test1_markers(&markers_fn);
+
+    let run = |i: usize| {
+        let paths_fn = tmp.join(format!("paths{i}.tsv"));
+        let assign_fn = tmp.join(format!("assign{i}.tsv"));
+        let cli = Cli::parse_from([
+            "rukki",
+            "-g",
+            "tests/test_graphs/test1.gfa",
+            "-m",
+            markers_fn.to_str().unwrap(),
+            "-p",
+            paths_fn.to_str().unwrap(),
+            "--final-assign",
+            assign_fn.to_str().unwrap(),
+            "--try-fill-bubbles",
+        ]);
+        run_trio_analysis(&cli.settings).unwrap();
+        (fs::read(paths_fn).unwrap(), fs::read(assign_fn).unwrap())
+    };
+    let first = run(0);
+    assert!(!first.0.is_empty());
+    assert_eq!(first, run(1));
+    fs::remove_dir_all(&tmp).unwrap();
+}
+
+#[test]
+fn group_split_paths() {
+    use rukki::path_sink::*;
+    let s = "
+S a * LN:i:100
+S b * LN:i:100
+S c * LN:i:100
+S d * LN:i:100
+S e * LN:i:100
+S f * LN:i:100
+L a + d - 10M
+";
+    let g = Graph::read(&s.replace(' ', "\t"));
+    let mut assignments = AssignmentStorage::new();
+    assignments.assign(g.name2id("a"), TrioGroup::MATERNAL, "");
+    assignments.assign(g.name2id("b"), TrioGroup::PATERNAL, "");
+    assignments.assign(g.name2id("c"), TrioGroup::HOMOZYGOUS, "");
+    assignments.assign(g.name2id("d"), TrioGroup::MATERNAL, "");
+    assignments.assign(g.name2id("f"), TrioGroup::HOMOZYGOUS, "");
+    let mut node_usage = AssignmentStorage::new();
+    node_usage.assign(g.name2id("a"), TrioGroup::MATERNAL, "");
+    node_usage.assign(g.name2id("d"), TrioGroup::MATERNAL, "");
+    node_usage.assign(g.name2id("f"), TrioGroup::HOMOZYGOUS, "");
+    let hap_names = ("mat", "pat");
+    //single-node haplo-paths through homozygous nodes are not renamed
+    let haplo_paths = vec![
+        (
+            Path::parse_gaf(&g, ">a<d").unwrap(),
+            g.name2id("a"),
+            TrioGroup::MATERNAL,
+        ),
+        (
+            Path::parse_gaf(&g, ">f").unwrap(),
+            g.name2id("f"),
+            TrioGroup::PATERNAL,
+        ),
+    ];
+    let records = path_records(&g, haplo_paths, &assignments, &node_usage, &hap_names);
+
+    let prefix = std::env::temp_dir().join(format!("rukki_group_paths_{}", std::process::id()));
+    let prefix = prefix.to_str().unwrap();
+    let mut sink = GroupSplitSink::create(
+        prefix,
+        |output_fn| -> Result<Box<dyn PathSink>, std::io::Error> {
+            Ok(Box::new(TsvSink::new(
+                fs::File::create(output_fn)?,
+                PathFormat {
+                    gaf: true,
+                    ..Default::default()
+                },
+                &hap_names,
+                None,
+                None,
+            )?))
+        },
+        &assignments,
+        &hap_names,
+    )
+    .unwrap();
+    write_to_sinks(&g, &records, &mut [&mut sink]).unwrap();
+    drop(sink);
+
+    let read = |suffix: &str| fs::read_to_string(format!("{prefix}.{suffix}.paths")).unwrap();
+    let header = "name\tpath\tassignment\tcircular\n";
+    assert_eq!(
+        read("hap1"),
+        format!("{header}mat_from_a\t>a<d\tMAT\tfalse\n")
+    );
+    assert_eq!(
+        read("hap2"),
+        format!("{header}pat_from_f\t>f\tPAT\tfalse\npat_unused_b\t>b\tPAT\tfalse\n")
+    );
+    assert_eq!(
+        read("shared"),
+        format!("{header}hom_unused_c\t>c\tHOM\tfalse\n")
+    );
+    assert_eq!(
+        read("unassigned"),
+        format!("{header}na_unused_e\t>e\tNA\tfalse\n")
+    );
+
+    for suffix in ["hap1", "hap2", "shared", "unassigned"] {
+        fs::remove_file(format!("{prefix}.{suffix}.paths")).unwrap();
+    }
+}
+
+#[test]
+fn unused_nodes_orientation() {
+    let s = "
+S a * LN:i:100
+S b * LN:i:100
+S c * LN:i:100
+L a + b - 10M
+L b - c + 10M
+L b + b - 10M
+";
+    let g = Graph::read(&s.replace(' ', "\t"));
+    //node can't be traversed in both orientations within a path
+    assert_eq!(
+        Path::parse_gaf(&g, ">b<b"),
+        Err(PathParseError::RepeatedNode(String::from("b")))
+    );
+
+    let mut assignments = AssignmentStorage::new();
+    for name in ["a", "b", "c"] {
+        assignments.assign(g.name2id(name), TrioGroup::MATERNAL, "");
+    }
+    //b is only traversed in reverse orientation
+    let path = Path::parse_gaf(&g, ">a<b>c").unwrap();
+    let mut node_usage = AssignmentStorage::new();
+    node_usage.update_all(
+        path.vertices().iter().map(|v| v.node_id),
+        TrioGroup::MATERNAL,
+    );
+    let records = path_records(
+        &g,
+        vec![(path, g.name2id("a"), TrioGroup::MATERNAL)],
+        &assignments,
+        &node_usage,
+        &("mat", "pat"),
+    );
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].name, "mat_from_a");
+}
+
+#[test]
+fn path_records_node_visitor() {
+    let s = "
+S a * LN:i:100
+S b * LN:i:200
+S c * LN:i:300
+L a + b + 10M
+";
+    let g = Graph::read(&s.replace(' ', "\t"));
+    let mut assignments = AssignmentStorage::new();
+    assignments.assign(g.name2id("a"), TrioGroup::MATERNAL, "");
+    assignments.assign(g.name2id("b"), TrioGroup::MATERNAL, "");
+    assignments.assign(g.name2id("c"), TrioGroup::PATERNAL, "");
+    let node_usage = AssignmentStorage::new();
+    let hap_names = ("mat", "pat");
+    let a = Vertex::forward(g.name2id("a"));
+    let b = Vertex::forward(g.name2id("b"));
+    let haplo_paths = vec![(
+        Path::from_link(g.connector(a, b).unwrap()),
+        g.name2id("a"),
+        TrioGroup::MATERNAL,
+    )];
+
+    let mut visited = Vec::new();
+    let records = path_records_visiting(
+        &g,
+        haplo_paths.clone(),
+        &assignments,
+        &node_usage,
+        &hap_names,
+        &mut |node_id, n| {
+            assert_eq!(g.name(node_id), n.name);
+            visited.push(node_id);
+            Ok(())
+        },
+    )
+    .unwrap();
+    assert_eq!(visited, (0..g.node_cnt()).collect::<Vec<_>>());
+    assert_eq!(
+        records,
+        path_records(
+            &g,
+            haplo_paths.clone(),
+            &assignments,
+            &node_usage,
+            &hap_names,
+        )
+    );
+
+    let err = path_records_visiting(
+        &g,
+        haplo_paths,
+        &assignments,
+        &node_usage,
+        &hap_names,
+        &mut |_, _| Err(std::io::Error::other("failed")),
+    );
+    assert!(err.is_err());
+}
+
+#[test]
+fn trio_plan() {
+    use clap::Parser;
+    #[derive(Parser)]
+    struct Cli {
+        #[command(flatten)]
+        settings: TrioSettings,
+    }
+
+    //x, y and z form a small tangle between a and b
+    let s = "
+S a * LN:i:700000
+S b * LN:i:600000
+S c * LN:i:100000
+S d * LN:i:800000
+S x * LN:i:1000
+S y * LN:i:1000
+S z * LN:i:1000
+L a + x + 0M
+L x + y + 0M
+L y + x + 0M
+L y + z + 0M
+L z + b + 0M
+";
+    let gfa = s.replace(' ', "\t");
+    let g = Graph::read(&gfa);
+    let mut assignments = AssignmentStorage::new();
+    assignments.assign(g.name2id("a"), TrioGroup::MATERNAL, "");
+    assignments.assign(g.name2id("b"), TrioGroup::PATERNAL, "");
+    assignments.assign(g.name2id("c"), TrioGroup::PATERNAL, "");
+    assignments.assign(g.name2id("d"), TrioGroup::HOMOZYGOUS, "");
+    let plan = TrioPlan::compute(&g, &assignments, &trio_walk::HaploSearchSettings::default());
+    assert_eq!(plan.maternal_seeds, 1);
+    assert_eq!(plan.paternal_seeds, 1);
+    assert_eq!(plan.seed_length, 1_300_000);
+    assert_eq!(plan.scc_cnt, 1);
+    assert_eq!(plan.scc_node_cnt, 2);
+    assert_eq!(plan.skippable_scc_cnt, 1);
+
+    //no outputs are produced in plan mode
+    let tmp = std::env::temp_dir().join(format!("rukki_plan_{}", std::process::id()));
+    fs::create_dir_all(&tmp).unwrap();
+    let graph_fn = tmp.join("graph.gfa");
+    let markers_fn = tmp.join("markers.tsv");
+    let paths_fn = tmp.join("paths.tsv");
+    fs::write(&graph_fn, &gfa).unwrap();
+    fs::write(&markers_fn, "a\t100\t0\nb\t0\t100\n").unwrap();
+    let cli = Cli::parse_from([
+        "rukki",
+        "-g",
+        graph_fn.to_str().unwrap(),
+        "-m",
+        markers_fn.to_str().unwrap(),
+        "-p",
+        paths_fn.to_str().unwrap(),
+        "--plan",
+    ]);
+    run_trio_analysis(&cli.settings).unwrap();
+    assert!(!paths_fn.exists());
+    fs::remove_dir_all(&tmp).unwrap();
+}
+
+#[test]
+fn overridden_assignment_output() {
+    use clap::Parser;
+    #[derive(Parser)]
+    struct Cli {
+        #[command(flatten)]
+        settings: TrioSettings,
+    }
+
+    let tmp = std::env::temp_dir().join(format!("rukki_overrides_output_{}", std::process::id()));
+    fs::create_dir_all(&tmp).unwrap();
+    let markers_fn = tmp.join("markers.tsv");
+    let overrides_fn = tmp.join("overrides.tsv");
+    let assign_fn = tmp.join("assign.tsv");
+    let markers = fs::read_to_string("tests/test_graphs/test1.ann.csv")
+        .unwrap()
+        .lines()
+        .skip(1)
+        .filter_map(|l| {
+            let split: Vec<&str> = l.split('\t').collect();
+            match split[1] {
+                "MATERNAL" => Some(format!("{}\t100\t0\n", split[0])),
+                "PATERNAL" => Some(format!("{}\t0\t100\n", split[0])),
+                _ => None,
+            }
+        })
+        .collect::<String>();
+    fs::write(&markers_fn, markers).unwrap();
+    fs::write(&overrides_fn, "utig4-1239\tISSUE\n").unwrap();
+
+    let cli = Cli::parse_from([
+        "rukki",
+        "-g",
+        "tests/test_graphs/test1.gfa",
+        "-m",
+        markers_fn.to_str().unwrap(),
+        "--assignment-overrides",
+        overrides_fn.to_str().unwrap(),
+        "--final-assign",
+        assign_fn.to_str().unwrap(),
+        "--try-fill-bubbles",
+    ]);
+    run_trio_analysis(&cli.settings).unwrap();
+    let annotation = fs::read_to_string(&assign_fn).unwrap();
+    let line = annotation
+        .lines()
+        .find(|l| l.starts_with("utig4-1239\t"))
+        .unwrap();
+    let split: Vec<&str> = line.split('\t').collect();
+    assert_eq!(split[1], "ISSUE");
+    assert_eq!(split[3], trio::OVERRIDE_INFO);
+    fs::remove_dir_all(&tmp).unwrap();
+}
+
+#[test]
+fn path_edits_with_skip_unused() {
+    use clap::Parser;
+    #[derive(Parser)]
+    struct Cli {
+        #[command(flatten)]
+        settings: TrioSettings,
+    }
+
+    let tmp = std::env::temp_dir().join(format!("rukki_edits_unused_{}", std::process::id()));
+    fs::create_dir_all(&tmp).unwrap();
+    let markers_fn = tmp.join("markers.tsv");
+    write_test1_markers(&markers_fn);
+
+    let run = |edits: &str| {
+        let edits_fn = tmp.join("edits.txt");
+        let paths_fn = tmp.join("paths.tsv");
+        fs::write(&edits_fn, edits).unwrap();
+        let cli = Cli::parse_from([
+            "rukki",
+            "-g",
+            "tests/test_graphs/test1.gfa",
+            "-m",
+            markers_fn.to_str().unwrap(),
+            "-p",
+            paths_fn.to_str().unwrap(),
+            "--path-edits",
+            edits_fn.to_str().unwrap(),
+            "--try-fill-bubbles",
+            "--skip-unused",
+        ]);
+        run_trio_analysis(&cli.settings).unwrap();
+        fs::read_to_string(paths_fn)
+            .unwrap()
+            .lines()
+            .skip(1)
+            .map(|l| String::from(l.split('\t').next().unwrap()))
+            .collect::<Vec<_>>()
+    };
+    //pieces of the broken haplo-path are kept
+    assert_eq!(
+        run("break mat_from_utig4-1828 utig4-1826"),
+        vec![
+            "mat_from_utig4-1239",
+            "pat_from_utig4-1238",
+            "pat_from_utig4-1827",
+            "mat_from_utig4-1828_1",
+            "mat_from_utig4-1828_2",
+        ]
+    );
+    //all unused nodes are still filtered after a join
+    assert_eq!(
+        run("join pat_from_utig4-1238 pat_from_utig4-1827 100"),
+        vec![
+            "mat_from_utig4-1239",
+            "pat_from_utig4-1238",
+            "mat_from_utig4-1828",
+        ]
+    );
+    fs::remove_dir_all(&tmp).unwrap();
+}
@@ -0,0 +1,73 @@
+use rukki::*;
+
+#[test]
+fn multi_node_alt_path() {
+    let s = "
+S a * LN:i:600000
+S p * LN:i:1000
+S x1 * LN:i:100
+S x2 * LN:i:100
+S c * LN:i:600000
+L a + p + 0M
+L p + c + 0M
+L a + x1 + 0M
+L x1 + x2 - 0M
+L x2 - c + 0M
+";
+    let g = Graph::read(&s.replace(' ', "\t"));
+    let blocks = pseudo_hap::pseudo_hap_decompose(&g, 500_000);
+    assert_eq!(blocks.len(), 1);
+    assert_eq!(blocks[0].instance_path().print(&g), "a+,p+,c+");
+    assert_eq!(blocks[0].known_alt_nodes().len(), 2);
+    let alt_paths = blocks[0].alt_paths(&g);
+    assert_eq!(alt_paths.len(), 1);
+    assert_eq!(alt_paths[0].print(&g), "x1+,x2-");
+}
+
+#[test]
+fn bubble_alleles() {
+    let s = "
+S a * LN:i:600000
+S p * LN:i:1000
+S x * LN:i:100
+S c * LN:i:300000
+S q * LN:i:2000
+S y * LN:i:200
+S z * LN:i:300
+S d * LN:i:300000
+L a + p + 0M
+L a + x + 0M
+L p + c + 0M
+L x + c + 0M
+L c + q + 0M
+L c + y + 0M
+L c + z + 0M
+L q + d + 0M
+L y + d + 0M
+L z + d + 0M
+";
+    let g = Graph::read(&s.replace(' ', "\t"));
+    let blocks = pseudo_hap::pseudo_hap_decompose(&g, 500_000);
+    assert_eq!(blocks.len(), 1);
+    assert_eq!(blocks[0].instance_path().print(&g), "a+,p+,c+,q+,d+");
+    let alleles = blocks[0]
+        .bubble_alleles(&g, 10)
+        .into_iter()
+        .map(|(primary, alts)| {
+            (
+                primary.print(&g),
+                alts.iter().map(|p| p.print(&g)).collect::<Vec<_>>(),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        alleles,
+        vec![
+            (String::from("a+,p+,c+"), vec![String::from("a+,x+,c+")]),
+            (
+                String::from("c+,q+,d+"),
+                vec![String::from("c+,y+,d+"), String::from("c+,z+,d+")]
+            ),
+        ]
+    );
+}
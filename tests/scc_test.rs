@@ -1,3 +1,142 @@
+use rukki::graph_algos::scc;
+use rukki::*;
+
+fn without_links(g: &Graph, links: &[Link]) -> Graph {
+    let gfa = g
+        .as_gfa()
+        .lines()
+        .filter(|line| {
+            !links
+                .iter()
+                .any(|&l| g.link_gfa_line(l) == *line || g.link_gfa_line(l.rc()) == *line)
+        })
+        .map(|line| format!("{line}\n"))
+        .collect::<String>();
+    Graph::read(&gfa)
+}
+
+#[test]
+fn feedback_links_acyclic() {
+    let s = "
+S a * LN:i:100
+S b * LN:i:100
+S c * LN:i:100
+S d * LN:i:100
+S e * LN:i:100
+L a + b + 10M
+L b + c + 10M
+L c + a + 10M
+L c + d + 10M
+L d + b + 10M
+L d + e + 10M
+L e + e + 10M
+";
+    let g = Graph::read(&s.replace(' ', "\t"));
+    assert!(!scc::strongly_connected(&g).is_empty());
+    let feedback = scc::feedback_link_set(&g);
+    assert!(!feedback.is_empty());
+    assert!(feedback.len() < g.link_cnt());
+    //self-loop is always reported
+    assert!(feedback.iter().any(|l| l.start == l.end));
+    assert_eq!(
+        g.link_cnt() - feedback.len(),
+        without_links(&g, &feedback).link_cnt()
+    );
+    assert!(scc::strongly_connected(&without_links(&g, &feedback)).is_empty());
+}
+
+#[test]
+fn feedback_links_dag() {
+    let s = "
+S a * LN:i:100
+S b * LN:i:100
+L a + b + 10M
+L a - b + 10M
+";
+    let g = Graph::read(&s.replace(' ', "\t"));
+    assert!(scc::feedback_link_set(&g).is_empty());
+}
+
+#[test]
+fn condense_cycles() {
+    let s = "
+S a * LN:i:100
+S b * LN:i:200
+S c * LN:i:300
+S d * LN:i:400
+S e * LN:i:500
+L a + b + 10M
+L b + c + 10M
+L c + b + 10M
+L c + d + 10M
+L d + e + 10M
+L e + e + 10M
+";
+    let g = Graph::read(&s.replace(' ', "\t"));
+    let (cond, mapping) = scc::condense(&g);
+    let id = |name| mapping[g.name2id(name)];
+    assert_eq!(cond.node_cnt(), 4);
+    assert_eq!(id("b"), id("c"));
+    assert_ne!(id("a"), id("b"));
+    assert_eq!(cond.node(id("b")).length, 500);
+    assert_eq!(cond.node(id("e")).length, 500);
+    assert_eq!(cond.link_cnt(), 3);
+    assert!(scc::strongly_connected(&cond).is_empty());
+}
+
+#[test]
+fn nontrivial_components() {
+    let s = "
+S a * LN:i:100
+S b * LN:i:200
+S c * LN:i:300
+S d * LN:i:400
+S e * LN:i:500
+L a + b + 10M
+L b + c + 10M
+L c + b + 10M
+L c + d + 10M
+L d + e + 10M
+L e + e + 10M
+";
+    let g = Graph::read(&s.replace(' ', "\t"));
+    let sccs = scc::nontrivial_sccs(&g);
+    assert_eq!(
+        sccs,
+        vec![vec![g.name2id("b"), g.name2id("c")], vec![g.name2id("e")]]
+    );
+    assert_eq!(
+        scc::format_sccs(&g, &sccs),
+        "scc_0\t2\t500\tb,c\nscc_1\t1\t500\te\n"
+    );
+}
+
+#[test]
+fn scc_cache_invalidation() {
+    let s = "
+S a * LN:i:100
+S b * LN:i:200
+S c * LN:i:300
+L a + b + 10M
+L b + c + 10M
+";
+    let mut g = Graph::read(&s.replace(' ', "\t"));
+    assert!(scc::nontrivial_sccs(&g).is_empty());
+    assert_eq!(scc::strongly_connected(&g), scc::strongly_connected(&g));
+    let back = Link {
+        start: Vertex::forward(g.name2id("c")),
+        end: Vertex::forward(g.name2id("a")),
+        overlap: 10,
+    };
+    g.add_link(back);
+    assert_eq!(
+        scc::nontrivial_sccs(&g),
+        vec![vec![g.name2id("a"), g.name2id("b"), g.name2id("c")]]
+    );
+    g.remove_link(&back);
+    assert!(scc::nontrivial_sccs(&g).is_empty());
+}
+
 //use rukki::*;
 //use rukki::graph_algos::scc;
 //use std::fs;
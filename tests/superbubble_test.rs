@@ -267,6 +267,53 @@ L f + g + 50M
     assert_eq!(superbubble::length_range(&chain, &g), (300, 300));
 }
 
+#[test]
+fn chain_through() {
+    let s = "
+S a * LN:i:100
+S b * LN:i:100
+S c * LN:i:100
+S d * LN:i:100
+S e * LN:i:100
+S f * LN:i:100
+S g * LN:i:100
+S h * LN:i:100
+S i * LN:i:100
+S j * LN:i:100
+S k * LN:i:100
+L a + b + 50M
+L a + c + 50M
+L b + d + 50M
+L c + d + 50M
+L d + e + 50M
+L d + f + 50M
+L e + g + 50M
+L f + g + 50M
+L g + h + 50M
+L g + i + 50M
+L h + j + 50M
+L i + j + 50M
+L j + k + 50M
+";
+    let g = Graph::read(&s.replace(' ', "\t"));
+    let params = superbubble::SbSearchParams::unrestricted();
+    for name in ["e", "a", "d", "i", "j"] {
+        let chain = superbubble::find_chain_through(&g, g.name2id(name), &params).unwrap();
+        assert_eq!(chain.len(), 3);
+        let boundary = chain
+            .iter()
+            .map(|b| b.start_vertex())
+            .chain(std::iter::once(chain.last().unwrap().end_vertex()))
+            .map(|v| g.v_str(v))
+            .collect_vec();
+        assert!(
+            boundary == ["a+", "d+", "g+", "j+"] || boundary == ["j-", "g-", "d-", "a-"],
+            "{boundary:?}"
+        );
+    }
+    assert!(superbubble::find_chain_through(&g, g.name2id("k"), &params).is_none());
+}
+
 #[test]
 fn simple_chain_loop() {
     let s = "
@@ -310,3 +357,370 @@ L f + a + 50M
     assert_eq!(chain[1].end_vertex(), Vertex::forward(g.name2id("d")));
     assert_eq!(superbubble::length_range(&chain, &g), (200, 200));
 }
+
+#[test]
+fn palindromes() {
+    let s = "
+S p ACGT
+S b AAAA
+S c CCCC
+S d GGGG
+L p + b + 0M
+L p + c + 0M
+L b + d + 0M
+L c + d + 0M
+L d + d - 0M
+";
+    let g = Graph::custom_read(
+        &s.replace(' ', "\t"),
+        &ReadOptions {
+            keep_sequences: true,
+            ..Default::default()
+        },
+    );
+    assert_eq!(g.palindromic_nodes(), vec![g.name2id("p")]);
+    let palindromic_links = g.palindromic_links();
+    assert_eq!(palindromic_links.len(), 1);
+    assert_eq!(g.l_str(palindromic_links[0]), "d+->d-");
+    let palindromic = (0..g.node_cnt())
+        .filter(|&node_id| g.is_palindromic(node_id))
+        .map(|node_id| g.name(node_id))
+        .collect_vec();
+    assert_eq!(palindromic, vec!["p", "d"]);
+
+    //neither p (palindromic sequence) nor d (link to its reverse-complement) can start a bubble
+    let params = superbubble::SbSearchParams::unrestricted();
+    assert!(superbubble::find_superbubble(&g, Vertex::forward(g.name2id("p")), &params).is_none());
+    assert!(superbubble::find_superbubble(&g, Vertex::reverse(g.name2id("d")), &params).is_none());
+    assert!(superbubble::find_all_outer(&g, &params).is_empty());
+    assert!(superbubble::find_maximal_chains(&g, &params).is_empty());
+}
+
+#[test]
+fn palindromes_default_read() {
+    //sequences are not kept by default, palindromic link still prevents d from starting a bubble
+    let s = "
+S p ACGT
+S b AAAA
+S c CCCC
+S d GGGG
+L p + b + 0M
+L p + c + 0M
+L b + d + 0M
+L c + d + 0M
+";
+    let params = superbubble::SbSearchParams::unrestricted();
+    let g = Graph::read(&s.replace(' ', "\t"));
+    assert!(!g.is_palindromic(g.name2id("p")));
+    let bubbles = superbubble::find_all_outer(&g, &params);
+    assert_eq!(bubbles.len(), 1);
+    assert_eq!(g.v_str(bubbles[0].start_vertex()), "p+");
+
+    let g = Graph::read(&(s.to_owned() + "L d + d - 0M\n").replace(' ', "\t"));
+    assert!(g.palindromic_nodes().is_empty());
+    assert!(g.is_palindromic(g.name2id("d")));
+    assert!(superbubble::find_superbubble(&g, Vertex::reverse(g.name2id("d")), &params).is_none());
+    let bubbles = superbubble::find_all_outer(&g, &params);
+    assert_eq!(bubbles.len(), 1);
+    assert_eq!(g.v_str(bubbles[0].start_vertex()), "p+");
+}
+
+#[test]
+fn search_params_builder() {
+    let s = "
+S a * LN:i:100
+S b * LN:i:100
+S c * LN:i:300
+S d * LN:i:100
+L a + b + 0M
+L a + c + 0M
+L b + d + 0M
+L c + d + 0M
+";
+    let g = Graph::read(&s.replace(' ', "\t"));
+    assert_eq!(
+        superbubble::SbSearchParams::builder().build(),
+        superbubble::SbSearchParams::unrestricted()
+    );
+    let params = superbubble::SbSearchParams::builder()
+        .max_length(1000)
+        .max_diff(150)
+        .max_count(10)
+        .build();
+    assert_eq!(
+        (params.max_length, params.max_diff, params.max_count),
+        (1000, 150, 10)
+    );
+    let find = |params: superbubble::SbSearchParams| {
+        superbubble::find_superbubble(&g, Vertex::forward(g.name2id("a")), &params)
+    };
+    assert!(find(superbubble::SbSearchParams::unrestricted()).is_some());
+    assert!(find(superbubble::SbSearchParams::builder().max_diff(200).build()).is_some());
+    assert!(find(params).is_none());
+    assert!(find(superbubble::SbSearchParams::builder().max_count(2).build()).is_none());
+}
+
+#[test]
+fn heaviest_path() {
+    //b-d is longer, c has the highest coverage
+    let s = "
+S a * LN:i:100 ll:f:10
+S b * LN:i:200 ll:f:10
+S c * LN:i:100 ll:f:50
+S d * LN:i:300 ll:f:10
+S e * LN:i:100
+S f * LN:i:100 ll:f:10
+L a + b + 0M
+L a + c + 0M
+L b + d + 0M
+L c + d + 0M
+L b + e + 0M
+L d + f + 0M
+L e + f + 0M
+";
+    let g = Graph::read(&s.replace(' ', "\t"));
+    let bubble = superbubble::find_superbubble(
+        &g,
+        Vertex::forward(g.name2id("a")),
+        &superbubble::SbSearchParams::unrestricted(),
+    )
+    .unwrap();
+    assert_eq!(g.name(bubble.end_vertex().node_id), "f");
+    assert_eq!(bubble.longest_path(&g).print(&g), "a+,b+,d+,f+");
+    assert_eq!(bubble.shortest_path(&g).print(&g), "a+,b+,e+,f+");
+    assert_eq!(bubble.heaviest_path(&g).print(&g), "a+,c+,d+,f+");
+}
+
+#[test]
+fn start_self_loop() {
+    let s = "
+S a * LN:i:100
+S b * LN:i:100
+S c * LN:i:100
+S d * LN:i:100
+L a + a + 10M
+L a + b + 0M
+L a + c + 0M
+L b + d + 0M
+L c + d + 0M
+";
+    let g = Graph::read(&s.replace(' ', "\t"));
+    let v = |name| Vertex::forward(g.name2id(name));
+    let params = superbubble::SbSearchParams::unrestricted();
+    let bubble = superbubble::find_superbubble(&g, v("a"), &params).unwrap();
+    assert_eq!(bubble.end_vertex(), v("d"));
+    assert_eq!(bubble.length_range(&g), (300, 300));
+    assert_eq!(bubble.vertices().count(), 4);
+    assert_eq!(bubble.heaviest_path(&g).len(), 3);
+
+    //same bubble is found from the other side (self-loop on the end vertex)
+    let rc_bubble = superbubble::find_superbubble(&g, v("d").rc(), &params).unwrap();
+    assert_eq!(rc_bubble.end_vertex(), v("a").rc());
+    assert_eq!(rc_bubble.length_range(&g), (300, 300));
+    assert_eq!(
+        rc_bubble.vertices().map(|w| w.rc()).sorted().collect_vec(),
+        bubble.vertices().copied().sorted().collect_vec()
+    );
+    assert_eq!(
+        rc_bubble.heaviest_path(&g).reverse_complement().len(),
+        bubble.heaviest_path(&g).len()
+    );
+    assert_eq!(
+        superbubble::find_maximal_chain(&g, v("a"), &params).len(),
+        superbubble::find_maximal_chain(&g, v("d").rc(), &params).len()
+    );
+    assert_eq!(superbubble::find_all_outer(&g, &params).len(), 1);
+
+    //self-loop on an inner vertex is rejected
+    let g = Graph::read(&format!("{s}L b + b + 10M\n").replace(' ', "\t"));
+    assert!(superbubble::find_superbubble(&g, v("a"), &params).is_none());
+    assert!(superbubble::find_superbubble(&g, v("d").rc(), &params).is_none());
+
+    //loop through the start vertex (not a self-loop) is still rejected
+    let s = "
+S a * LN:i:100
+S b * LN:i:100
+S c * LN:i:100
+S d * LN:i:100
+L a + b + 0M
+L a + c + 0M
+L b + d + 0M
+L c + d + 0M
+L b + a + 0M
+";
+    let g = Graph::read(&s.replace(' ', "\t"));
+    assert!(superbubble::find_superbubble(&g, Vertex::forward(g.name2id("a")), &params).is_none());
+}
+
+#[test]
+fn adjacent_start_end() {
+    //start and end are connected directly and via b
+    let s = "
+S a * LN:i:100
+S b * LN:i:100
+S c * LN:i:100
+S x * LN:i:100
+L a + b + 0M
+L a + c + 0M
+L b + c + 0M
+";
+    let g = Graph::read(&s.replace(' ', "\t"));
+    let v = |name| Vertex::forward(g.name2id(name));
+    let params = superbubble::SbSearchParams::unrestricted();
+    let bubble = superbubble::find_superbubble(&g, v("a"), &params).unwrap();
+    assert_eq!(bubble.end_vertex(), v("c"));
+    assert_eq!(bubble.length_range(&g), (200, 300));
+    assert_eq!(bubble.shortest_path(&g).print(&g), "a+,c+");
+    assert_eq!(bubble.longest_path(&g).print(&g), "a+,b+,c+");
+
+    //end vertex with an incoming link from outside doesn't form a superbubble
+    let g = Graph::read(&format!("{s}L x + c + 0M\n").replace(' ', "\t"));
+    assert!(superbubble::find_superbubble(&g, v("a"), &params).is_none());
+}
+
+#[test]
+fn single_bubble_chain_range() {
+    let s = "
+S a * LN:i:100
+S b * LN:i:150
+S c * LN:i:200
+S d * LN:i:100
+L a + b + 10M
+L a + c + 20M
+L b + d + 10M
+L c + d + 30M
+";
+    let g = Graph::read(&s.replace(' ', "\t"));
+    let params = superbubble::SbSearchParams::unrestricted();
+    let chain = superbubble::find_chain_ahead(&g, Vertex::forward(g.name2id("a")), &params);
+    assert_eq!(chain.len(), 1);
+    assert_eq!(
+        superbubble::length_range(&chain, &g),
+        chain[0].length_range(&g)
+    );
+    assert_eq!(superbubble::length_range(&chain, &g), (330, 350));
+}
+
+#[test]
+fn overlap_not_shorter_than_node() {
+    let s = "
+S a * LN:i:100
+S b * LN:i:100
+S c * LN:i:100
+S d * LN:i:100
+L b + d + 10M
+L c + d + 10M
+";
+    let mut g = Graph::read(&s.replace(' ', "\t"));
+    //overlap equal to (and exceeding) the length of the downstream node
+    //(rejected by GFA parser, but can be added programmatically)
+    let a = Vertex::forward(g.name2id("a"));
+    for (name, overlap) in [("b", 100), ("c", 150)] {
+        g.add_link(Link {
+            start: a,
+            end: Vertex::forward(g.name2id(name)),
+            overlap,
+        });
+    }
+    let params = superbubble::SbSearchParams::unrestricted();
+    let bubble =
+        superbubble::find_superbubble(&g, Vertex::forward(g.name2id("a")), &params).unwrap();
+    assert_eq!(g.name(bubble.end_vertex().node_id), "d");
+    assert_eq!(bubble.length_range(&g), (190, 190));
+    assert_eq!(superbubble::find_all_outer(&g, &params).len(), 1);
+}
+
+#[test]
+fn bubble_all_paths() {
+    let s = "
+S a * LN:i:100
+S b * LN:i:100
+S c * LN:i:200
+S d * LN:i:100
+S e * LN:i:300
+S f * LN:i:100
+L a + b + 0M
+L a + c + 0M
+L b + d + 0M
+L c + d + 0M
+L d + e + 0M
+L d + f + 0M
+L b + f + 0M
+L e + f + 0M
+";
+    let g = Graph::read(&s.replace(' ', "\t"));
+    let params = superbubble::SbSearchParams::unrestricted();
+    let bubble =
+        superbubble::find_superbubble(&g, Vertex::forward(g.name2id("a")), &params).unwrap();
+    let paths = bubble.all_paths(&g, 10);
+    assert_eq!(
+        paths.iter().map(|p| p.print(&g)).collect_vec(),
+        vec![
+            "a+,b+,f+",
+            "a+,b+,d+,f+",
+            "a+,c+,d+,f+",
+            "a+,b+,d+,e+,f+",
+            "a+,c+,d+,e+,f+",
+        ]
+    );
+    assert_eq!(paths[0].print(&g), bubble.shortest_path(&g).print(&g));
+    assert_eq!(paths[4].print(&g), bubble.longest_path(&g).print(&g));
+    assert_eq!(bubble.all_paths(&g, 2).len(), 2);
+}
+
+#[test]
+fn maximal_chains_cover() {
+    //chain of two bubbles a..c..e and separate bubble x..z
+    let s = "
+S a * LN:i:100
+S b1 * LN:i:100
+S b2 * LN:i:200
+S c * LN:i:100
+S d1 * LN:i:100
+S d2 * LN:i:100
+S e * LN:i:100
+S x * LN:i:100
+S y1 * LN:i:100
+S y2 * LN:i:300
+S z * LN:i:100
+L a + b1 + 0M
+L a + b2 + 0M
+L b1 + c + 0M
+L b2 + c + 0M
+L c + d1 + 0M
+L c + d2 + 0M
+L d1 + e + 0M
+L d2 + e + 0M
+L x + y1 + 0M
+L x + y2 + 0M
+L y1 + z + 0M
+L y2 + z + 0M
+";
+    let g = Graph::read(&s.replace(' ', "\t"));
+    let params = superbubble::SbSearchParams::unrestricted();
+    let chains = superbubble::find_maximal_chains(&g, &params);
+    assert_eq!(chains.len(), 2);
+    let ranges = chains
+        .iter()
+        .map(|chain| superbubble::length_range(chain, &g))
+        .sorted()
+        .collect_vec();
+    assert_eq!(ranges, vec![(300, 500), (500, 600)]);
+    //no node belongs to more than one chain
+    let nodes = chains
+        .iter()
+        .flat_map(|chain| chain.iter().flat_map(|b| b.vertices().map(|v| v.node_id)))
+        .collect::<std::collections::HashSet<_>>();
+    let total: usize = chains
+        .iter()
+        .map(|chain| {
+            chain
+                .iter()
+                .flat_map(|b| b.vertices().map(|v| v.node_id))
+                .collect::<std::collections::HashSet<_>>()
+                .len()
+        })
+        .sum();
+    assert_eq!(nodes.len(), total);
+    assert_eq!(total, g.node_cnt());
+}
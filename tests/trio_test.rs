@@ -32,3 +32,443 @@ fn homozygous_assignment() {
         &["utig4-1237", "utig4-1552", "utig4-1826", "utig4-2589"]
     );
 }
+
+#[test]
+fn homozygous_assignment_changes() {
+    init();
+
+    let graph_fn = "tests/test_graphs/test1.gfa";
+    let assignments_fn = "tests/test_graphs/test1.no_homozygous.csv";
+    let g = graph::Graph::read(&fs::read_to_string(graph_fn).unwrap());
+    let before = trio::parse_node_assignments(&g, assignments_fn).unwrap();
+    let assigner =
+        trio::HomozygousAssigner::new(&g, before.clone(), 200_000, None, 500_000, 1.5, usize::MAX);
+
+    let after = assigner.run();
+    let changed = after.changed_nodes(&before);
+    assert!(!changed.is_empty());
+    assert_eq!(changed, before.changed_nodes(&after));
+    for node_id in changed {
+        assert_eq!(after.group(node_id), Some(TrioGroup::HOMOZYGOUS));
+        assert_ne!(before.group(node_id), Some(TrioGroup::HOMOZYGOUS));
+    }
+    assert!(after.changed_nodes(&after).is_empty());
+}
+
+#[test]
+fn missed_het_pairs() {
+    //a/b share the left flank only (right flank of b is missing), c/d form a regular bubble
+    let s = "
+S x * LN:i:1000 ll:f:30
+S a * LN:i:100000 ll:f:15
+S b * LN:i:95000 ll:f:16
+S y * LN:i:1000 ll:f:30
+S c * LN:i:100000 ll:f:15
+S d * LN:i:100000 ll:f:15
+S z * LN:i:1000 ll:f:30
+S e * LN:i:30000 ll:f:15
+L x + a + 100M
+L x + b + 100M
+L a + y + 100M
+L y + c + 100M
+L y + d + 100M
+L c + z + 100M
+L d + z + 100M
+L z + e + 100M
+";
+    let g = Graph::read(&s.replace(' ', "\t"));
+    let mut assignments = AssignmentStorage::new();
+    for (name, group) in [
+        ("a", TrioGroup::MATERNAL),
+        ("b", TrioGroup::PATERNAL),
+        ("c", TrioGroup::MATERNAL),
+        ("d", TrioGroup::PATERNAL),
+        ("e", TrioGroup::PATERNAL),
+    ] {
+        assignments.assign(g.name2id(name), group, "");
+    }
+    let settings = HetPairSettings {
+        min_len: 10_000,
+        ..HetPairSettings::default()
+    };
+    let pairs = trio::missed_het_pairs(&g, &assignments, &settings)
+        .into_iter()
+        .map(|(n1, n2)| (g.name(n1), g.name(n2)))
+        .collect_vec();
+    assert_eq!(pairs, vec![("a", "b")]);
+
+    //coverage too different
+    let g = Graph::read(
+        &s.replace(' ', "\t")
+            .replace("95000\tll:f:16", "95000\tll:f:40"),
+    );
+    assert!(trio::missed_het_pairs(&g, &assignments, &settings).is_empty());
+}
+
+#[test]
+fn propagate_assignments() {
+    init();
+    //m1 - x - m2, p1 - y - z, u is isolated, t between m1 and p1
+    let s = "
+S m1 * LN:i:100000
+S m2 * LN:i:100000
+S p1 * LN:i:100000
+S s1 * LN:i:100
+S x * LN:i:100
+S y * LN:i:100
+S z * LN:i:100
+S t * LN:i:100
+S u * LN:i:100
+L m1 + x + 0M
+L x + m2 + 0M
+L p1 + y + 0M
+L y + z + 0M
+L m1 - t + 0M
+L t + p1 - 0M
+L s1 + x - 0M
+";
+    let g = Graph::read(&s.replace(' ', "\t"));
+    let mut assignments = AssignmentStorage::new();
+    assignments.assign(g.name2id("m1"), TrioGroup::MATERNAL, "");
+    assignments.assign(g.name2id("m2"), TrioGroup::MATERNAL, "");
+    assignments.assign(g.name2id("p1"), TrioGroup::PATERNAL, "");
+    //short assigned nodes don't vote and are kept intact
+    assignments.assign(g.name2id("s1"), TrioGroup::PATERNAL, "");
+
+    let group = |a: &AssignmentStorage, name| a.group(g.name2id(name));
+    let propagated = trio::propagate_assignments(&g, assignments.clone(), 1, 10_000);
+    assert_eq!(group(&propagated, "x"), Some(TrioGroup::MATERNAL));
+    assert_eq!(group(&propagated, "y"), Some(TrioGroup::PATERNAL));
+    assert_eq!(group(&propagated, "z"), None);
+    //tie
+    assert_eq!(group(&propagated, "t"), None);
+    assert_eq!(group(&propagated, "u"), None);
+    assert_eq!(group(&propagated, "s1"), Some(TrioGroup::PATERNAL));
+    assert_eq!(
+        propagated.info(g.name2id("x")).unwrap(),
+        String::from("propagated")
+    );
+
+    let propagated = trio::propagate_assignments(&g, assignments.clone(), 2, 10_000);
+    assert_eq!(group(&propagated, "z"), Some(TrioGroup::PATERNAL));
+    assert_eq!(group(&propagated, "t"), None);
+
+    //m1, m2 vs p1
+    let propagated = trio::propagate_assignments(&g, assignments, 3, 10_000);
+    assert_eq!(group(&propagated, "t"), Some(TrioGroup::MATERNAL));
+}
+
+#[test]
+fn binomial_confidence() {
+    let close = |x: f64, y: f64| (x - y).abs() < 1e-6;
+    assert_eq!(trio::binomial_confidence(0, 0), 0.);
+    assert!(close(trio::binomial_confidence(1, 1), 0.25));
+    assert!(close(trio::binomial_confidence(10, 0), 1. - 1. / 1024.));
+    assert!(close(
+        trio::binomial_confidence(3, 7),
+        trio::binomial_confidence(7, 3)
+    ));
+    assert!(close(trio::binomial_confidence(5, 5), 1. - 638. / 1024.));
+    assert!(trio::binomial_confidence(1000, 800) > 0.99);
+    assert!(trio::binomial_confidence(1000, 1000) < 0.5);
+    assert!(close(trio::binomial_confidence(100_000, 0), 1.));
+
+    let s = "
+S a * LN:i:1000
+S b * LN:i:100000
+S c * LN:i:1000
+";
+    let g = Graph::read(&s.replace(' ', "\t"));
+    let info = |name: &str, mat, pat| TrioInfo {
+        node_name: String::from(name),
+        mat,
+        pat,
+    };
+    let assignments = assign_parental_groups(
+        &g,
+        &[info("a", 20, 1), info("b", 50, 50), info("c", 5, 0)],
+        &GroupAssignmentSettings::default(),
+        500_000,
+        f64::MAX,
+    );
+    let confidence = |name| assignments.get(g.name2id(name)).map(|a| a.confidence);
+    assert_eq!(assignments.group(g.name2id("a")), Some(TrioGroup::MATERNAL));
+    assert!(close(
+        confidence("a").unwrap() as f64,
+        trio::binomial_confidence(20, 1)
+    ));
+    assert_eq!(assignments.group(g.name2id("b")), Some(TrioGroup::ISSUE));
+    assert!(confidence("b").unwrap() < 0.5);
+    //below count threshold
+    assert!(confidence("c").is_none());
+
+    let mut storage = AssignmentStorage::new();
+    storage.assign(0, TrioGroup::HOMOZYGOUS, "");
+    assert!(storage.get(0).unwrap().confidence.is_nan());
+}
+
+#[test]
+fn trio_file_spec() {
+    let markers_fn =
+        std::env::temp_dir().join(format!("rukki_trio_file_spec_{}.tsv", std::process::id()));
+    let read = |content: &str, spec: &TrioFileSpec| {
+        fs::write(&markers_fn, content).unwrap();
+        trio::read_trio(&markers_fn, spec)
+            .map(|infos| {
+                infos
+                    .into_iter()
+                    .map(|ti| (ti.node_name, ti.mat, ti.pat))
+                    .collect_vec()
+            })
+            .map_err(|e| e.to_string())
+    };
+    let expected = vec![(String::from("a"), 10, 1), (String::from("b"), 0, 7)];
+
+    let default = TrioFileSpec::default();
+    assert_eq!(read("a\t10\t1\nb\t0\t7\n", &default).unwrap(), expected);
+    assert_eq!(
+        read("node\tmat\tpat\na\t10\t1\tx\nb\t0\t7\n", &default).unwrap(),
+        expected
+    );
+
+    let by_name = TrioFileSpec::parse("contig,hap1,hap2").unwrap();
+    assert_eq!(
+        by_name,
+        TrioFileSpec {
+            node: ColumnRef::Name(String::from("contig")),
+            mat: ColumnRef::Name(String::from("hap1")),
+            pat: ColumnRef::Name(String::from("hap2")),
+        }
+    );
+    assert_eq!(
+        read("hap2\tcontig\thap1\n1\ta\t10\n7\tb\t0\n", &by_name).unwrap(),
+        expected
+    );
+    assert!(read("a\t10\t1\n", &by_name)
+        .unwrap_err()
+        .contains("line 1: column Name(\"contig\") not found"));
+
+    let by_index = TrioFileSpec::parse("3,1,2").unwrap();
+    assert_eq!(read("10\t1\ta\n0\t7\tb\n", &by_index).unwrap(), expected);
+
+    assert!(read("a\t10\t1\nb\tx\t7\n", &default)
+        .unwrap_err()
+        .contains("line 2: invalid marker count 'x'"));
+    assert!(read("node\tmat\tpat\na\t10\n", &default)
+        .unwrap_err()
+        .contains("line 2: missing column 3"));
+
+    assert!(TrioFileSpec::parse("1,2").is_none());
+    assert!(TrioFileSpec::parse("0,1,2").is_none());
+    assert!(TrioFileSpec::parse("a,,b").is_none());
+    fs::remove_file(&markers_fn).unwrap();
+}
+
+#[test]
+fn marker_density() {
+    //a is short with few markers, b is long with many (sparse) markers
+    let s = "
+S a * LN:i:1000
+S b * LN:i:100000
+";
+    let g = Graph::read(&s.replace(' ', "\t"));
+    let info = |name: &str, mat, pat| TrioInfo {
+        node_name: String::from(name),
+        mat,
+        pat,
+    };
+    let infos = [info("a", 5, 0), info("b", 20, 0)];
+    let group = |settings: &GroupAssignmentSettings, name| {
+        assign_parental_groups(&g, &infos, settings, 500_000, f64::MAX).group(g.name2id(name))
+    };
+
+    let by_cnt = GroupAssignmentSettings::default();
+    assert_eq!(group(&by_cnt, "a"), None);
+    assert_eq!(group(&by_cnt, "b"), Some(TrioGroup::MATERNAL));
+
+    let by_density = GroupAssignmentSettings {
+        assign_density: Some(1.),
+        ..GroupAssignmentSettings::default()
+    };
+    assert_eq!(group(&by_density, "a"), Some(TrioGroup::MATERNAL));
+    assert_eq!(group(&by_density, "b"), None);
+    let assignments = assign_parental_groups(&g, &infos, &by_density, 500_000, f64::MAX);
+    assert_eq!(assignments.info(g.name2id("a")).unwrap(), "m5:p0:5.00/kb");
+}
+
+#[test]
+fn group_compatibility() {
+    use TrioGroup::*;
+    let groups = [MATERNAL, PATERNAL, HOMOZYGOUS, ISSUE];
+    let expected_incompatible = |g1, g2| {
+        matches!(
+            (g1, g2),
+            (ISSUE, _) | (_, ISSUE) | (MATERNAL, PATERNAL) | (PATERNAL, MATERNAL)
+        )
+    };
+    for g1 in groups {
+        for g2 in groups {
+            assert_eq!(
+                TrioGroup::incompatible(g1, g2),
+                expected_incompatible(g1, g2),
+                "{g1:?} {g2:?}"
+            );
+            assert_eq!(
+                TrioGroup::incompatible(g1, g2),
+                TrioGroup::incompatible(g2, g1)
+            );
+            assert_ne!(
+                TrioGroup::compatible(g1, g2),
+                TrioGroup::incompatible(g1, g2)
+            );
+        }
+    }
+}
+
+#[test]
+fn group_blend() {
+    use TrioGroup::*;
+    let groups = [MATERNAL, PATERNAL, HOMOZYGOUS];
+    for g1 in groups {
+        for g2 in groups {
+            let expected = if g1 == g2 { g1 } else { HOMOZYGOUS };
+            assert_eq!(TrioGroup::blend(g1, g2), expected, "{g1:?} {g2:?}");
+            assert_eq!(TrioGroup::blend(g2, g1), expected);
+            assert_eq!(
+                TrioGroup::optional_blend(Some(g1), Some(g2)),
+                Some(expected)
+            );
+        }
+        assert_eq!(TrioGroup::optional_blend(Some(g1), None), Some(g1));
+        assert_eq!(TrioGroup::optional_blend(None, Some(g1)), Some(g1));
+    }
+    assert_eq!(TrioGroup::optional_blend(None, None), None);
+    for g in groups.into_iter().chain(std::iter::once(ISSUE)) {
+        assert!(std::panic::catch_unwind(|| TrioGroup::blend(g, ISSUE)).is_err());
+        assert!(std::panic::catch_unwind(|| TrioGroup::blend(ISSUE, g)).is_err());
+    }
+}
+
+#[test]
+fn group_parsing() {
+    use TrioGroup::*;
+    for g in [MATERNAL, PATERNAL, HOMOZYGOUS, ISSUE] {
+        assert_eq!(format!("{g:?}").parse::<TrioGroup>(), Ok(g));
+    }
+    assert!("maternal".parse::<TrioGroup>().is_err());
+    assert!("".parse::<TrioGroup>().is_err());
+}
+
+#[test]
+fn homozygous_by_coverage() {
+    init();
+    let s = "
+S m1 * LN:i:1000 ll:f:10
+S p1 * LN:i:1000 ll:f:10
+S m2 * LN:i:300 ll:f:20
+S h * LN:i:500 ll:f:20
+S l * LN:i:500 ll:f:12
+S big * LN:i:5000 ll:f:25
+S nocov * LN:i:500
+";
+    let g = graph::Graph::read(&s.replace(' ', "\t"));
+    let mut assignments = AssignmentStorage::new();
+    assignments.assign(g.name2id("m1"), TrioGroup::MATERNAL, "");
+    assignments.assign(g.name2id("p1"), TrioGroup::PATERNAL, "");
+    assignments.assign(g.name2id("m2"), TrioGroup::MATERNAL, "");
+
+    let hap_cov = trio::definite_mean_cov(&g, &assignments);
+    assert!((hap_cov - 26000. / 2300.).abs() < 1e-6);
+
+    let assigner = HomozygousAssigner::new(&g, assignments.clone(), 400, None, 2000, 0., 2000);
+    let unchanged = assigner.run();
+    assert!((0..g.node_cnt()).all(|node_id| unchanged.group(node_id) == assignments.group(node_id)));
+
+    let mut assigner = HomozygousAssigner::new(&g, assignments, 400, None, 2000, 0., 2000);
+    assigner.set_min_doubled_cov(1.75 * hap_cov);
+    let assignments = assigner.run();
+    let group = |name| assignments.group(g.name2id(name));
+    assert_eq!(group("m1"), Some(TrioGroup::MATERNAL));
+    assert_eq!(group("p1"), Some(TrioGroup::PATERNAL));
+    assert_eq!(group("m2"), Some(TrioGroup::HOMOZYGOUS));
+    assert_eq!(group("h"), Some(TrioGroup::HOMOZYGOUS));
+    assert_eq!(group("l"), None);
+    assert_eq!(group("big"), None);
+    assert_eq!(group("nocov"), None);
+}
+
+#[test]
+fn gzipped_markers() {
+    use flate2::write::GzEncoder;
+    use std::io::Write;
+
+    let content = "node\tmat\tpat\na\t10\t1\nb\t0\t7\n";
+    let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(content.as_bytes()).unwrap();
+    let bytes = encoder.finish().unwrap();
+
+    let tmp = std::env::temp_dir();
+    let pid = std::process::id();
+    //second file is misnamed and has to be recognized by magic bytes
+    for markers_fn in [
+        tmp.join(format!("rukki_gz_markers_{pid}.tsv.gz")),
+        tmp.join(format!("rukki_gz_markers_{pid}.tsv")),
+    ] {
+        fs::write(&markers_fn, &bytes).unwrap();
+        let infos = trio::read_trio(&markers_fn, &TrioFileSpec::default()).unwrap();
+        assert_eq!(
+            infos
+                .into_iter()
+                .map(|ti| (ti.node_name, ti.mat, ti.pat))
+                .collect_vec(),
+            vec![(String::from("a"), 10, 1), (String::from("b"), 0, 7)]
+        );
+        fs::remove_file(&markers_fn).unwrap();
+    }
+}
+
+#[test]
+fn assignment_overrides() {
+    let s = "
+S a * LN:i:100
+S b * LN:i:200
+S c * LN:i:300
+";
+    let g = Graph::read(&s.replace(' ', "\t"));
+    let overrides_fn =
+        std::env::temp_dir().join(format!("rukki_overrides_{}.tsv", std::process::id()));
+    fs::write(
+        &overrides_fn,
+        "node\tgroup\n# comment\na\tmaternal\nx\tPATERNAL\n\nb\tISSUE\n",
+    )
+    .unwrap();
+    let overrides = trio::read_assignment_overrides(&g, &overrides_fn).unwrap();
+    assert_eq!(
+        overrides,
+        vec![
+            (g.name2id("a"), TrioGroup::MATERNAL),
+            (g.name2id("b"), TrioGroup::ISSUE)
+        ]
+    );
+
+    let mut assignments = AssignmentStorage::new();
+    assignments.assign(g.name2id("a"), TrioGroup::PATERNAL, "Markers");
+    assignments.assign(g.name2id("b"), TrioGroup::ISSUE, "Markers");
+    assignments.assign(g.name2id("c"), TrioGroup::PATERNAL, "Markers");
+    assert_eq!(trio::apply_overrides(&mut assignments, &overrides), 1);
+    assert_eq!(assignments.group(g.name2id("a")), Some(TrioGroup::MATERNAL));
+    assert!(assignments.is_definite(g.name2id("a")));
+    assert_eq!(
+        assignments.info(g.name2id("a")).unwrap(),
+        trio::OVERRIDE_INFO
+    );
+    assert_eq!(
+        assignments.info(g.name2id("b")).unwrap(),
+        trio::OVERRIDE_INFO
+    );
+    assert_eq!(assignments.info(g.name2id("c")).unwrap(), "Markers");
+
+    fs::write(&overrides_fn, "a\tmaternal\nb\tunknown\n").unwrap();
+    assert!(trio::read_assignment_overrides(&g, &overrides_fn).is_err());
+    fs::remove_file(&overrides_fn).unwrap();
+}
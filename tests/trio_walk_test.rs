@@ -2,7 +2,7 @@ extern crate log;
 use itertools::Itertools;
 
 use rukki::trio::*;
-use rukki::trio_walk::{HaploSearchSettings, HaploSearcher};
+use rukki::trio_walk::{HaploSearchSettings, HaploSearcher, JumpRejection};
 use rukki::*;
 use std::fs;
 
@@ -61,6 +61,127 @@ fn haplo_paths() {
             String::from("utig4-1830-,utig4-1826-,utig4-1827+,utig4-1831+,utig4-1243-,utig4-1241-,utig4-1237-,utig4-1238+,utig4-1552+,utig4-1553+,utig4-4096-,utig4-4097+,utig4-2592-,utig4-2589-,utig4-2591+"))])
 }
 
+#[test]
+fn path_trace() {
+    init();
+
+    let graph_fn = "tests/test_graphs/test1.gfa";
+    let assignments_fn = "tests/test_graphs/test1.ann.csv";
+    let g = graph::Graph::read(&fs::read_to_string(graph_fn).unwrap());
+    let assignments = trio::parse_node_assignments(&g, assignments_fn).unwrap();
+    let settings = trio_walk::HaploSearchSettings::default();
+    let assignments = augment_by_path_search(&g, assignments, settings);
+
+    let seeds = build_searcher(settings, &g, &assignments)
+        .find_all()
+        .into_iter()
+        .map(|(_, node_id, _)| node_id)
+        .collect_vec();
+    let seed = seeds[0];
+
+    let mut haplo_searcher = build_searcher(settings, &g, &assignments);
+    haplo_searcher.set_trace_node(seed);
+    haplo_searcher.find_all();
+    let trace = haplo_searcher.take_trace();
+    assert!(trace.len() > 2);
+    assert!(trace[0].starts_with(&format!("{}+\t", g.name(seed))));
+    //growth in both directions ends with a stop
+    assert_eq!(
+        trace
+            .iter()
+            .filter(|l| l.split('\t').nth(3) == Some("stop"))
+            .count(),
+        2
+    );
+    assert!(haplo_searcher.take_trace().is_empty());
+
+    //not a seed
+    let mut haplo_searcher = build_searcher(settings, &g, &assignments);
+    let non_seed = (0..g.node_cnt()).find(|n| !seeds.contains(n)).unwrap();
+    haplo_searcher.set_trace_node(non_seed);
+    haplo_searcher.find_all();
+    assert!(haplo_searcher.take_trace().is_empty());
+}
+
+#[test]
+fn dense_used_storage() {
+    init();
+
+    let graph_fn = "tests/test_graphs/test1.gfa";
+    let assignments_fn = "tests/test_graphs/test1.ann.csv";
+    let g = graph::Graph::read(&fs::read_to_string(graph_fn).unwrap());
+    let assignments = trio::parse_node_assignments(&g, assignments_fn).unwrap();
+    let settings = trio_walk::HaploSearchSettings::default();
+    let assignments = augment_by_path_search(&g, assignments, settings);
+
+    let run = |settings| {
+        let mut haplo_searcher = build_searcher(settings, &g, &assignments);
+        let paths = haplo_searcher
+            .find_all()
+            .into_iter()
+            .map(|(p, _, group)| (group, p.print(&g)))
+            .collect_vec();
+        let used = haplo_searcher.take_used();
+        let mut used = used
+            .assigned()
+            .map(|node_id| {
+                (
+                    node_id,
+                    used.group(node_id),
+                    used.info(node_id).unwrap().to_string(),
+                )
+            })
+            .collect_vec();
+        used.sort();
+        (paths, used)
+    };
+    let sparse = run(settings);
+    let dense = run(HaploSearchSettings {
+        dense_used_node_cnt: 0,
+        ..settings
+    });
+    assert!(!sparse.1.is_empty());
+    assert_eq!(sparse, dense);
+}
+
+#[test]
+fn init_node_len() {
+    init();
+
+    let graph_fn = "tests/test_graphs/test1.gfa";
+    let assignments_fn = "tests/test_graphs/test1.ann.csv";
+    let g = graph::Graph::read(&fs::read_to_string(graph_fn).unwrap());
+    let assignments = trio::parse_node_assignments(&g, assignments_fn).unwrap();
+    let settings = trio_walk::HaploSearchSettings::default();
+    let assignments = augment_by_path_search(&g, assignments, settings);
+
+    let run = |settings| {
+        let mut haplo_searcher = build_searcher(settings, &g, &assignments);
+        let mut paths = haplo_searcher
+            .find_all()
+            .into_iter()
+            .map(|(p, _, group)| (group, p.print(&g)))
+            .collect_vec();
+        paths.sort();
+        paths
+    };
+    let default = run(settings);
+    assert_eq!(default.len(), 2);
+    //no seeds, no paths
+    assert!(run(HaploSearchSettings {
+        init_node_len: usize::MAX,
+        ..settings
+    })
+    .is_empty());
+    //seeding from shorter nodes doesn't affect the long node threshold used in the search
+    let short_seeds = run(HaploSearchSettings {
+        init_node_len: 100_000,
+        ..settings
+    });
+    assert!(short_seeds.len() >= default.len());
+    assert!(default.iter().all(|p| short_seeds.contains(p)));
+}
+
 #[test]
 fn augment_by_search() {
     init();
@@ -231,4 +352,309 @@ fn haplo_paths_3() {
             String::from("utig4-4093-,utig4-3587-,utig4-3588+,utig4-4041-,utig4-3592+,utig4-1535-,utig4-1533-,utig4-1529-,utig4-1531+,utig4-1892-,utig4-925-,utig4-923+,utig4-926+,utig4-1595+,utig4-1597+,utig4-1896+,utig4-1619-,utig4-1617+,utig4-65-,utig4-64+,utig4-67+,[N5000N:ambig_path],utig4-1477-,utig4-1476+,utig4-1251-,utig4-1249+,utig4-1252+,utig4-1254+,utig4-3626+,utig4-3631+,utig4-1027-,utig4-1025-,utig4-1022-,utig4-1019-,utig4-1020+,utig4-1387+,utig4-1389+,utig4-1392+,utig4-1393+,utig4-1450+,utig4-1451+,utig4-1795+,utig4-1406-,utig4-1402-,utig4-1403+,utig4-3448-,utig4-1409+,utig4-3446-,[N14098N:alt-utig4-3445],utig4-3456-")),
         (TrioGroup::PATERNAL,
             String::from("utig4-3455-,utig4-3445-,utig4-3447+,utig4-1410-,utig4-1408-,utig4-1404-,utig4-1402+,utig4-1405+,utig4-1795-,utig4-1452-,utig4-1450-,utig4-1394-,utig4-1392-,utig4-1388-,utig4-1387-,utig4-1021-,utig4-1019+,utig4-1023+,utig4-1024+,utig4-1026+,utig4-3630-,utig4-3626-,utig4-3627+,utig4-1257-,utig4-1253-,utig4-1249-,utig4-1251+,utig4-1476-,utig4-1478+,utig4-3650-,utig4-68-,utig4-64-,utig4-66+,utig4-1617-,utig4-1618+,utig4-1896-,utig4-1596-,utig4-1595-,utig4-927-,utig4-923-,utig4-924+,utig4-1892+,utig4-1530-,utig4-1529+,utig4-1532+,utig4-1534+,utig4-3593-,utig4-3591-,utig4-3589-,[N34594N:alt-utig4-3587],utig4-3384+"))]);
+
+    //gap size is estimated by the shortest walk between the path ends if requested
+    let estimating_settings = trio_walk::HaploSearchSettings {
+        estimate_ambig_gaps: true,
+        ..settings
+    };
+    let mut haplo_searcher = build_searcher(estimating_settings, &g, &augment_assign);
+    assert!(haplo_searcher.find_all().iter().any(|(p, _, _)| p
+        .print(&g)
+        .contains("utig4-67+,[N47999N:ambig_path],utig4-1477-")));
+}
+
+#[test]
+fn redundant_paths() {
+    let s = "
+S a * LN:i:100
+S b * LN:i:100
+S c * LN:i:100
+L a + b + 10M
+L b + c + 10M
+";
+    let g = Graph::read(&s.replace(' ', "\t"));
+    let v = |name| Vertex::forward(g.name2id(name));
+    let mut abc = Path::from_link(g.connector(v("a"), v("b")).unwrap());
+    abc.append(g.connector(v("b"), v("c")).unwrap());
+    let bc = Path::from_link(g.connector(v("b"), v("c")).unwrap());
+    let cb = bc.clone().reverse_complement();
+    let paths = vec![
+        (abc.clone(), g.name2id("a"), TrioGroup::MATERNAL),
+        //contained
+        (bc.clone(), g.name2id("b"), TrioGroup::MATERNAL),
+        //contained, but in other haplotype
+        (bc, g.name2id("b"), TrioGroup::PATERNAL),
+        //same nodes as the previous one, only second copy is redundant
+        (cb, g.name2id("c"), TrioGroup::PATERNAL),
+        (abc, g.name2id("a"), TrioGroup::PATERNAL),
+    ];
+    assert_eq!(trio_walk::redundant_paths(&paths), vec![1, 2, 3]);
+}
+
+#[test]
+fn deterministic_ties() {
+    init();
+
+    //both connectors lack coverage, the one with smaller node id is chosen
+    let s = "
+S a * LN:i:600000
+S c1 * LN:i:1000
+S c2 * LN:i:1000
+S b * LN:i:600000
+S x * LN:i:600000
+L a + c2 + 0M
+L a + c1 + 0M
+L c1 + b + 0M
+L c2 + b + 0M
+";
+    let g = Graph::read(&s.replace(' ', "\t"));
+    let mut assignments = AssignmentStorage::new();
+    for name in ["a", "b", "x"] {
+        assignments.assign(g.name2id(name), TrioGroup::MATERNAL, "");
+    }
+    let settings = HaploSearchSettings::default();
+    for _ in 0..3 {
+        let mut haplo_searcher = build_searcher(settings, &g, &assignments);
+        let paths = haplo_searcher
+            .find_all()
+            .into_iter()
+            .map(|(p, node_id, _)| (p.print(&g), node_id))
+            .collect_vec();
+        assert_eq!(
+            paths,
+            vec![
+                (String::from("a+,c1+,b+"), g.name2id("a")),
+                (String::from("x+"), g.name2id("x"))
+            ]
+        );
+    }
+
+    //random tie-breaking is reproducible given the seed
+    let first_path = |seed: u64| {
+        let settings = HaploSearchSettings {
+            random_ties: true,
+            seed,
+            ..HaploSearchSettings::default()
+        };
+        build_searcher(settings, &g, &assignments).find_all()[0]
+            .0
+            .print(&g)
+    };
+    let chosen = (0..16).map(first_path).collect_vec();
+    assert_eq!(chosen, (0..16).map(first_path).collect_vec());
+    assert!(chosen.iter().all(|p| p == "a+,c1+,b+" || p == "a+,c2+,b+"));
+    assert!(chosen.iter().any(|p| p == "a+,c2+,b+"));
+}
+
+#[test]
+fn used_by_name() {
+    init();
+
+    let s = "
+S a * LN:i:600000
+S c * LN:i:1000
+S b * LN:i:600000
+L a + c + 0M
+L c + b + 0M
+";
+    let g = Graph::read(&s.replace(' ', "\t"));
+    let mut assignments = AssignmentStorage::new();
+    assignments.assign(g.name2id("a"), TrioGroup::MATERNAL, "");
+    assignments.assign(g.name2id("b"), TrioGroup::MATERNAL, "");
+    let mut haplo_searcher = build_searcher(HaploSearchSettings::default(), &g, &assignments);
+    assert_eq!(haplo_searcher.find_all().len(), 1);
+    let used = haplo_searcher.used_by_name();
+    assert_eq!(used.len(), 3);
+    assert!(used.values().all(|&group| group == TrioGroup::MATERNAL));
+    assert_eq!(used["c"], TrioGroup::MATERNAL);
+}
+
+#[test]
+fn max_path_length() {
+    init();
+
+    let s = "
+S a * LN:i:600000
+S c * LN:i:1000
+S b * LN:i:600000
+S d * LN:i:1000
+S e * LN:i:600000
+L a + c + 0M
+L c + b + 0M
+L b + d + 0M
+L d + e + 0M
+";
+    let g = Graph::read(&s.replace(' ', "\t"));
+    let mut assignments = AssignmentStorage::new();
+    for name in ["a", "b", "e"] {
+        assignments.assign(g.name2id(name), TrioGroup::MATERNAL, "");
+    }
+    let run = |settings| {
+        let mut haplo_searcher = build_searcher(settings, &g, &assignments);
+        haplo_searcher
+            .find_all()
+            .into_iter()
+            .map(|(p, _, _)| p.print(&g))
+            .collect_vec()
+    };
+    let settings = HaploSearchSettings::default();
+    assert_eq!(run(settings), vec!["a+,c+,b+,d+,e+"]);
+    let limited = run(HaploSearchSettings {
+        max_path_length: 1_000_000,
+        ..settings
+    });
+    assert_eq!(limited[0], "a+,c+,b+");
+    assert!(limited.len() > 1);
+}
+
+#[test]
+fn trio_summary() {
+    init();
+
+    let s = "
+S a * LN:i:600000
+S c * LN:i:1000
+S b * LN:i:600000
+S x * LN:i:700000
+S y * LN:i:500
+L a + c + 0M
+L c + b + 0M
+";
+    let g = Graph::read(&s.replace(' ', "\t"));
+    let mut assignments = AssignmentStorage::new();
+    assignments.assign(g.name2id("a"), TrioGroup::MATERNAL, "");
+    assignments.assign(g.name2id("b"), TrioGroup::MATERNAL, "");
+    assignments.assign(g.name2id("x"), TrioGroup::PATERNAL, "");
+    assignments.assign(g.name2id("y"), TrioGroup::ISSUE, "");
+    let mut haplo_searcher = build_searcher(HaploSearchSettings::default(), &g, &assignments);
+    let haplo_paths = haplo_searcher.find_all();
+    let summary = TrioSummary::compute(&g, &assignments, &haplo_paths, haplo_searcher.used(), None);
+    assert_eq!((summary.maternal_nodes, summary.paternal_nodes), (2, 1));
+    assert_eq!((summary.homozygous_nodes, summary.issue_nodes), (0, 1));
+    assert_eq!((summary.maternal_paths, summary.paternal_paths), (1, 1));
+    assert_eq!(summary.placed_length, 1_901_000);
+    assert_eq!(summary.unused_length, 500);
+    assert_eq!(summary.path_n50, 1_201_000);
+    assert_eq!(
+        (summary.maternal_path_n50, summary.paternal_path_n50),
+        (1_201_000, 700_000)
+    );
+    assert_eq!(summary.maternal_path_ng50, None);
+
+    //paternal haplotype doesn't reach half of the genome size
+    let summary = TrioSummary::compute(
+        &g,
+        &assignments,
+        &haplo_paths,
+        haplo_searcher.used(),
+        Some(2_000_000),
+    );
+    assert_eq!(summary.maternal_path_ng50, Some(1_201_000));
+    assert_eq!(summary.paternal_path_ng50, None);
+}
+
+#[test]
+fn jump_rejection_counts() {
+    init();
+
+    let s = "
+S a * LN:i:600000
+S c * LN:i:1000
+S b * LN:i:600000
+S e * LN:i:600000
+L a + c + 0M
+L c + b + 0M
+L c + e + 0M
+";
+    let g = Graph::read(&s.replace(' ', "\t"));
+    let mut assignments = AssignmentStorage::new();
+    for name in ["a", "b"] {
+        assignments.assign(g.name2id(name), TrioGroup::MATERNAL, "");
+    }
+    //e is long, but unassigned
+    let mut haplo_searcher = build_searcher(HaploSearchSettings::default(), &g, &assignments);
+    haplo_searcher.find_all();
+    let rejections = haplo_searcher.jump_rejections();
+    assert!(rejections.count(JumpRejection::NotAllDefinite) > 0);
+    assert_eq!(rejections.count(JumpRejection::MultipleCandidates), 0);
+
+    //both b and e are compatible
+    assignments.assign(g.name2id("e"), TrioGroup::MATERNAL, "");
+    let mut haplo_searcher = build_searcher(HaploSearchSettings::default(), &g, &assignments);
+    haplo_searcher.find_all();
+    let rejections = haplo_searcher.jump_rejections();
+    assert_eq!(rejections.count(JumpRejection::NotAllDefinite), 0);
+    assert!(rejections.count(JumpRejection::MultipleCandidates) > 0);
+}
+
+#[test]
+fn lazy_find_all() {
+    init();
+
+    let s = "
+S a * LN:i:700000
+S c * LN:i:1000
+S b * LN:i:600000
+S x * LN:i:650000
+S y * LN:i:1000
+S z * LN:i:600000
+L a + c + 0M
+L c + b + 0M
+L x + y + 0M
+L y + z + 0M
+";
+    let g = Graph::read(&s.replace(' ', "\t"));
+    let mut assignments = AssignmentStorage::new();
+    for name in ["a", "b"] {
+        assignments.assign(g.name2id(name), TrioGroup::MATERNAL, "");
+    }
+    for name in ["x", "z"] {
+        assignments.assign(g.name2id(name), TrioGroup::PATERNAL, "");
+    }
+    let all = build_searcher(HaploSearchSettings::default(), &g, &assignments).find_all();
+    assert_eq!(all.len(), 2);
+
+    let mut haplo_searcher = build_searcher(HaploSearchSettings::default(), &g, &assignments);
+    let first = haplo_searcher.find_all_iter().next().unwrap();
+    assert_eq!(first, all[0]);
+    assert_eq!(first.0.print(&g), "a+,c+,b+");
+    //only the nodes of the first path are used so far
+    assert_eq!(haplo_searcher.used().assigned().count(), 3);
+    assert!(!haplo_searcher.used().contains(g.name2id("x")));
+
+    let mut haplo_searcher = build_searcher(HaploSearchSettings::default(), &g, &assignments);
+    assert_eq!(haplo_searcher.find_all_iter().collect_vec(), all);
+}
+
+#[test]
+fn extension_within_scc() {
+    init();
+
+    //a, r, b and s form a single strongly connected component
+    let s = "
+S a * LN:i:700000
+S r * LN:i:1000
+S b * LN:i:600000
+S s * LN:i:2000
+L a + r + 0M
+L r + b + 0M
+L b + s + 0M
+L s + a + 0M
+";
+    let g = Graph::read(&s.replace(' ', "\t"));
+    assert_eq!(graph_algos::scc::nontrivial_sccs(&g).len(), 1);
+    let mut assignments = AssignmentStorage::new();
+    for name in ["a", "b"] {
+        assignments.assign(g.name2id(name), TrioGroup::MATERNAL, "");
+    }
+    let settings = HaploSearchSettings {
+        skippable_tangle_size: 0,
+        ..HaploSearchSettings::default()
+    };
+    let paths = build_searcher(settings, &g, &assignments).find_all();
+    //SCC membership doesn't prevent extension through the short nodes with unique links
+    //(extension stops before reusing a)
+    assert_eq!(paths.len(), 1);
+    assert_eq!(paths[0].0.print(&g), "a+,r+,b+,s+");
 }